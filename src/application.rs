@@ -3,26 +3,153 @@
 //! main structs App and TestState
 
 use crate::config;
+use crossterm::cursor::CursorShape;
 use crossterm::event::KeyEvent;
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use crate::handlers::{self, KeyHandler};
-use crate::painters::{draw_post, draw_settings, draw_test_and_update, Painter};
-use crate::settings::Settings;
+use crate::colorscheme::{Theme, ThemeDraft};
+use crate::handlers::{self, KeyHandler, Ticker};
+use crate::keys::KeyBindings;
+use crate::langs;
+use crate::painters::{
+    self, draw_history, draw_post, draw_scrolling_test, draw_settings, draw_submit_test, draw_test,
+    draw_theme_editor, Painter,
+};
+use crate::settings::{Settings, TypingTestConfig};
 use crate::typer::TestState;
-use crate::Term;
+use tui::{backend::Backend, Terminal};
 
 pub const APPLOGO: &str = " _._ _  _ |  _    
 _>| | |(_)|<(/_\\/ 
                /  ";
 
+/// test setups Shift + Tab cycles back through
+const RECENT_TESTS: usize = 5;
+/// how long the main loop waits for an event before ticking anyway
+pub const POLL_TIMEOUT: Duration = Duration::from_millis(250);
+/// mistakes within this long after a bell don't ring another one
+const BELL_COOLDOWN: Duration = Duration::from_millis(300);
+/// columns left free next to the wrapped lines
+const RESIZE_PADDING: u16 = 4;
+/// lines don't get narrower than this however small the terminal is
+const MIN_LINE_WIDTH: usize = 10;
+
+/// Where finished tests get reported as json lines
+pub enum JsonOutput {
+    /// printed after the terminal is restored so the tui doesn't get garbled
+    Stdout,
+    /// appended to the file as soon as each test finishes
+    File(PathBuf),
+}
+
+/// How the test text is laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TestLayout {
+    /// a few wrapped lines moving up as they're typed
+    #[default]
+    Lines,
+    /// a single line scrolling under the cursor
+    Scroll,
+    /// each line is typed out whole and checked on Enter
+    Submit,
+}
+
+impl TestLayout {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "lines" => Some(Self::Lines),
+            "scroll" => Some(Self::Scroll),
+            "submit" => Some(Self::Submit),
+            _ => None,
+        }
+    }
+}
+
+/// How the caret in the test is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CursorStyle {
+    #[default]
+    Block,
+    Underline,
+    Bar,
+    /// no terminal cursor, the next letter is drawn reversed in its own color
+    Highlight,
+}
+
+impl CursorStyle {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "block" => Some(Self::Block),
+            "underline" => Some(Self::Underline),
+            "bar" => Some(Self::Bar),
+            "highlight" => Some(Self::Highlight),
+            _ => None,
+        }
+    }
+
+    /// shape of the terminal cursor, highlight doesn't use one
+    pub fn shape(&self) -> Option<CursorShape> {
+        match self {
+            Self::Block => Some(CursorShape::Block),
+            Self::Underline => Some(CursorShape::UnderScore),
+            Self::Bar => Some(CursorShape::Line),
+            Self::Highlight => None,
+        }
+    }
+}
+
 pub struct App<'t> {
     pub settings: Settings,
     pub test: TestState<'t>,
     pub margin: u16,
     pub paragraph: u16,
     pub key_handler: KeyHandler,
+    pub ticker: Ticker,
     pub painter: Painter,
     pub is_alive: bool,
+    /// keys for quitting, restarting and going to the settings
+    pub keys: KeyBindings,
+    /// typed text further behind the cursor is dimmed
+    pub fade_typed: bool,
+    /// text still to be typed is dimmed so the typed part stands out
+    pub dim_todo: bool,
+    /// mistakes are painted like correct letters until the post screen
+    pub blind: bool,
+    /// finished tests aren't saved, they don't count towards the history or averages
+    pub warmup: bool,
+    /// the finished test made it into the history
+    pub run_saved: bool,
+    /// how long the results screen stays up before the next test starts by itself
+    pub auto_advance: Option<Duration>,
+    /// when the results screen moves on to the next test
+    pub advance_at: Option<Instant>,
+    /// wpm to beat, drawn as a line on the results graph
+    pub target_wpm: Option<f64>,
+    pub layout: TestLayout,
+    pub cursor: CursorStyle,
+    /// the main loop waits this long for an event before ticking
+    pub poll_timeout: Duration,
+    /// something changed since the last paint
+    pub dirty: bool,
+    pub json_output: Option<JsonOutput>,
+    /// json lines waiting to be printed to stdout
+    pub json_reports: Vec<String>,
+    /// the terminal bell rings after the next paint
+    pub bell_pending: bool,
+    pub last_bell: Option<Instant>,
+    /// leaving the started test waits for a second press,
+    /// holds whether the test was already paused before asking
+    pub confirm_leave: Option<bool>,
+    /// a timed test takes keys only after this
+    pub countdown_until: Option<Instant>,
+    /// share codes of the recently committed tests, the current one is at the front
+    pub recent_tests: VecDeque<String>,
+    /// theme being changed in the theme editor
+    pub theme_draft: Option<ThemeDraft>,
 }
 
 impl App<'_> {
@@ -35,9 +162,21 @@ impl App<'_> {
         app
     }
 
-    /// Paints to the screen using current painter
-    pub fn paint(&mut self, terminal: &mut Term) {
-        (self.painter)(terminal, self)
+    /// Paints to the screen using current painter,
+    /// works on any backend so it can also paint into a test buffer
+    /// ```
+    /// use smokey::application::App;
+    /// use tui::{backend::TestBackend, Terminal};
+    ///
+    /// let mut app = App::setup();
+    /// let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+    /// app.paint(&mut terminal);
+    /// assert!(!app.dirty);
+    /// ```
+    pub fn paint<B: Backend>(&mut self, terminal: &mut Terminal<B>) {
+        self.dirty = false;
+        let painter = self.painter;
+        painters::paint(terminal, self, painter)
     }
 
     /// Performs an action based on KeyEvent
@@ -55,46 +194,340 @@ impl App<'_> {
     /// assert!(app.is_alive);
     ///
     /// // Esc should go back to the settings
+    /// // the test is started so it asks first
+    /// app.handle_key_event(KeyEvent::from(KeyCode::Esc));
+    /// assert!(app.test.is_paused());
     /// app.handle_key_event(KeyEvent::from(KeyCode::Esc));
     /// // now q char is handled differently -> (quit app)
     /// app.handle_key_event(KeyEvent::from(KeyCode::Char('q')));
     /// assert!(!app.is_alive);
     /// ```
     pub fn handle_key_event(&mut self, key_event: KeyEvent) {
+        self.dirty = true;
         (self.key_handler)(key_event, self)
     }
 
+    /// Runs key events through the current key handler
+    /// ticking after each one just like the main loop does
+    /// but without a terminal, handy for driving the app in tests
+    /// ```
+    /// use crossterm::event::{KeyCode, KeyEvent};
+    /// use smokey::application::App;
+    /// let mut app = App::setup();
+    ///
+    /// // types the first word
+    /// let keys: Vec<KeyEvent> = app.test.active
+    ///     .iter()
+    ///     .take_while(|span| !span.content.is_empty())
+    ///     .filter_map(|span| span.content.chars().next())
+    ///     .map(|c| KeyEvent::from(KeyCode::Char(c)))
+    ///     .collect();
+    /// app.feed_keys(&keys);
+    ///
+    /// assert_eq!(app.test.current_char, ' ');
+    /// assert_eq!(app.test.mistakes, 0);
+    /// assert_eq!(app.test.summarize().acc, 100.);
+    /// ```
+    pub fn feed_keys(&mut self, keys: &[KeyEvent]) {
+        for key in keys {
+            self.handle_key_event(*key);
+            self.tick();
+        }
+    }
+
+    /// Performs periodic actions using current ticker
+    /// e.g. samples wpm and ends timed test when the time is up
+    pub fn tick(&mut self) {
+        (self.ticker)(self)
+    }
+
     pub fn change_to_post(&mut self) {
         self.painter = draw_post;
         self.key_handler = handlers::post::handle;
+        self.advance_at = self.auto_advance.map(|dwell| Instant::now() + dwell);
+        self.ticker = match self.advance_at {
+            Some(_) => handlers::post::tick,
+            None => handlers::idle,
+        };
+    }
+
+    /// Whole seconds left until the next test starts by itself
+    pub fn advance_left(&self) -> Option<u64> {
+        let left = self
+            .advance_at?
+            .saturating_duration_since(Instant::now())
+            .as_millis() as u64;
+        Some(left.div_ceil(1000))
     }
 
     pub fn change_to_settings(&mut self) {
         self.painter = draw_settings;
         self.key_handler = handlers::settings::handle;
+        self.ticker = handlers::idle;
     }
 
+    pub fn change_to_history(&mut self) {
+        self.settings.load_history();
+        self.painter = draw_history;
+        self.key_handler = handlers::history::handle;
+        self.ticker = handlers::idle;
+    }
+
+    /// Opens the theme editor on the theme highlighted in the themes list
+    /// or on the current theme when the list isn't selected
+    pub fn change_to_theme_editor(&mut self) {
+        let draft = match self.settings.get_previewed_theme_name() {
+            Some(name) => ThemeDraft::new(name, self.settings.get_previewed_theme()),
+            None => ThemeDraft::new("custom", self.settings.theme),
+        };
+        self.theme_draft = Some(draft);
+        self.painter = draw_theme_editor;
+        self.key_handler = handlers::theme_editor::handle;
+        self.ticker = handlers::idle;
+    }
+
+    /// Writes the edited theme to its theme file and switches to it
+    pub fn save_theme_draft(&mut self) {
+        let draft = match &self.theme_draft {
+            Some(draft) => draft,
+            None => return,
+        };
+
+        if let Err(e) = draft.save() {
+            warn!("couldn't save theme {:?}: {}", draft.name, e);
+            return;
+        }
+        self.settings.theme = draft.theme;
+        self.settings.colors = draft.theme.to_settings_colors();
+        self.settings.reload_themes();
+    }
+
+    /// Goes to the test, timed tests with a countdown count down first
     pub fn change_to_test(&mut self) {
-        self.painter = draw_test_and_update;
-        self.key_handler = handlers::typer::handle;
+        self.painter = match self.layout {
+            TestLayout::Lines => draw_test,
+            TestLayout::Scroll => draw_scrolling_test,
+            TestLayout::Submit => draw_submit_test,
+        };
+
+        let countdown = match self.test.time_limit {
+            Some(_) if self.test.first => self.settings.test_cfg.countdown,
+            _ => None,
+        };
+        match countdown {
+            Some(secs) => {
+                self.countdown_until = Some(Instant::now() + Duration::from_secs(secs));
+                self.key_handler = handlers::countdown::handle;
+                self.ticker = handlers::countdown::tick;
+            }
+            None => self.start_typing(),
+        }
+    }
+
+    /// Lets the keys through to the test
+    pub fn start_typing(&mut self) {
+        self.countdown_until = None;
+        self.key_handler = match self.layout {
+            TestLayout::Submit => handlers::typer::handle_submit,
+            _ => handlers::typer::handle,
+        };
+        self.ticker = handlers::typer::tick;
+    }
+
+    /// Whole seconds left of the countdown, rounded up so it ends on 1
+    pub fn countdown_left(&self) -> Option<u64> {
+        let left = self
+            .countdown_until?
+            .saturating_duration_since(Instant::now());
+        Some(left.as_millis().div_ceil(1000) as u64)
+    }
+
+    /// Rewraps the test to fit terminal of the given width
+    pub fn resize(&mut self, width: u16) {
+        self.dirty = true;
+        // room for the cursor and extra mistakes at the end of a line
+        let available = width.saturating_sub(RESIZE_PADDING) as usize;
+        let line_width = match available < langs::LIMIT {
+            true => Some(available.max(MIN_LINE_WIDTH)),
+            false => None,
+        };
+        self.test.set_line_width(line_width);
+    }
+
+    /// Reports the finished test as a json line if json output was requested
+    pub fn report_result(&mut self) {
+        let output = match &self.json_output {
+            Some(output) => output,
+            None => return,
+        };
+
+        let line = serde_json::to_string(&self.settings.test_cfg).expect("summary serializes");
+        match output {
+            JsonOutput::Stdout => self.json_reports.push(line),
+            JsonOutput::File(path) => {
+                if let Err(e) = append_line(path, &line) {
+                    warn!("couldn't write results to {:?}: {}", path, e);
+                }
+            }
+        }
+    }
+
+    /// Asks for the terminal bell, bells asked for within the cooldown are dropped
+    pub fn ring_bell(&mut self) {
+        if let Some(at) = self.last_bell {
+            if at.elapsed() < BELL_COOLDOWN {
+                return;
+            }
+        }
+        self.last_bell = Some(Instant::now());
+        self.bell_pending = true;
+    }
+
+    /// Whether the bell should ring now, it's cleared afterwards
+    pub fn take_bell(&mut self) -> bool {
+        std::mem::take(&mut self.bell_pending)
+    }
+
+    /// Goes to the settings, a started test gets paused and asks first
+    /// so a stray press doesn't throw it away, the next call leaves for real
+    pub fn leave_test(&mut self) {
+        if self.test.done == 0 || self.confirm_leave.take().is_some() {
+            return self.change_to_settings();
+        }
+
+        let was_paused = self.test.is_paused();
+        if !was_paused {
+            self.test.toggle_pause();
+        }
+        self.confirm_leave = Some(was_paused);
+    }
+
+    /// Dismisses the leave prompt and gets back to typing
+    pub fn stay_in_test(&mut self) {
+        if let Some(false) = self.confirm_leave.take() {
+            self.test.toggle_pause();
+        }
     }
 
     pub fn stop(&mut self) {
         self.is_alive = false;
     }
 
+    /// Resets the test, the test picks up the latest theme
     pub fn reset_test(&mut self) {
-        self.test.cursor_x = 1;
+        self.test.colors = self.settings.theme.to_test_colors();
         self.test.reset(&self.settings.test_cfg);
     }
 
+    /// Starts the test over with the same text, the failed run isn't saved
+    pub fn restart_test(&mut self) {
+        self.test.restart(&self.settings.test_cfg);
+    }
+
+    /// Short code of the current test setup that can be shared
+    pub fn export_config_code(&self) -> String {
+        config::encode_config(&self.settings.test_cfg)
+    }
+
+    /// Switches to the test setup from a shared code,
+    /// the code only carries the test so personal preferences are kept
+    /// ```
+    /// use smokey::application::App;
+    ///
+    /// let mut app = App::setup();
+    /// app.settings.test_cfg.length = 37;
+    /// let code = app.export_config_code();
+    ///
+    /// let mut friend = App::setup();
+    /// friend.import_config_code(&code).unwrap();
+    /// assert_eq!(friend.settings.test_cfg.length, 37);
+    /// assert!(friend.import_config_code("garbage").is_err());
+    /// ```
+    pub fn import_config_code(&mut self, code: &str) -> anyhow::Result<()> {
+        let shared = config::decode_config(code)?;
+        self.settings.apply_shared_test(shared);
+        self.reset_test();
+        Ok(())
+    }
+
+    /// Puts the current test setup at the front of the recent ones
+    pub fn remember_test(&mut self) {
+        let current = self.export_config_code();
+        if self.recent_tests.front() == Some(&current) {
+            return;
+        }
+        self.recent_tests.retain(|recent| *recent != current);
+        self.recent_tests.push_front(current);
+        self.recent_tests.truncate(RECENT_TESTS);
+    }
+
+    /// Switches to the test setup used before the current one,
+    /// going on cycles through all the recent ones
+    pub fn previous_test(&mut self) {
+        self.remember_test();
+        while self.recent_tests.len() > 1 {
+            self.recent_tests.rotate_left(1);
+            let code = self.recent_tests[0].clone();
+            // made here so scripts are fine
+            match config::decode_own_config(&code) {
+                Ok(shared) => {
+                    self.settings.apply_shared_test(shared);
+                    self.reset_test();
+                    return;
+                }
+                // the word list could be gone since
+                Err(e) => {
+                    warn!("{:#}", e);
+                    self.recent_tests.pop_front();
+                }
+            }
+        }
+    }
+
+    /// Restarts the last test with the very same text
+    pub fn replay_test(&mut self) {
+        self.test.replay(&self.settings.test_cfg);
+    }
+
     pub fn from_config() -> Self {
-        let final_config = config::get_final_config();
-        let test = TestState::with_colors(final_config.theme.to_test_colors());
-        let settings = Settings::with_config(
-            final_config.theme.to_settings_colors(),
-            final_config.typing_test_config,
-        );
+        Self::from_final_config(config::get_final_config())
+    }
+
+    /// App using provided config instead of reading smokey.toml
+    pub fn from_final_config(final_config: config::FinalConfig) -> Self {
+        let mut app = Self {
+            keys: final_config.keys,
+            fade_typed: final_config.fade_typed,
+            dim_todo: final_config.dim_todo,
+            blind: final_config.blind,
+            layout: final_config.layout,
+            cursor: final_config.cursor,
+            auto_advance: final_config.auto_advance.map(Duration::from_secs),
+            target_wpm: final_config.target_wpm,
+            ..Self::with_config(final_config.theme, final_config.typing_test_config)
+        };
+        // the app starts on the test, drawn the way the layout asks for
+        app.change_to_test();
+        app
+    }
+
+    /// App starting with the given test and theme
+    /// ```
+    /// use smokey::application::App;
+    /// use smokey::colorscheme::Theme;
+    /// use smokey::settings::TypingTestConfig;
+    ///
+    /// let test_cfg = TypingTestConfig {
+    ///     length: 10,
+    ///     ..Default::default()
+    /// };
+    /// let mut app = App::with_config(Theme::default(), test_cfg);
+    /// app.reset_test();
+    /// assert_eq!(app.settings.test_cfg.length, 10);
+    /// ```
+    pub fn with_config(theme: Theme, test_cfg: TypingTestConfig) -> Self {
+        let test = TestState::with_colors(theme.to_test_colors());
+        let settings = Settings::with_config(theme, test_cfg);
 
         Self {
             settings,
@@ -110,14 +543,41 @@ impl Default for App<'_> {
     fn default() -> Self {
         Self {
             is_alive: true,
+            keys: KeyBindings::default(),
+            fade_typed: false,
+            dim_todo: false,
+            blind: false,
+            layout: TestLayout::default(),
+            cursor: CursorStyle::default(),
+            poll_timeout: POLL_TIMEOUT,
+            dirty: true,
             margin: 2,
             paragraph: 62,
 
-            painter: draw_test_and_update,
+            painter: draw_test,
             key_handler: handlers::typer::handle,
+            ticker: handlers::typer::tick,
 
             settings: Settings::default(),
             test: TestState::default(),
+            json_output: None,
+            json_reports: vec![],
+            bell_pending: false,
+            last_bell: None,
+            confirm_leave: None,
+            countdown_until: None,
+            recent_tests: VecDeque::new(),
+            theme_draft: None,
+            warmup: false,
+            run_saved: false,
+            auto_advance: None,
+            target_wpm: None,
+            advance_at: None,
         }
     }
 }
+
+fn append_line(path: &Path, line: &str) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)
+}
@@ -1,5 +1,12 @@
+use crate::config::UserTheme;
 use crate::settings::SettingsColors;
+use crate::storage;
 use crate::typer::TestColors;
+use anyhow::{anyhow, bail};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 use tui::style::{Color, Style};
 
 pub trait ToForeground {
@@ -20,6 +27,8 @@ pub struct Theme {
     pub todo: Color,
     pub hover: Color,
     pub active: Color,
+    /// wrong letters piling up past the end of a word
+    pub extra: Color,
 }
 
 impl Default for Theme {
@@ -30,11 +39,83 @@ impl Default for Theme {
             todo: Color::Gray,
             hover: Color::Magenta,
             active: Color::Green,
+            extra: Color::LightRed,
         }
     }
 }
 
 impl Theme {
+    /// Done and mistakes in blue and orange instead of red and green
+    /// friendly to red-green colorblindness
+    pub fn deuteranopia() -> Self {
+        Theme {
+            done: Color::Rgb(86, 180, 233),
+            mistake: Color::Rgb(230, 159, 0),
+            todo: Color::Gray,
+            hover: Color::Rgb(240, 228, 66),
+            active: Color::Rgb(0, 114, 178),
+            extra: Color::Rgb(213, 94, 0),
+        }
+    }
+
+    /// Bright on dark, done and mistakes differ in brightness not just hue
+    pub fn high_contrast() -> Self {
+        Theme {
+            done: Color::White,
+            mistake: Color::LightYellow,
+            todo: Color::DarkGray,
+            hover: Color::LightCyan,
+            active: Color::White,
+            extra: Color::Yellow,
+        }
+    }
+
+    /// Themes that don't need a theme file, the default one comes first
+    pub fn builtin() -> Vec<(&'static str, Self)> {
+        vec![
+            ("default", Theme::default()),
+            ("deuteranopia", Theme::deuteranopia()),
+            ("high-contrast", Theme::high_contrast()),
+        ]
+    }
+
+    /// Built-in theme of the given name or the theme file of that name
+    pub fn named(name: &str) -> Self {
+        match Theme::builtin().into_iter().find(|(n, _)| *n == name) {
+            Some((_, theme)) => theme,
+            None => Theme::from_file(storage::get_theme_path(name)),
+        }
+    }
+
+    /// Reads a theme file which has the same fields as [colors] in smokey.toml
+    /// falls back to the default theme if the file can't be read or parsed
+    pub fn from_file(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let user_theme = fs::read_to_string(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|s| toml::from_str::<UserTheme>(&s).map_err(anyhow::Error::from));
+
+        match user_theme {
+            Ok(user_theme) => user_theme.into_theme(),
+            Err(e) => {
+                warn!("couldn't load theme {:?}: {}", path, e);
+                Theme::default()
+            }
+        }
+    }
+
+    /// Colors in the order of THEME_FIELDS
+    pub fn fields(self) -> [Color; 6] {
+        [
+            self.done,
+            self.mistake,
+            self.todo,
+            self.hover,
+            self.active,
+            self.extra,
+        ]
+    }
+
     pub fn to_settings_colors(self) -> SettingsColors {
         SettingsColors {
             hover: self.hover,
@@ -46,6 +127,365 @@ impl Theme {
             todo: self.todo,
             done: self.done,
             wrong: self.mistake,
+            extra: self.extra,
+        }
+    }
+}
+
+/// Theme fields in the order the theme editor lists them
+pub const THEME_FIELDS: [&str; 6] = ["done", "mistake", "todo", "hover", "active", "extra"];
+
+/// How far one press moves an rgb channel in the theme editor
+pub const NUDGE_STEP: i16 = 8;
+
+/// Named colors in the order the theme editor cycles through them,
+/// along with roughly how they look in rgb
+const NAMED_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// A channel of an rgb color
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+}
+
+/// Moves one channel of an rgb color by delta, stopping at 0 and 255
+/// named colors are turned into rgb first
+/// ```
+/// use smokey::colorscheme::{nudge_color, Channel};
+/// use tui::style::Color;
+///
+/// assert_eq!(nudge_color(Color::Rgb(250, 0, 0), Channel::Red, 8), Color::Rgb(255, 0, 0));
+/// assert_eq!(nudge_color(Color::Rgb(0, 4, 0), Channel::Green, -8), Color::Rgb(0, 0, 0));
+/// ```
+pub fn nudge_color(color: Color, channel: Channel, delta: i16) -> Color {
+    let (mut r, mut g, mut b) = to_rgb(color);
+    let value = match channel {
+        Channel::Red => &mut r,
+        Channel::Green => &mut g,
+        Channel::Blue => &mut b,
+    };
+    *value = (*value as i16 + delta).clamp(0, u8::MAX as i16) as u8;
+    Color::Rgb(r, g, b)
+}
+
+/// The named color step places further along, wrapping around,
+/// colors that aren't named start from black
+pub fn cycle_named_color(color: Color, step: isize) -> Color {
+    let len = NAMED_COLORS.len() as isize;
+    let i = match NAMED_COLORS.iter().position(|(named, _)| *named == color) {
+        Some(i) => (i as isize + step).rem_euclid(len),
+        None => 0,
+    };
+    NAMED_COLORS[i as usize].0
+}
+
+/// Channels of the color, named colors are approximated,
+/// colors that can't be told get the middle gray
+fn to_rgb(color: Color) -> (u8, u8, u8) {
+    match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        named => NAMED_COLORS
+            .iter()
+            .find(|(c, _)| *c == named)
+            .map_or((128, 128, 128), |(_, rgb)| *rgb),
+    }
+}
+
+/// Writes the theme to a theme file, the directories on the way get created
+pub fn save_theme(theme: Theme, path: &Path) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, toml::to_string(&UserTheme::from(theme))?)?;
+    Ok(())
+}
+
+/// Working copy of a theme that the theme editor changes
+#[derive(Debug, Clone)]
+pub struct ThemeDraft {
+    /// theme file name the draft gets saved under
+    pub name: String,
+    pub theme: Theme,
+    /// index into THEME_FIELDS
+    pub field: usize,
+    /// channel that left and right nudge, None cycles through named colors
+    pub channel: Option<Channel>,
+}
+
+impl ThemeDraft {
+    /// Built-in themes can't be overwritten so their drafts get a name of their own
+    pub fn new(name: &str, theme: Theme) -> Self {
+        let builtin = Theme::builtin().iter().any(|(n, _)| *n == name);
+        Self {
+            name: match builtin {
+                true => format!("{}-custom", name),
+                false => name.to_string(),
+            },
+            theme,
+            field: 0,
+            channel: None,
         }
     }
+
+    /// Color of the selected field
+    pub fn color(&self) -> Color {
+        self.theme.fields()[self.field]
+    }
+
+    fn color_mut(&mut self) -> &mut Color {
+        let theme = &mut self.theme;
+        match self.field {
+            0 => &mut theme.done,
+            1 => &mut theme.mistake,
+            2 => &mut theme.todo,
+            3 => &mut theme.hover,
+            4 => &mut theme.active,
+            _ => &mut theme.extra,
+        }
+    }
+
+    pub fn next_field(&mut self) {
+        self.field = (self.field + 1) % THEME_FIELDS.len();
+    }
+
+    pub fn previous_field(&mut self) {
+        self.field = (self.field + THEME_FIELDS.len() - 1) % THEME_FIELDS.len();
+    }
+
+    /// Left and right go through named colors or nudge the channel from now on
+    pub fn pick_channel(&mut self, channel: Option<Channel>) {
+        self.channel = channel;
+    }
+
+    /// Moves the selected color by step named colors or nudge steps
+    pub fn shift(&mut self, step: i16) {
+        let channel = self.channel;
+        let color = self.color_mut();
+        *color = match channel {
+            Some(channel) => nudge_color(*color, channel, step * NUDGE_STEP),
+            None => cycle_named_color(*color, step as isize),
+        };
+    }
+
+    /// Saves the draft to the themes directory
+    pub fn save(&self) -> anyhow::Result<PathBuf> {
+        let path = storage::get_theme_path(&self.name);
+        save_theme(self.theme, &path)?;
+        Ok(path)
+    }
+}
+
+/// Parses "#rrggbb", "rgb(r, g, b)" or a name of a standard color
+/// ignoring surrounding whitespace and case
+/// ```
+/// use smokey::colorscheme::parse_color;
+/// use tui::style::Color;
+///
+/// assert_eq!(parse_color("#ff8800").unwrap(), Color::Rgb(255, 136, 0));
+/// assert_eq!(parse_color("rgb(255, 136, 0)").unwrap(), Color::Rgb(255, 136, 0));
+/// assert_eq!(parse_color("LightBlue").unwrap(), Color::LightBlue);
+/// assert!(parse_color("#ff88").is_err());
+/// ```
+pub fn parse_color(color: &str) -> anyhow::Result<Color> {
+    let color = color.trim().to_lowercase();
+
+    if let Some(hex) = color.strip_prefix('#') {
+        return hex_to_color(hex);
+    }
+
+    if let Some(channels) = color.strip_prefix("rgb(").and_then(|c| c.strip_suffix(')')) {
+        return rgb_to_color(channels);
+    }
+
+    str_to_color(&color).ok_or_else(|| anyhow!("{:?} isn't a known color name", color))
+}
+
+fn hex_to_color(hex: &str) -> anyhow::Result<Color> {
+    if hex.len() != 6 {
+        bail!(
+            "hex color must have exactly 6 digits, #{} has {}",
+            hex,
+            hex.len()
+        );
+    }
+
+    if let Some(c) = hex.chars().find(|c| !c.is_ascii_hexdigit()) {
+        bail!("{:?} in #{} isn't a hex digit", c, hex);
+    }
+
+    let r: u8 = u8::from_str_radix(&hex[0..2], 16)?;
+    let g: u8 = u8::from_str_radix(&hex[2..4], 16)?;
+    let b: u8 = u8::from_str_radix(&hex[4..6], 16)?;
+    Ok(Color::Rgb(r, g, b))
+}
+
+fn rgb_to_color(channels: &str) -> anyhow::Result<Color> {
+    let channels: Vec<&str> = channels.split(',').map(|c| c.trim()).collect();
+    if channels.len() != 3 {
+        bail!("rgb color needs 3 channels, got {}", channels.len());
+    }
+
+    let mut rgb = [0_u8; 3];
+    for (value, channel) in rgb.iter_mut().zip(channels) {
+        *value = channel
+            .parse()
+            .map_err(|_| anyhow!("{:?} isn't a channel value between 0 and 255", channel))?;
+    }
+
+    Ok(Color::Rgb(rgb[0], rgb[1], rgb[2]))
+}
+
+fn str_to_color(color: &str) -> Option<Color> {
+    match color {
+        "black" => Some(Color::Black),
+        "red" => Some(Color::Red),
+        "green" => Some(Color::Green),
+        "yellow" => Some(Color::Yellow),
+        "blue" => Some(Color::Blue),
+        "magenta" => Some(Color::Magenta),
+        "cyan" => Some(Color::Cyan),
+        "gray" => Some(Color::Gray),
+        "darkgray" => Some(Color::DarkGray),
+        "lightred" => Some(Color::LightRed),
+        "lightgreen" => Some(Color::LightGreen),
+        "lightyellow" => Some(Color::LightYellow),
+        "lightblue" => Some(Color::LightBlue),
+        "lightmagenta" => Some(Color::LightMagenta),
+        "lightcyan" => Some(Color::LightCyan),
+        "white" => Some(Color::White),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_themes() {
+        for (name, theme) in Theme::builtin() {
+            assert_ne!(
+                theme.done, theme.mistake,
+                "{} mixes up done and mistakes",
+                name
+            );
+            assert_ne!(theme.todo, theme.done, "{} mixes up todo and done", name);
+            assert_eq!(Theme::named(name), theme);
+        }
+
+        // red and green are what colorblind users can't tell apart
+        let deuteranopia = Theme::deuteranopia();
+        for color in [deuteranopia.done, deuteranopia.mistake] {
+            assert!(
+                ![Color::Red, Color::Green, Color::LightRed, Color::LightGreen].contains(&color)
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_hex_color() {
+        assert_eq!(parse_color("#000000").unwrap(), Color::Rgb(0, 0, 0));
+        assert_eq!(parse_color("#FFFFFF").unwrap(), Color::Rgb(255, 255, 255));
+        assert_eq!(parse_color("#ffffff").unwrap(), Color::Rgb(255, 255, 255));
+        assert_eq!(parse_color("#Ff8800").unwrap(), Color::Rgb(255, 136, 0));
+    }
+
+    #[test]
+    fn test_parse_rgb_color() {
+        assert_eq!(
+            parse_color("rgb(255,136,0)").unwrap(),
+            Color::Rgb(255, 136, 0)
+        );
+        assert_eq!(
+            parse_color(" RGB( 1, 2, 3 ) ").unwrap(),
+            Color::Rgb(1, 2, 3)
+        );
+        assert!(parse_color("rgb(256, 0, 0)").is_err());
+        assert!(parse_color("rgb(1, 2)").is_err());
+    }
+
+    #[test]
+    fn test_parse_bad_color() {
+        let too_short = parse_color("#fff").unwrap_err();
+        assert!(too_short.to_string().contains("6 digits"));
+
+        let too_long = parse_color("#ff880000").unwrap_err();
+        assert!(too_long.to_string().contains("6 digits"));
+
+        let not_hex = parse_color("#gg8800").unwrap_err();
+        assert!(not_hex.to_string().contains("isn't a hex digit"));
+
+        assert!(parse_color("rouge").is_err());
+        assert!(parse_color("").is_err());
+    }
+
+    #[test]
+    fn test_nudge_color() {
+        let color = Color::Rgb(100, 250, 3);
+        assert_eq!(nudge_color(color, Channel::Red, 8), Color::Rgb(108, 250, 3));
+        assert_eq!(
+            nudge_color(color, Channel::Green, 8),
+            Color::Rgb(100, 255, 3)
+        );
+        assert_eq!(
+            nudge_color(color, Channel::Blue, -8),
+            Color::Rgb(100, 250, 0)
+        );
+        // named colors start from their rgb look
+        assert_eq!(
+            nudge_color(Color::White, Channel::Blue, -55),
+            Color::Rgb(255, 255, 200)
+        );
+
+        assert_eq!(cycle_named_color(Color::Black, 1), Color::Red);
+        assert_eq!(cycle_named_color(Color::Black, -1), Color::White);
+        assert_eq!(cycle_named_color(Color::White, 1), Color::Black);
+        assert_eq!(cycle_named_color(Color::Rgb(1, 2, 3), 1), Color::Black);
+    }
+
+    #[test]
+    fn test_theme_draft() {
+        let mut draft = ThemeDraft::new("default", Theme::default());
+        assert_eq!(draft.name, "default-custom");
+        assert_eq!(ThemeDraft::new("dusk", Theme::default()).name, "dusk");
+
+        draft.previous_field();
+        draft.previous_field();
+        assert_eq!(THEME_FIELDS[draft.field], "active");
+        draft.shift(1);
+        assert_eq!(draft.theme.active, Color::Yellow);
+
+        draft.pick_channel(Some(Channel::Red));
+        draft.shift(-1);
+        assert_eq!(draft.theme.active, Color::Rgb(197, 205, 0));
+        assert_eq!(draft.color(), draft.theme.active);
+        // the rest stays untouched
+        assert_eq!(draft.theme.done, Theme::default().done);
+
+        let dir = std::env::temp_dir().join("smokey_test_save_theme");
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("themes").join("draft.toml");
+        save_theme(draft.theme, &path).unwrap();
+        assert_eq!(Theme::from_file(&path), draft.theme);
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
@@ -0,0 +1,58 @@
+use crate::keys::{Action, KeyBind, KeyBindings};
+
+#[derive(serde_derive::Deserialize, Debug)]
+pub struct UserKeys {
+    quit: Option<String>,
+    restart: Option<String>,
+    settings: Option<String>,
+}
+
+impl UserKeys {
+    /// consumes UserKeys and returns KeyBindings
+    /// keys that don't parse keep their defaults
+    pub fn into_key_bindings(self) -> KeyBindings {
+        let mut keys = KeyBindings::default();
+        let user_keys = [
+            (Action::Quit, self.quit),
+            (Action::Restart, self.restart),
+            (Action::Settings, self.settings),
+        ];
+
+        for (action, raw) in user_keys {
+            let raw = match raw {
+                Some(raw) => raw,
+                None => continue,
+            };
+            match KeyBind::parse(&raw) {
+                Some(bind) => keys.set(action, bind),
+                None => warn!(
+                    "couldn't parse key {:?} for {:?}, using the default",
+                    raw, action
+                ),
+            }
+        }
+
+        keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    #[test]
+    fn test_user_keys() {
+        let user_keys: UserKeys =
+            toml::from_str("quit = \"ctrl+q\"\nrestart = \"nope+r\"").unwrap();
+        let keys = user_keys.into_key_bindings();
+        let defaults = KeyBindings::default();
+
+        assert_eq!(
+            keys.quit,
+            KeyBind::new(KeyCode::Char('q'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(keys.restart, defaults.restart);
+        assert_eq!(keys.settings, defaults.settings);
+    }
+}
@@ -0,0 +1,203 @@
+//! The test picked last time is saved to the storage on quit
+//! so the next run starts where the previous one left off
+
+use crate::settings::{
+    decode_test_mod_bitflags, encode_test_mod_bitflags, is_drill, is_quote, is_script, TestVariant,
+    TypingTestConfig, DRILL_SIGN, QUOTE_SIGN, SCRIPT_SIGN,
+};
+use crate::storage;
+use serde_derive::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Debug)]
+struct LastTest {
+    /// name as it appears in the test list, scripts and quotes keep their sign
+    name: String,
+    length: usize,
+    pool: usize,
+    time: Option<u64>,
+    /// BITFLAG_MODS encoded
+    mods: u8,
+    /// files saved before this was kept are plain tests
+    #[serde(default)]
+    endless: bool,
+}
+
+impl From<&TypingTestConfig> for LastTest {
+    fn from(ttc: &TypingTestConfig) -> Self {
+        Self {
+            name: ttc.list_name(),
+            length: ttc.length,
+            pool: ttc.word_pool,
+            time: ttc.time_limit,
+            mods: encode_test_mod_bitflags(&ttc.mods),
+            endless: ttc.endless,
+        }
+    }
+}
+
+impl LastTest {
+    fn into_typing_test_config(self) -> TypingTestConfig {
+        let (variant, name) = if is_script(&self.name) {
+            (
+                TestVariant::Script,
+                self.name[SCRIPT_SIGN.len()..].to_string(),
+            )
+        } else if is_quote(&self.name) {
+            (
+                TestVariant::Quote,
+                self.name[QUOTE_SIGN.len()..].to_string(),
+            )
+        } else if is_drill(&self.name) {
+            (
+                TestVariant::Drill,
+                self.name[DRILL_SIGN.len()..].to_string(),
+            )
+        } else {
+            (TestVariant::Standard, self.name)
+        };
+
+        TypingTestConfig {
+            name,
+            variant,
+            length: self.length.max(1),
+            word_pool: self.pool.max(1),
+            time_limit: self.time.filter(|&t| t > 0),
+            mods: decode_test_mod_bitflags(self.mods),
+            endless: self.endless,
+            ..TypingTestConfig::default()
+        }
+    }
+}
+
+/// Saves the test config so it can be picked up on the next start
+pub fn save_last_test(ttc: &TypingTestConfig) {
+    if let Err(e) = save_to(&storage::get_last_test_file(), ttc) {
+        warn!("couldn't save the last test: {}", e);
+    }
+}
+
+/// Test config saved on the last quit,
+/// None if there is none or it's corrupted or its word file is gone
+pub fn load_last_test() -> Option<TypingTestConfig> {
+    load_from(&storage::get_last_test_file())
+}
+
+fn save_to(path: &Path, ttc: &TypingTestConfig) -> anyhow::Result<()> {
+    fs::write(path, toml::to_string(&LastTest::from(ttc))?)?;
+    Ok(())
+}
+
+fn load_from(path: &Path) -> Option<TypingTestConfig> {
+    let last_test: LastTest = toml::from_str(&fs::read_to_string(path).ok()?).ok()?;
+    let ttc = last_test.into_typing_test_config();
+    ttc.is_available().then_some(ttc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::langs;
+    use crate::settings::TestMod;
+
+    #[test]
+    fn test_last_test_round_trip() {
+        let dir = std::env::temp_dir().join("smokey_test_last_test_round_trip");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config");
+
+        // missing file
+        assert!(load_from(&path).is_none());
+
+        let mut ttc = TypingTestConfig {
+            length: 50,
+            word_pool: 1000,
+            ..TypingTestConfig::default()
+        };
+        ttc.mods.insert(TestMod::Punctuation);
+        ttc.mods.insert(TestMod::Numbers);
+
+        save_to(&path, &ttc).unwrap();
+        let loaded = load_from(&path).unwrap();
+        assert_eq!(loaded.name, ttc.name);
+        assert_eq!(loaded.length, 50);
+        assert_eq!(loaded.word_pool, 1000);
+        assert_eq!(loaded.time_limit, None);
+        assert_eq!(loaded.mods, ttc.mods);
+        assert!(!loaded.endless);
+
+        let endless = TypingTestConfig {
+            endless: true,
+            ..TypingTestConfig::default()
+        };
+        save_to(&path, &endless).unwrap();
+        assert!(load_from(&path).unwrap().endless);
+
+        // saved before endless was kept
+        fs::write(
+            &path,
+            "name = \"english\"\nlength = 30\npool = 100\nmods = 0\n",
+        )
+        .unwrap();
+        let old = load_from(&path).unwrap();
+        assert_eq!(old.length, 30);
+        assert!(!old.endless);
+
+        // corrupted file
+        fs::write(&path, "name = [oops").unwrap();
+        assert!(load_from(&path).is_none());
+
+        // word file that's gone
+        ttc.name = "surely_no_such_word_file".to_string();
+        save_to(&path, &ttc).unwrap();
+        assert!(load_from(&path).is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_last_test_keeps_sign() {
+        let ttc = TypingTestConfig {
+            name: "gibberish".to_string(),
+            variant: TestVariant::Script,
+            ..TypingTestConfig::default()
+        };
+        let last_test = LastTest::from(&ttc);
+        assert_eq!(last_test.name, "#!gibberish");
+
+        let loaded = last_test.into_typing_test_config();
+        assert_eq!(loaded.name, "gibberish");
+        assert!(matches!(loaded.variant, TestVariant::Script));
+
+        let (drill, _) = langs::DRILLS[0];
+        let ttc = TypingTestConfig {
+            name: drill.to_string(),
+            variant: TestVariant::Drill,
+            ..TypingTestConfig::default()
+        };
+        let last_test = LastTest::from(&ttc);
+        assert_eq!(last_test.name, format!("{}{}", DRILL_SIGN, drill));
+
+        let loaded = last_test.into_typing_test_config();
+        assert_eq!(loaded.name, drill);
+        assert!(matches!(loaded.variant, TestVariant::Drill));
+
+        // drills load without a file behind them, unknown ones don't
+        let dir = std::env::temp_dir().join("smokey_test_last_test_keeps_sign");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config");
+        save_to(&path, &ttc).unwrap();
+        assert!(matches!(
+            load_from(&path).unwrap().variant,
+            TestVariant::Drill
+        ));
+        let unknown = TypingTestConfig {
+            name: "surely_no_such_drill".to_string(),
+            ..ttc
+        };
+        save_to(&path, &unknown).unwrap();
+        assert!(load_from(&path).is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,12 +1,25 @@
+mod keys_parser;
+mod last_test;
+mod share_code;
 mod test_parser;
 mod theme_parser;
 
-use crate::{colorscheme::Theme, settings::TypingTestConfig, storage};
+use crate::{
+    application::{CursorStyle, TestLayout},
+    colorscheme::Theme,
+    keys::KeyBindings,
+    settings::TypingTestConfig,
+    storage,
+};
 use serde_derive::Deserialize;
 use std::fs;
 
+use keys_parser::UserKeys;
+pub use last_test::{load_last_test, save_last_test};
+pub use share_code::{decode_config, decode_own_config, encode_config};
+pub use test_parser::try_parse_mods;
 use test_parser::UserTest;
-use theme_parser::UserTheme;
+pub(crate) use theme_parser::UserTheme;
 
 /// Default Config overwritten partially or completely
 /// by user preferences expressed in smokey.toml
@@ -14,31 +27,92 @@ use theme_parser::UserTheme;
 pub struct FinalConfig {
     pub theme: Theme,
     pub typing_test_config: TypingTestConfig,
+    pub keys: KeyBindings,
+    pub fade_typed: bool,
+    pub dim_todo: bool,
+    pub blind: bool,
+    pub layout: TestLayout,
+    pub cursor: CursorStyle,
+    pub auto_advance: Option<u64>,
+    pub target_wpm: Option<f64>,
 }
 
 #[derive(Deserialize, Debug)]
 struct UserConfig {
+    /// name of a theme file in the themes storage directory
+    theme: Option<String>,
     colors: Option<UserTheme>,
     test: Option<UserTest>,
+    keys: Option<UserKeys>,
+    /// typed text dims once the cursor is a few letters past it
+    fade_typed: Option<bool>,
+    /// text still to be typed is dimmed
+    dim_todo: Option<bool>,
+    /// mistakes look like any typed letter until the results
+    blind: Option<bool>,
+    /// "lines" or "scroll"
+    layout: Option<String>,
+    /// "block", "underline", "bar" or "highlight"
+    cursor: Option<String>,
+    /// seconds on the results screen before the next test starts
+    auto_advance: Option<u64>,
+    /// wpm drawn as a flat line on the results graph
+    target_wpm: Option<f64>,
 }
 
 impl UserConfig {
     // Consumes user_config and returns FinalConfig
     // that will be used during app runtime
     fn into_final_config(self) -> FinalConfig {
-        let final_theme = match self.colors {
-            Some(user_theme) => user_theme.into_theme(),
+        let base_theme = match self.theme {
+            Some(name) => Theme::named(&name),
             None => Theme::default(),
         };
 
+        // [colors] still can tweak the chosen theme
+        let final_theme = match self.colors {
+            Some(user_theme) => user_theme.apply_to(base_theme),
+            None => base_theme,
+        };
+
+        // [test] is the default, otherwise the app starts with the last test
         let final_ttc = match self.test {
             Some(user_test) => user_test.into_typing_test_config(),
-            None => TypingTestConfig::default(),
+            None => load_last_test().unwrap_or_default(),
+        };
+
+        let final_keys = match self.keys {
+            Some(user_keys) => user_keys.into_key_bindings(),
+            None => KeyBindings::default(),
+        };
+
+        let final_layout = match self.layout {
+            Some(name) => TestLayout::from_name(&name).unwrap_or_else(|| {
+                warn!("unknown layout {:?}, using lines", name);
+                TestLayout::default()
+            }),
+            None => TestLayout::default(),
+        };
+
+        let final_cursor = match self.cursor {
+            Some(name) => CursorStyle::from_name(&name).unwrap_or_else(|| {
+                warn!("unknown cursor {:?}, using block", name);
+                CursorStyle::default()
+            }),
+            None => CursorStyle::default(),
         };
 
         FinalConfig {
             theme: final_theme,
+            cursor: final_cursor,
+            layout: final_layout,
             typing_test_config: final_ttc,
+            keys: final_keys,
+            fade_typed: self.fade_typed.unwrap_or_default(),
+            dim_todo: self.dim_todo.unwrap_or_default(),
+            blind: self.blind.unwrap_or_default(),
+            auto_advance: self.auto_advance.filter(|&secs| secs > 0),
+            target_wpm: self.target_wpm.filter(|&wpm| wpm > 0. && wpm.is_finite()),
         }
     }
 }
@@ -53,6 +127,9 @@ fn parse_user_config() -> anyhow::Result<UserConfig> {
 pub fn get_final_config() -> FinalConfig {
     match parse_user_config() {
         Ok(user_config) => user_config.into_final_config(),
-        _ => FinalConfig::default(),
+        _ => FinalConfig {
+            typing_test_config: load_last_test().unwrap_or_default(),
+            ..FinalConfig::default()
+        },
     }
 }
@@ -0,0 +1,237 @@
+//! Test setups packed into short codes that can be passed around
+//! the code holds the test, its length, word pool, time limit, mods and whether it's endless
+//! personal preferences like strict or the bell stay out of it
+
+use crate::settings::{
+    self, decode_test_mod_bitflags, encode_test_mod_bitflags, TestVariant, TypingTestConfig,
+};
+use crate::storage;
+use anyhow::{bail, ensure, Context};
+
+/// bumped whenever the layout changes so old codes get a clear error
+const CODE_VERSION: u8 = 2;
+/// version, variant, mods and flags bytes followed by length, pool and time as u32
+const HEADER_LEN: usize = 4 + 3 * 4;
+/// bit of the flags byte set for endless tests
+const ENDLESS_FLAG: u8 = 1;
+
+/// Packs the test setup into a url safe code
+/// ```
+/// use smokey::config::{decode_config, encode_config};
+/// use smokey::settings::TypingTestConfig;
+///
+/// let ttc = TypingTestConfig {
+///     length: 42,
+///     ..Default::default()
+/// };
+/// let code = encode_config(&ttc);
+/// assert_eq!(decode_config(&code).unwrap().length, 42);
+/// ```
+pub fn encode_config(ttc: &TypingTestConfig) -> String {
+    let variant: u8 = match ttc.variant {
+        TestVariant::Standard => 0,
+        TestVariant::Script => 1,
+        TestVariant::Quote => 2,
+        TestVariant::Drill => 3,
+    };
+    let shrink = |n: usize| n.min(u32::MAX as usize) as u32;
+
+    let flags = if ttc.endless { ENDLESS_FLAG } else { 0 };
+    let mut bytes = vec![
+        CODE_VERSION,
+        variant,
+        encode_test_mod_bitflags(&ttc.mods),
+        flags,
+    ];
+    bytes.extend(shrink(ttc.length).to_le_bytes());
+    bytes.extend(shrink(ttc.word_pool).to_le_bytes());
+    // 0 for tests that aren't timed
+    let time = ttc.time_limit.map_or(0, |t| t.min(u32::MAX as u64) as u32);
+    bytes.extend(time.to_le_bytes());
+    bytes.extend(ttc.name.as_bytes());
+
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+/// Unpacks a code made by encode_config,
+/// errors out on anything garbled or on tests that aren't available here
+/// codes come from other people so scripts are refused, they'd run right away
+pub fn decode_config(code: &str) -> anyhow::Result<TypingTestConfig> {
+    decode(code, false)
+}
+
+/// Same as decode_config for codes made on this machine, like the recent tests,
+/// which can pick scripts from the tests list
+pub fn decode_own_config(code: &str) -> anyhow::Result<TypingTestConfig> {
+    decode(code, true)
+}
+
+fn decode(code: &str, allow_scripts: bool) -> anyhow::Result<TypingTestConfig> {
+    let bytes = base64::decode_config(code.trim(), base64::URL_SAFE_NO_PAD)
+        .context("the code isn't valid base64")?;
+    ensure!(bytes.len() > HEADER_LEN, "the code is too short");
+    ensure!(
+        bytes[0] == CODE_VERSION,
+        "the code was made by a different version of smokey"
+    );
+
+    let variant = match bytes[1] {
+        0 => TestVariant::Standard,
+        1 => TestVariant::Script,
+        2 => TestVariant::Quote,
+        3 => TestVariant::Drill,
+        other => bail!("unknown test kind {}", other),
+    };
+
+    let mods = decode_test_mod_bitflags(bytes[2]);
+    ensure!(
+        encode_test_mod_bitflags(&mods) == bytes[2],
+        "the code has mods this version doesn't know"
+    );
+
+    let flags = bytes[3];
+    ensure!(
+        flags & !ENDLESS_FLAG == 0,
+        "the code has options this version doesn't know"
+    );
+
+    let read_u32 = |at: usize| {
+        let mut word = [0; 4];
+        word.copy_from_slice(&bytes[at..at + 4]);
+        u32::from_le_bytes(word)
+    };
+    let length = read_u32(4) as usize;
+    let word_pool = read_u32(8) as usize;
+    let time = read_u32(12) as u64;
+    ensure!(length > 0, "the test length can't be 0");
+    ensure!(word_pool > 0, "the word pool can't be 0");
+
+    let name =
+        String::from_utf8(bytes[HEADER_LEN..].to_vec()).context("the test name is garbled")?;
+
+    let ttc = TypingTestConfig {
+        name,
+        variant,
+        length,
+        word_pool,
+        time_limit: (time > 0).then_some(time),
+        mods,
+        endless: flags & ENDLESS_FLAG != 0,
+        ..TypingTestConfig::default()
+    };
+
+    // the name ends up in a path, it can't point anywhere outside the storage
+    storage::check_test_name(&ttc.name)?;
+    ensure!(
+        allow_scripts || !matches!(ttc.variant, TestVariant::Script),
+        "shared codes can't run scripts, pick {} from the tests list instead",
+        ttc.name
+    );
+    ensure!(
+        settings::list_tests().contains(&ttc.list_name()),
+        "there's no {} test here",
+        ttc.name
+    );
+
+    Ok(ttc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::langs;
+    use crate::settings::TestMod;
+
+    fn round_trip(ttc: &TypingTestConfig) -> TypingTestConfig {
+        decode_config(&encode_config(ttc)).unwrap()
+    }
+
+    #[test]
+    fn test_code_round_trip() {
+        let mut ttc = TypingTestConfig {
+            length: 50,
+            word_pool: 1000,
+            ..TypingTestConfig::default()
+        };
+        ttc.mods.insert(TestMod::Punctuation);
+        ttc.mods.insert(TestMod::Symbols);
+
+        let decoded = round_trip(&ttc);
+        assert_eq!(decoded.name, ttc.name);
+        assert!(matches!(decoded.variant, TestVariant::Standard));
+        assert_eq!(decoded.length, 50);
+        assert_eq!(decoded.word_pool, 1000);
+        assert_eq!(decoded.time_limit, None);
+        assert_eq!(decoded.mods, ttc.mods);
+        assert!(!decoded.endless);
+
+        let timed = TypingTestConfig {
+            time_limit: Some(60),
+            ..TypingTestConfig::default()
+        };
+        assert_eq!(round_trip(&timed).time_limit, Some(60));
+
+        let endless = TypingTestConfig {
+            endless: true,
+            ..TypingTestConfig::default()
+        };
+        assert!(round_trip(&endless).endless);
+
+        let (drill, _) = langs::DRILLS[0];
+        let drill = TypingTestConfig {
+            name: drill.to_string(),
+            variant: TestVariant::Drill,
+            length: 12,
+            ..TypingTestConfig::default()
+        };
+        let decoded = round_trip(&drill);
+        assert_eq!(decoded.name, drill.name);
+        assert!(matches!(decoded.variant, TestVariant::Drill));
+        assert_eq!(decoded.length, 12);
+    }
+
+    #[test]
+    fn test_bad_codes() {
+        let code = encode_config(&TypingTestConfig::default());
+        assert!(decode_config("").is_err());
+        assert!(decode_config("not a code!").is_err());
+        assert!(decode_config(&code[..8]).is_err());
+
+        // valid base64 but bytes that don't add up
+        let mut bytes = base64::decode_config(&code, base64::URL_SAFE_NO_PAD).unwrap();
+        bytes[2] = 0b1000_0000;
+        assert!(decode_config(&base64::encode_config(&bytes, base64::URL_SAFE_NO_PAD)).is_err());
+        bytes[2] = 0;
+        bytes[3] = 0b10;
+        assert!(decode_config(&base64::encode_config(&bytes, base64::URL_SAFE_NO_PAD)).is_err());
+        // codes of the first version
+        bytes[0] = 1;
+        bytes[3] = 0;
+        assert!(decode_config(&base64::encode_config(&bytes, base64::URL_SAFE_NO_PAD)).is_err());
+
+        let missing = TypingTestConfig {
+            name: "surely_no_such_word_file".to_string(),
+            ..TypingTestConfig::default()
+        };
+        assert!(decode_config(&encode_config(&missing)).is_err());
+    }
+
+    #[test]
+    fn test_codes_stay_in_the_storage() {
+        let script = |name: &str| TypingTestConfig {
+            name: name.to_string(),
+            variant: TestVariant::Script,
+            ..TypingTestConfig::default()
+        };
+        for name in ["/bin/true", "../../bin/true", "..", "#!true"] {
+            assert!(decode_config(&encode_config(&script(name))).is_err());
+            assert!(decode_own_config(&encode_config(&script(name))).is_err());
+        }
+
+        let escape = TypingTestConfig {
+            name: "../words/english".to_string(),
+            ..TypingTestConfig::default()
+        };
+        assert!(decode_config(&encode_config(&escape)).is_err());
+    }
+}
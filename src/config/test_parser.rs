@@ -1,4 +1,8 @@
-use crate::settings::{is_script, TestMod, TestVariant, TypingTestConfig, TEST_MODS};
+use crate::langs;
+use crate::settings::{
+    is_quote, is_script, ExtraLetters, PunctuationKind, TestMod, TestVariant, TypingTestConfig,
+    WordWeighting, TEST_MODS,
+};
 use std::collections::HashSet;
 
 #[derive(serde_derive::Deserialize, Debug)]
@@ -6,7 +10,36 @@ pub struct UserTest {
     name: Option<String>,
     len: Option<usize>,
     pool: Option<usize>,
+    time: Option<u64>,
+    endless: Option<bool>,
+    max_length: Option<usize>,
     mods: Option<Vec<String>>,
+    punctuation: Option<Vec<String>>,
+    curly_quotes: Option<bool>,
+    symbols: Option<String>,
+    seed: Option<u64>,
+    min_len: Option<usize>,
+    max_len: Option<usize>,
+    max_extras: Option<usize>,
+    extras: Option<String>,
+    weighting: Option<String>,
+    capitals: Option<f64>,
+    lowercase: Option<bool>,
+    mix: Option<String>,
+    mix_ratio: Option<f64>,
+    skip_on_space: Option<bool>,
+    strict: Option<bool>,
+    max_mistakes: Option<usize>,
+    min_acc: Option<f64>,
+    bell: Option<bool>,
+    countdown: Option<u64>,
+    practice: Option<bool>,
+    separator: Option<String>,
+    save_min_acc: Option<f64>,
+    save_min_secs: Option<u64>,
+    numbers_only: Option<bool>,
+    numbers_ratio: Option<f64>,
+    digits: Option<Vec<usize>>,
 }
 
 impl UserTest {
@@ -20,6 +53,10 @@ impl UserTest {
             ..TypingTestConfig::default()
         };
 
+        if let Some(max_length) = self.max_length {
+            ttc.max_length = max_length.max(1);
+        }
+
         if let Some(length) = self.len {
             if length > 0 {
                 ttc.length = length
@@ -32,10 +69,85 @@ impl UserTest {
             }
         }
 
+        if let Some(time) = self.time {
+            if time > 0 {
+                ttc.time_limit = Some(time)
+            }
+        }
+
+        ttc.endless = self.endless.unwrap_or_default() && ttc.time_limit.is_none();
+
         if let Some(mods) = self.mods {
             ttc.mods = parse_mods(&mods)
         }
 
+        if let Some(kinds) = self.punctuation {
+            ttc.punctuation_kinds = Some(parse_punctuation_kinds(&kinds));
+        }
+
+        ttc.curly_quotes = self.curly_quotes.unwrap_or_default();
+        ttc.symbols = self.symbols;
+        ttc.seed = self.seed;
+        ttc.min_word_len = self.min_len;
+        ttc.max_word_len = self.max_len;
+        if let Some(max_extras) = self.max_extras {
+            ttc.max_extra_mistakes = max_extras
+        }
+        if let Some(extras) = self.extras {
+            match ExtraLetters::from_name(&extras) {
+                Some(extra_letters) => ttc.extra_letters = extra_letters,
+                None => warn!("unknown extras {:?}, extra letters overflow", extras),
+            }
+        }
+
+        if let Some(weighting) = self.weighting {
+            match WordWeighting::from_name(&weighting) {
+                Some(word_weighting) => ttc.word_weighting = word_weighting,
+                None => warn!("unknown weighting {:?}, words stay uniform", weighting),
+            }
+        }
+
+        if let Some(capitals) = self.capitals {
+            // a chance outside of 0..=1 is clamped so it can't panic the generator
+            ttc.random_capitals = capitals.clamp(0., 1.);
+        }
+        ttc.lowercase = self.lowercase.unwrap_or_default();
+        ttc.mix = self.mix;
+        if let Some(ratio) = self.mix_ratio {
+            ttc.mix_ratio = ratio.clamp(0., 1.);
+        }
+
+        ttc.skip_on_space = self.skip_on_space.unwrap_or_default();
+        ttc.strict = self.strict.unwrap_or_default();
+        ttc.max_mistakes = self.max_mistakes;
+        ttc.min_acc = self.min_acc;
+        ttc.bell = self.bell.unwrap_or_default();
+        ttc.countdown = self.countdown.filter(|&secs| secs > 0);
+        ttc.practice = self.practice.unwrap_or_default();
+        if let Some(separator) = self.separator {
+            let mut chars = separator.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if !c.is_alphanumeric() && !c.is_control() => ttc.separator = c,
+                _ => warn!(
+                    "separator {:?} has to be a single symbol, words stay spaced",
+                    separator
+                ),
+            }
+        }
+        ttc.save_min_acc = self.save_min_acc;
+        ttc.save_min_secs = self.save_min_secs.filter(|&secs| secs > 0);
+        ttc.numbers_only = self.numbers_only.unwrap_or_default();
+        if let Some(ratio) = self.numbers_ratio {
+            ttc.numbers_ratio = ratio.clamp(0., 1.);
+        }
+        if let Some(digits) = self.digits {
+            // a group that doesn't fit on a line would never wrap
+            ttc.digit_groups = digits
+                .into_iter()
+                .filter(|len| (1..=langs::LIMIT).contains(len))
+                .collect();
+        }
+
         ttc
     }
 }
@@ -50,9 +162,39 @@ fn parse_mods(raw_mods: &[String]) -> HashSet<TestMod> {
     parsed_mods
 }
 
+fn parse_punctuation_kinds(raw_kinds: &[String]) -> HashSet<PunctuationKind> {
+    let mut kinds = HashSet::new();
+    for raw_kind in raw_kinds {
+        match PunctuationKind::from_name(raw_kind.trim()) {
+            Some(kind) => {
+                kinds.insert(kind);
+            }
+            None => warn!("unknown punctuation {:?} is left out", raw_kind),
+        }
+    }
+    kinds
+}
+
+/// Like parse_mods but rejects unknown mods
+/// returning the first one that isn't recognized
+pub fn try_parse_mods(raw_mods: &[String]) -> Result<HashSet<TestMod>, String> {
+    let mut parsed_mods = HashSet::new();
+    for raw_mod in raw_mods {
+        match TEST_MODS.get_by_left(raw_mod.trim()) {
+            Some(&parsed_mod) => {
+                parsed_mods.insert(parsed_mod);
+            }
+            None => return Err(raw_mod.to_string()),
+        }
+    }
+    Ok(parsed_mods)
+}
+
 fn resolve_test_variant(test_name: &str) -> TestVariant {
     if is_script(test_name) {
         TestVariant::Script
+    } else if is_quote(test_name) {
+        TestVariant::Quote
     } else {
         TestVariant::Standard
     }
@@ -78,4 +220,27 @@ mod tests {
         hs.insert(TestMod::Symbols);
         assert_eq!(parse_mods(&full), hs);
     }
+
+    #[test]
+    fn test_parse_punctuation_kinds() {
+        let raw = vec_of_strings!["end", " paired", "commas"];
+        let kinds = parse_punctuation_kinds(&raw);
+        let expected: HashSet<PunctuationKind> = [PunctuationKind::End, PunctuationKind::Paired]
+            .into_iter()
+            .collect();
+        assert_eq!(kinds, expected);
+        assert!(parse_punctuation_kinds(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_try_parse_mods() {
+        let faulty = vec_of_strings!["punctuation", "nonexistant"];
+        let full = vec_of_strings!["numbers", " punctuation"];
+        assert_eq!(try_parse_mods(&faulty), Err("nonexistant".to_string()));
+
+        let mut hs = HashSet::new();
+        hs.insert(TestMod::Punctuation);
+        hs.insert(TestMod::Numbers);
+        assert_eq!(try_parse_mods(&full), Ok(hs));
+    }
 }
@@ -1,26 +1,49 @@
-use crate::colorscheme::Theme;
-use serde_derive::Deserialize;
-use std::num::ParseIntError;
+use crate::colorscheme::{parse_color, Theme};
+use serde_derive::{Deserialize, Serialize};
 use tui::style::Color;
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct UserTheme {
+    #[serde(skip_serializing_if = "Option::is_none")]
     done: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     mistake: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     todo: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     hover: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     active: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    extra: Option<String>,
+}
+
+impl From<Theme> for UserTheme {
+    fn from(theme: Theme) -> Self {
+        Self {
+            done: color_to_string(theme.done),
+            mistake: color_to_string(theme.mistake),
+            todo: color_to_string(theme.todo),
+            hover: color_to_string(theme.hover),
+            active: color_to_string(theme.active),
+            extra: color_to_string(theme.extra),
+        }
+    }
 }
 
 impl UserTheme {
     pub fn into_theme(self) -> Theme {
-        let mut base = Theme::default();
+        self.apply_to(Theme::default())
+    }
 
+    /// overwrites colors of the base theme with the user defined ones
+    pub fn apply_to(self, mut base: Theme) -> Theme {
         change_color_to_user_defined(&mut base.done, &self.done);
         change_color_to_user_defined(&mut base.mistake, &self.mistake);
         change_color_to_user_defined(&mut base.todo, &self.todo);
         change_color_to_user_defined(&mut base.active, &self.active);
         change_color_to_user_defined(&mut base.hover, &self.hover);
+        change_color_to_user_defined(&mut base.extra, &self.extra);
 
         base
     }
@@ -28,53 +51,41 @@ impl UserTheme {
 
 fn change_color_to_user_defined(final_color: &mut Color, user_defined_color: &Option<String>) {
     if let Some(clr_str) = user_defined_color {
-        if let Some(clr) = parse_user_defined_colors(clr_str) {
-            *final_color = clr;
+        match parse_user_defined_colors(clr_str) {
+            Some(clr) => *final_color = clr,
+            None => warn!("couldn't parse color {:?}, using the default", clr_str),
         }
     }
 }
 
 pub fn parse_user_defined_colors(user_color: &str) -> Option<Color> {
-    let prepared_user_color = user_color.trim();
-
-    if let Some(c) = prepared_user_color.chars().next() {
-        if c == '#' && user_color.len() >= 7 {
-            hex_to_color(prepared_user_color).ok()
-        } else {
-            str_to_color(&prepared_user_color.to_lowercase())
-        }
-    } else {
-        None
-    }
+    parse_color(user_color).ok()
 }
 
-fn hex_to_color(hex_code: &str) -> Result<Color, ParseIntError> {
-    let r: u8 = u8::from_str_radix(&hex_code[1..3], 16)?;
-    let g: u8 = u8::from_str_radix(&hex_code[3..5], 16)?;
-    let b: u8 = u8::from_str_radix(&hex_code[5..7], 16)?;
-    Ok(Color::Rgb(r, g, b))
-}
-
-fn str_to_color(color: &str) -> Option<Color> {
-    match color {
-        "black" => Some(Color::Black),
-        "red" => Some(Color::Red),
-        "green" => Some(Color::Green),
-        "yellow" => Some(Color::Yellow),
-        "blue" => Some(Color::Blue),
-        "magenta" => Some(Color::Magenta),
-        "cyan" => Some(Color::Cyan),
-        "gray" => Some(Color::Gray),
-        "darkgray" => Some(Color::DarkGray),
-        "lightred" => Some(Color::LightRed),
-        "lightgreen" => Some(Color::LightGreen),
-        "lightyellow" => Some(Color::LightYellow),
-        "lightblue" => Some(Color::LightBlue),
-        "lightmagenta" => Some(Color::LightMagenta),
-        "lightcyan" => Some(Color::LightCyan),
-        "white" => Some(Color::White),
-        _ => None,
-    }
+/// inverse of parse_user_defined_colors
+/// colors that can't be written down in a theme file return None
+fn color_to_string(color: Color) -> Option<String> {
+    let name = match color {
+        Color::Rgb(r, g, b) => return Some(format!("#{:02x}{:02x}{:02x}", r, g, b)),
+        Color::Black => "black",
+        Color::Red => "red",
+        Color::Green => "green",
+        Color::Yellow => "yellow",
+        Color::Blue => "blue",
+        Color::Magenta => "magenta",
+        Color::Cyan => "cyan",
+        Color::Gray => "gray",
+        Color::DarkGray => "darkgray",
+        Color::LightRed => "lightred",
+        Color::LightGreen => "lightgreen",
+        Color::LightYellow => "lightyellow",
+        Color::LightBlue => "lightblue",
+        Color::LightMagenta => "lightmagenta",
+        Color::LightCyan => "lightcyan",
+        Color::White => "white",
+        _ => return None,
+    };
+    Some(name.to_string())
 }
 
 #[cfg(test)]
@@ -157,6 +168,7 @@ mod tests {
         mistake = "maGenta"
         hover = "BLUE"
         todo = "#ff0000"
+        extra = "darkgray"
     "##;
 
         let th = Theme {
@@ -165,8 +177,46 @@ mod tests {
             mistake: Color::Magenta,
             hover: Color::Blue,
             todo: Color::Rgb(255, 0, 0),
+            extra: Color::DarkGray,
         };
 
         assert_eq!(th, theme_from_config(complete_config));
     }
+
+    #[test]
+    fn test_theme_round_trip() {
+        let th = Theme {
+            done: Color::Rgb(252, 8, 244),
+            active: Color::LightYellow,
+            mistake: Color::Magenta,
+            hover: Color::DarkGray,
+            todo: Color::Rgb(0, 0, 0),
+            extra: Color::Rgb(9, 9, 9),
+        };
+
+        let serialized = toml::to_string(&UserTheme::from(th)).unwrap();
+        let deserialized: UserTheme = toml::from_str(&serialized).unwrap();
+        assert_eq!(th, deserialized.into_theme());
+    }
+
+    #[test]
+    fn test_theme_from_file() {
+        let dir = std::env::temp_dir().join("smokey_test_theme_from_file");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let th = Theme {
+            done: Color::Rgb(1, 2, 3),
+            ..Theme::default()
+        };
+        let good = dir.join("good.toml");
+        std::fs::write(&good, toml::to_string(&UserTheme::from(th)).unwrap()).unwrap();
+        assert_eq!(th, Theme::from_file(&good));
+
+        let bad = dir.join("bad.toml");
+        std::fs::write(&bad, "done = [this isn't toml").unwrap();
+        assert_eq!(Theme::default(), Theme::from_file(&bad));
+
+        assert_eq!(Theme::default(), Theme::from_file(dir.join("missing.toml")));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -12,7 +12,8 @@ correct_chars,
 mistakes,
 datetime(date, 'unixepoch', 'localtime'),
 length,
-word_pool
+word_pool,
+duration
 
 FROM run
 INNER JOIN test ON test.test_id = run.test_id
@@ -20,7 +21,7 @@ ORDER BY run_id desc
 LIMIT ?;
 "#;
 
-fn decode(bitflag: u8) -> String {
+pub(super) fn decode(bitflag: u8) -> String {
     let mut result = String::from(" ");
 
     for i in 0..3 {
@@ -115,7 +116,12 @@ pub fn get_history(conn: &Connection, limit: usize) -> Result<History, rusqlite:
             row.get(2)?
         } else {
             let raw_name: String = row.get(2)?;
-            let length: usize = row.get(7)?;
+            let duration: u64 = row.get(9)?;
+            let length: String = if duration == 0 {
+                row.get::<_, usize>(7)?.to_string()
+            } else {
+                format!("{}s", duration)
+            };
             format!(
                 "{} {}/{}{}",
                 raw_name,
@@ -15,6 +15,8 @@ pub fn init_db(conn: &mut Connection) -> SqlResult<()> {
 
     test_table_init(&tx)?;
     run_table_init(&tx)?;
+    add_duration_column(&tx)?;
+    missed_word_table_init(&tx)?;
 
     tx.commit()?;
 
@@ -52,6 +54,35 @@ fn run_table_init(conn: &Connection) -> SqlResult<()> {
     Ok(())
 }
 
+/// wrong keys by the word they were typed in over all runs
+fn missed_word_table_init(conn: &Connection) -> SqlResult<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS missed_word (
+    word TEXT PRIMARY KEY,
+    misses INTEGER NOT NULL
+    );",
+        [],
+    )?;
+    Ok(())
+}
+
+/// databases created before timed tests lack the duration column
+fn add_duration_column(conn: &Connection) -> SqlResult<()> {
+    let has_duration: bool = conn.query_row(
+        "SELECT count(*) FROM pragma_table_info('run') WHERE name = 'duration'",
+        [],
+        |row| row.get(0),
+    )?;
+
+    if !has_duration {
+        conn.execute(
+            "ALTER TABLE run ADD COLUMN duration INTEGER NOT NULL DEFAULT 0",
+            [],
+        )?;
+    }
+    Ok(())
+}
+
 pub fn enable_foreign_keys(conn: &Connection) {
     conn.execute("PRAGMA foreign_keys = ON", []).unwrap();
 }
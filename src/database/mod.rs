@@ -1,10 +1,10 @@
 pub mod history;
 pub mod init;
-use crate::settings::{TestMod, TestVariant, TypingTestConfig, BITFLAG_MODS};
+use crate::settings::{TestIdentity, TestVariant, TypingTestConfig};
 use crate::storage;
 use anyhow::Result;
 use rusqlite::{params, Connection};
-use std::collections::HashSet;
+use std::collections::HashMap;
 
 /// A wrapper around rusqlite::Connection
 /// with convenient methods to save run results
@@ -26,30 +26,36 @@ impl RunHistoryDatbase {
         let test_id = get_test_id_or_create(&self.conn, &ttc.name).unwrap();
         let sum = &ttc.test_summary;
 
-        let (len, mods, pool): (usize, u8, usize);
-
-        match ttc.variant {
-            TestVariant::Script => {
-                len = 0;
-                mods = 0;
-                pool = 0;
-            }
-            TestVariant::Standard => {
-                len = ttc.length;
-                mods = encode_test_mod_bitflag(&ttc.mods);
-                pool = ttc.word_pool;
-            }
-        }
+        let tid = run_identity(ttc);
 
         self.conn.execute(
-            "INSERT INTO run (date, test_id, length, mods, word_pool, correct_chars, mistakes, wpm, acc)
-            VALUES ((SELECT strftime('%s', 'now')), ?, ?, ?, ?, ?, ?, ?, ?);",
-            params![test_id, len, mods, pool,
-            sum.correct_chars, sum.mistakes, sum.wpm, sum.acc],
+            "INSERT INTO run (date, test_id, length, mods, word_pool, duration, correct_chars, mistakes, wpm, acc)
+            VALUES ((SELECT strftime('%s', 'now')), ?, ?, ?, ?, ?, ?, ?, ?, ?);",
+            params![test_id, tid.length, tid.mods, tid.word_pool, tid.duration,
+            sum.correct_chars, sum.mistakes, sum.net_wpm, sum.acc],
             )
             .expect("inserting into run");
     }
 
+    /// Adds the words missed in a run to the tally of all runs
+    pub fn save_missed_words(&mut self, words: &HashMap<String, usize>) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        for (word, misses) in words {
+            tx.execute(
+                "INSERT INTO missed_word (word, misses) VALUES (?, ?)
+                ON CONFLICT (word) DO UPDATE SET misses = misses + excluded.misses;",
+                params![word, misses],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Returns up to limit most recent runs, newest first
+    pub fn recent_results(&self, limit: usize) -> Result<Vec<RunRecord>> {
+        get_recent_results(&self.conn, limit)
+    }
+
     pub fn print_history(&self, limit: usize) {
         history::get_history(&self.conn, limit)
             .expect("could")
@@ -57,6 +63,142 @@ impl RunHistoryDatbase {
     }
 }
 
+/// identity under which a run lands in the database
+/// scripts and quotes don't have length, mods etc. so these are zeroed
+fn run_identity(ttc: &TypingTestConfig) -> TestIdentity {
+    match ttc.variant {
+        TestVariant::Script | TestVariant::Quote | TestVariant::Drill => TestIdentity {
+            length: 0,
+            word_pool: 0,
+            mods: 0,
+            duration: 0,
+        },
+        TestVariant::Standard => ttc.gib_identity(),
+    }
+}
+
+/// Amount of most recent runs the average wpm is calculated from
+pub const RECENT_RUNS_AMOUNT: usize = 10;
+
+/// wpm statistics of runs of a single test type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AggStats {
+    pub best: f64,
+    /// average of RECENT_RUNS_AMOUNT most recent runs
+    pub recent_avg: f64,
+}
+
+/// Returns best and recent average wpm of runs matching the test config
+/// None if there are no runs yet
+pub fn stats_for(conn: &Connection, ttc: &TypingTestConfig) -> Option<AggStats> {
+    let tid = run_identity(ttc);
+    conn.query_row(
+        "SELECT max(wpm), avg(CASE WHEN nth <= ? THEN wpm END) FROM (
+            SELECT wpm, row_number() OVER (ORDER BY run_id DESC) AS nth FROM run WHERE
+            test_id = (select test_id FROM test WHERE test_name = ?)
+            AND length = ?
+            AND word_pool = ?
+            AND mods = ?
+            AND duration = ?
+        )",
+        params![
+            RECENT_RUNS_AMOUNT,
+            &ttc.name,
+            tid.length,
+            tid.word_pool,
+            tid.mods,
+            tid.duration
+        ],
+        |row| {
+            let best: Option<f64> = row.get(0)?;
+            let recent_avg: Option<f64> = row.get(1)?;
+            Ok(best.zip(recent_avg))
+        },
+    )
+    .ok()
+    .flatten()
+    .map(|(best, recent_avg)| AggStats { best, recent_avg })
+}
+
+/// A single saved run as it is stored in the database
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunRecord {
+    /// unix timestamp
+    pub date: i64,
+    /// the same date in local time e.g. 2022-03-15 21:37:00
+    pub local_date: String,
+    pub test_name: String,
+    pub length: usize,
+    pub word_pool: usize,
+    pub duration: u64,
+    pub mods: u8,
+    pub correct_chars: usize,
+    pub mistakes: usize,
+    pub wpm: f64,
+    pub acc: f64,
+}
+
+impl RunRecord {
+    /// name of the test along with its length, word pool and mods
+    /// the way the cli history shows it
+    pub fn describe(&self) -> String {
+        // scripts and quotes are saved without a word pool
+        if self.word_pool == 0 {
+            return self.test_name.clone();
+        }
+        let length = match self.duration {
+            0 => self.length.to_string(),
+            secs => format!("{}s", secs),
+        };
+        format!(
+            "{} {}/{}{}",
+            self.test_name,
+            length,
+            self.word_pool,
+            history::decode(self.mods)
+        )
+    }
+}
+
+pub fn get_recent_results(conn: &Connection, limit: usize) -> Result<Vec<RunRecord>> {
+    let mut stmt = conn.prepare(
+        "SELECT date, test.test_name, length, word_pool, duration, mods,
+        correct_chars, mistakes, wpm, acc, datetime(date, 'unixepoch', 'localtime')
+        FROM run
+        INNER JOIN test ON test.test_id = run.test_id
+        ORDER BY run_id DESC
+        LIMIT ?",
+    )?;
+
+    let rows = stmt.query_map([limit], |row| {
+        Ok(RunRecord {
+            date: row.get(0)?,
+            test_name: row.get(1)?,
+            length: row.get(2)?,
+            word_pool: row.get(3)?,
+            duration: row.get(4)?,
+            mods: row.get(5)?,
+            correct_chars: row.get(6)?,
+            mistakes: row.get(7)?,
+            wpm: row.get(8)?,
+            acc: row.get(9)?,
+            local_date: row.get(10)?,
+        })
+    })?;
+
+    Ok(rows.collect::<Result<Vec<RunRecord>, rusqlite::Error>>()?)
+}
+
+/// Words missed the most over all runs, the most missed first
+pub fn get_problem_words(conn: &Connection, limit: usize) -> Result<Vec<String>> {
+    let mut stmt =
+        conn.prepare("SELECT word FROM missed_word ORDER BY misses DESC, word LIMIT ?;")?;
+    let words = stmt
+        .query_map([limit], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(words)
+}
+
 pub fn get_max_wpm_script(conn: &Connection, script_name: &str) -> Option<f64> {
     conn.query_row(
         "SELECT max(wpm) FROM run WHERE
@@ -68,18 +210,15 @@ pub fn get_max_wpm_script(conn: &Connection, script_name: &str) -> Option<f64> {
 }
 
 pub fn get_max_wpm(conn: &Connection, ttc: &TypingTestConfig) -> Option<f64> {
+    let tid = ttc.gib_identity();
     conn.query_row(
         "SELECT max(wpm) FROM run WHERE
         test_id = (select test_id FROM test WHERE test_name = ?)
         AND length = ?
         AND word_pool = ?
-        AND mods = ?",
-        params![
-            &ttc.name,
-            ttc.length,
-            ttc.word_pool,
-            encode_test_mod_bitflag(&ttc.mods),
-        ],
+        AND mods = ?
+        AND duration = ?",
+        params![&ttc.name, tid.length, tid.word_pool, tid.mods, tid.duration],
         |row| row.get(0),
     )
     .ok()
@@ -106,36 +245,12 @@ pub fn get_test_id_or_create(conn: &Connection, test_id: &str) -> Result<usize>
     }
 }
 
-pub fn encode_test_mod_bitflag(test_mods: &HashSet<TestMod>) -> u8 {
-    let mut bitflag: u8 = 0;
-    for test_mod in test_mods {
-        bitflag += BITFLAG_MODS.get_by_right(test_mod).expect("wrong mod?");
-    }
-    bitflag
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::settings::TestMod;
     use crate::settings::TypingTestConfig;
     use rusqlite::Connection;
-    use std::collections::HashSet;
-
-    #[test]
-    fn test_encode_mod_bitflag() {
-        let mut tm: HashSet<TestMod> = HashSet::new();
-        let zero = encode_test_mod_bitflag(&tm);
-        assert_eq!(zero, 0_u8);
-
-        tm.insert(TestMod::Punctuation);
-        let one = encode_test_mod_bitflag(&tm);
-        assert_eq!(one, 1_u8);
-
-        tm.insert(TestMod::Symbols);
-        let five = encode_test_mod_bitflag(&tm);
-        assert_eq!(five, 5_u8);
-    }
 
     #[test]
     fn test_get_max_wpm() {
@@ -150,7 +265,7 @@ mod tests {
 
         let wpms: [f64; 5] = [69., 152., 51., 72., 150.];
         for wpm in wpms {
-            ttc.test_summary.wpm = wpm;
+            ttc.test_summary.net_wpm = wpm;
             conn.save(&ttc);
         }
 
@@ -160,4 +275,94 @@ mod tests {
         assert!(max_wpm - f64::EPSILON <= should_be_max_wpm);
         assert!(max_wpm + f64::EPSILON >= should_be_max_wpm);
     }
+
+    #[test]
+    fn test_problem_words() {
+        let mut conn = RunHistoryDatbase {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        init::init_db(&mut conn.conn).unwrap();
+        assert!(get_problem_words(&conn.conn, 10).unwrap().is_empty());
+
+        let run = |words: &[(&str, usize)]| -> HashMap<String, usize> {
+            words.iter().map(|&(w, n)| (w.to_string(), n)).collect()
+        };
+        conn.save_missed_words(&run(&[("the", 1), ("which", 2)]))
+            .unwrap();
+        conn.save_missed_words(&run(&[("the", 3), ("about", 2)]))
+            .unwrap();
+
+        // misses add up over runs
+        assert_eq!(
+            get_problem_words(&conn.conn, 10).unwrap(),
+            vec!["the", "about", "which"]
+        );
+        assert_eq!(get_problem_words(&conn.conn, 1).unwrap(), vec!["the"]);
+    }
+
+    #[test]
+    fn test_recent_results() {
+        let mut conn = RunHistoryDatbase {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        init::init_db(&mut conn.conn).unwrap();
+        assert!(conn.recent_results(10).unwrap().is_empty());
+
+        let mut ttc = TypingTestConfig::default();
+        ttc.mods.insert(TestMod::Numbers);
+        for wpm in [60., 70., 80.] {
+            ttc.test_summary.net_wpm = wpm;
+            ttc.test_summary.acc = 95.;
+            conn.save(&ttc);
+        }
+
+        let recent = conn.recent_results(2).unwrap();
+        assert_eq!(recent.len(), 2);
+
+        // newest first
+        assert!((recent[0].wpm - 80.).abs() < f64::EPSILON);
+        assert!((recent[1].wpm - 70.).abs() < f64::EPSILON);
+
+        let record = &recent[0];
+        assert_eq!(record.test_name, "english");
+        assert_eq!(record.length, ttc.length);
+        assert_eq!(record.word_pool, ttc.word_pool);
+        assert_eq!(record.duration, 0);
+        assert_eq!(record.mods, 0b10);
+        assert!(record.date > 0);
+        assert_eq!(record.local_date.len(), "2022-03-15 21:37:00".len());
+        assert_eq!(record.describe(), "english 25/5000 17");
+    }
+
+    #[test]
+    fn test_stats_for() {
+        let mut conn = RunHistoryDatbase {
+            conn: Connection::open_in_memory().unwrap(),
+        };
+        init::init_db(&mut conn.conn).unwrap();
+        let mut ttc = TypingTestConfig::default();
+        assert!(stats_for(&conn.conn, &ttc).is_none());
+
+        // 2 old slow runs fall out of the recent window
+        for wpm in [200., 20., 20.] {
+            ttc.test_summary.net_wpm = wpm;
+            conn.save(&ttc);
+        }
+        for _ in 0..RECENT_RUNS_AMOUNT {
+            ttc.test_summary.net_wpm = 100.;
+            conn.save(&ttc);
+        }
+
+        // runs of other test types don't count
+        let mut other = TypingTestConfig {
+            length: 50,
+            ..Default::default()
+        };
+        other.test_summary.net_wpm = 300.;
+        conn.save(&other);
+
+        let stats = stats_for(&conn.conn, &ttc).unwrap();
+        assert!((stats.best - 200.).abs() < f64::EPSILON);
+        assert!((stats.recent_avg - 100.).abs() < f64::EPSILON);
+    }
 }
@@ -0,0 +1,95 @@
+//! Word lists downloaded into the storage with --fetch
+//! the download goes through curl so there's no http client to build
+
+use crate::storage::{get_storage_dir, save_word_list};
+use anyhow::{ensure, Context};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// seconds a download can take before curl gives up
+const MAX_FETCH_SECS: u32 = 60;
+/// the biggest word list that's downloaded, 64MiB
+const MAX_FETCH_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Downloads the word list and saves it in the storage under the name
+pub fn fetch_word_list(name: &str, url: &str) -> anyhow::Result<PathBuf> {
+    fetch_word_list_into(&get_storage_dir(), name, url)
+}
+
+fn fetch_word_list_into(dir: &Path, name: &str, url: &str) -> anyhow::Result<PathBuf> {
+    let bytes = download(url)?;
+    save_word_list(dir, name, &bytes)
+}
+
+/// file:// urls are read straight away, anything else is up to curl
+fn download(url: &str) -> anyhow::Result<Vec<u8>> {
+    if let Some(path) = url.strip_prefix("file://") {
+        return std::fs::read(path).with_context(|| format!("couldn't read {}", path));
+    }
+
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location"])
+        .args(["--max-time", &MAX_FETCH_SECS.to_string()])
+        .args(["--max-filesize", &MAX_FETCH_BYTES.to_string()])
+        // a url starting with - isn't taken for an option
+        .args(["--", url])
+        .output()
+        .context("couldn't run curl")?;
+    ensure!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    );
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_fetch_file_url() {
+        let dir = std::env::temp_dir().join("smokey_test_fetch_file_url");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("source.txt");
+        fs::write(&source, "one\ntwo\nthree\n").unwrap();
+
+        let url = format!("file://{}", source.display());
+        let path = fetch_word_list_into(&dir, "fetched", &url).unwrap();
+        assert!(crate::storage::is_valid_word_list(&path));
+
+        let missing = format!("file://{}", dir.join("missing").display());
+        assert!(fetch_word_list_into(&dir, "missing", &missing).is_err());
+        assert!(!dir.join("words").join("missing").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_url_isnt_an_option() {
+        let dir = std::env::temp_dir().join("smokey_test_url_isnt_an_option");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("source.txt");
+        let written = dir.join("written");
+        fs::write(&source, "one\n").unwrap();
+        // as an option this config would have curl copy the source over
+        let config = dir.join("curlrc");
+        fs::write(
+            &config,
+            format!(
+                "url = \"file://{}\"\noutput = \"{}\"\n",
+                source.display(),
+                written.display()
+            ),
+        )
+        .unwrap();
+
+        assert!(download(&format!("-K{}", config.display())).is_err());
+        assert!(!written.exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
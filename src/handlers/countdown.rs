@@ -0,0 +1,73 @@
+use crate::application::App;
+use crate::keys::Action;
+use crossterm::event::KeyEvent;
+
+/// handles keys while a timed test counts down,
+/// typing is dropped instead of waiting for the test to start
+pub fn handle(key: KeyEvent, app: &mut App) {
+    match app.keys.action(&key) {
+        Some(Action::Quit) => app.stop(),
+        Some(Action::Restart) => {
+            app.reset_test();
+            app.change_to_test();
+        }
+        Some(Action::Settings) => {
+            app.countdown_until = None;
+            app.change_to_settings();
+        }
+        None => (),
+    }
+}
+
+/// repaints every tick so the seconds keep going down
+/// and lets the keys through to the test once it's over
+pub fn tick(app: &mut App) {
+    app.dirty = true;
+    if app.countdown_left() == Some(0) {
+        app.start_typing();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::{typer, KeyHandler};
+    use crossterm::event::KeyCode;
+    use std::time::Instant;
+
+    #[test]
+    fn test_countdown_before_timed_test() {
+        let mut app = App::setup();
+        app.settings.test_cfg.countdown = Some(3);
+
+        // untimed tests start right away
+        app.change_to_test();
+        assert!(app.countdown_until.is_none());
+
+        app.settings.test_cfg.time_limit = Some(15);
+        app.reset_test();
+        app.change_to_test();
+        let countdown: KeyHandler = handle;
+        assert_eq!(app.key_handler as usize, countdown as usize);
+        assert_eq!(app.countdown_left(), Some(3));
+
+        // keys during the countdown are dropped, not saved for later
+        let first = app.test.current_char;
+        app.feed_keys(&[KeyEvent::from(KeyCode::Char(first))]);
+        assert!(app.test.first);
+        assert_eq!(app.test.done, 0);
+
+        app.countdown_until = Some(Instant::now());
+        app.tick();
+        let typer: KeyHandler = typer::handle;
+        assert_eq!(app.key_handler as usize, typer as usize);
+        assert_eq!(app.countdown_left(), None);
+
+        app.feed_keys(&[KeyEvent::from(KeyCode::Char(first))]);
+        assert!(!app.test.first);
+
+        // a started test doesn't count down again
+        app.change_to_test();
+        assert_eq!(app.key_handler as usize, typer as usize);
+    }
+}
@@ -0,0 +1,87 @@
+use crate::application::App;
+use crate::keys::Action;
+use crossterm::event::{KeyCode, KeyEvent};
+
+pub fn handle(key: KeyEvent, app: &mut App) {
+    match app.keys.action(&key) {
+        Some(Action::Quit) => return app.stop(),
+        Some(Action::Restart) => {
+            app.reset_test();
+            return app.change_to_test();
+        }
+        Some(Action::Settings) => return app.change_to_settings(),
+        None => (),
+    }
+
+    match key.code {
+        KeyCode::Char(c) => match c {
+            'j' => app.settings.history.next(),
+            'k' => app.settings.history.previous(),
+            'q' => app.stop(),
+            _ => (),
+        },
+
+        KeyCode::Down => app.settings.history.next(),
+        KeyCode::Up => app.settings.history.previous(),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{init::init_db, RunHistoryDatbase};
+    use crate::handlers::{self, KeyHandler};
+    use crate::settings::Settings;
+    use rusqlite::Connection;
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_key_event(KeyEvent::from(code));
+    }
+
+    #[test]
+    fn test_history_screen() {
+        let mut settings = Settings {
+            database: RunHistoryDatbase {
+                conn: Connection::open_in_memory().unwrap(),
+            },
+            ..Default::default()
+        };
+        init_db(&mut settings.database.conn).unwrap();
+        for wpm in [60., 70., 80.] {
+            settings.test_cfg.test_summary.net_wpm = wpm;
+            settings.save_run_to_database();
+        }
+
+        let mut app = App {
+            settings,
+            ..App::setup()
+        };
+
+        // reachable straight from the test
+        press(&mut app, KeyCode::F(3));
+        let history_handler: KeyHandler = handle;
+        assert_eq!(app.key_handler as usize, history_handler as usize);
+        assert_eq!(app.settings.history.items.len(), 3);
+        assert_eq!(app.settings.history.state.selected(), Some(0));
+        assert!((app.settings.history.get_item().wpm - 80.).abs() < f64::EPSILON);
+
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.settings.history.state.selected(), Some(2));
+        // wraps around
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.settings.history.state.selected(), Some(0));
+        press(&mut app, KeyCode::Char('k'));
+        assert_eq!(app.settings.history.state.selected(), Some(2));
+
+        // draws without a real terminal
+        let mut terminal = tui::Terminal::new(tui::backend::TestBackend::new(80, 20)).unwrap();
+        app.paint(&mut terminal);
+
+        press(&mut app, KeyCode::Esc);
+        let settings_handler: KeyHandler = handlers::settings::handle;
+        assert_eq!(app.key_handler as usize, settings_handler as usize);
+        assert!(app.is_alive);
+    }
+}
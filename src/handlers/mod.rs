@@ -1,8 +1,18 @@
+pub mod countdown;
+pub mod history;
 pub mod post;
 pub mod settings;
+pub mod theme_editor;
 pub mod typer;
 
 use crate::application::App;
 use crossterm::event::KeyEvent;
 
 pub type KeyHandler = fn(KeyEvent, &mut App);
+
+/// Called on every iteration of the main loop
+/// regardless of whether any event came in
+pub type Ticker = fn(&mut App);
+
+/// Ticker for screens that don't need to do anything
+pub fn idle(_app: &mut App) {}
@@ -1,29 +1,91 @@
 use crate::application::App;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crate::handlers;
+use crate::keys::Action;
+use crossterm::event::{KeyCode, KeyEvent};
 
 pub fn handle(key: KeyEvent, app: &mut App) {
-    match key.code {
-        KeyCode::Esc => app.stop(),
+    // whatever happens next the next test doesn't start by itself
+    app.advance_at = None;
+    app.ticker = handlers::idle;
 
-        KeyCode::Tab => {
+    match app.keys.action(&key) {
+        Some(Action::Quit) => return app.stop(),
+        Some(Action::Restart) => {
             app.reset_test();
-            app.change_to_test();
+            return app.change_to_test();
         }
+        // esc quits from here, the settings are under s
+        Some(Action::Settings) | None => (),
+    }
 
-        KeyCode::Char(c) => {
-            if let KeyModifiers::CONTROL = key.modifiers {
-                if c == 'c' {
-                    app.stop();
-                }
-            }
+    match key.code {
+        KeyCode::Esc => app.stop(),
 
-            match c {
-                'q' => app.stop(),
-                's' => app.change_to_settings(),
-                _ => (),
+        KeyCode::Char(c) => match c {
+            'q' => app.stop(),
+            's' => app.change_to_settings(),
+            'h' => app.change_to_history(),
+            'r' => {
+                app.replay_test();
+                app.change_to_test();
             }
-        }
+            _ => (),
+        },
 
         _ => (),
     }
 }
+
+/// starts the next test once the results were up for long enough,
+/// repaints meanwhile so the seconds left keep going down
+pub fn tick(app: &mut App) {
+    app.dirty = true;
+    if app.advance_left() == Some(0) {
+        app.reset_test();
+        app.change_to_test();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::{typer, KeyHandler};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_results_wait_then_advance() {
+        let mut app = App::setup();
+        // nothing to save from here
+        app.warmup = true;
+        app.auto_advance = Some(Duration::from_secs(5));
+        app.test.start_on_text("ab");
+        app.feed_keys(&[
+            KeyEvent::from(KeyCode::Char('a')),
+            KeyEvent::from(KeyCode::Char('b')),
+        ]);
+
+        // finished on the results screen, the typed test is still there
+        let post_handler: KeyHandler = handle;
+        assert_eq!(app.key_handler as usize, post_handler as usize);
+        assert_eq!(app.advance_left(), Some(5));
+        tick(&mut app);
+        assert_eq!(app.key_handler as usize, post_handler as usize);
+        assert!(!app.test.first);
+
+        app.advance_at = Some(Instant::now());
+        tick(&mut app);
+        let test_handler: KeyHandler = typer::handle;
+        assert_eq!(app.key_handler as usize, test_handler as usize);
+        assert!(app.test.first);
+
+        // a key on the results screen stops the timer
+        app.test.start_on_text("ab");
+        app.feed_keys(&[
+            KeyEvent::from(KeyCode::Char('a')),
+            KeyEvent::from(KeyCode::Char('b')),
+        ]);
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('x')));
+        assert!(app.advance_at.is_none());
+        assert_eq!(app.key_handler as usize, post_handler as usize);
+    }
+}
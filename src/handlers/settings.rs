@@ -1,49 +1,248 @@
 use crate::application::App;
+use crate::keys::Action;
+use crate::settings::{ListEdit, SetList};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
 pub fn handle(key: KeyEvent, app: &mut App) {
-    match key.code {
-        KeyCode::Esc => {
-            if app.settings.escape() {
-                app.stop();
+    if app.settings.list_edit.is_some() {
+        return handle_list_edit(key, app);
+    }
+
+    // typing while in the tests list filters it
+    if app.settings.active == SetList::Test && key.modifiers != KeyModifiers::CONTROL {
+        match key.code {
+            KeyCode::Char(c) => return app.settings.push_test_query(c),
+            KeyCode::Backspace => return app.settings.pop_test_query(),
+            KeyCode::Esc if !app.settings.test_query.is_empty() => {
+                return app.settings.clear_test_query()
             }
+            _ => (),
         }
+    }
 
-        KeyCode::Tab => {
+    match app.keys.action(&key) {
+        Some(Action::Quit) => return app.stop(),
+        Some(Action::Restart) => {
             app.reset_test();
-            app.change_to_test();
+            return app.change_to_test();
         }
+        // esc steps back through the lists here
+        Some(Action::Settings) | None => (),
+    }
 
-        KeyCode::Char(c) => {
-            if let KeyModifiers::CONTROL = key.modifiers {
-                if c == 'c' {
-                    app.stop();
-                    return;
-                }
-            }
+    match key.code {
+        // escape with nothing left to step back from leaves
+        KeyCode::Esc if app.settings.escape() => app.stop(),
 
-            match c {
-                'h' => app.settings.left(),
-                'j' => app.settings.down(),
-                'k' => app.settings.up(),
-                'l' => app.settings.right(),
+        // ctrl so they don't end up in the tests filter
+        KeyCode::Char('d') if key.modifiers == KeyModifiers::CONTROL => app.settings.begin_delete(),
+        KeyCode::Char('r') if key.modifiers == KeyModifiers::CONTROL => app.settings.begin_rename(),
 
-                's' => app.settings.enter(),
-                'd' => {
-                    app.settings.escape();
-                }
+        KeyCode::Char(c) => match c {
+            'h' => app.settings.left(),
+            'j' => app.settings.down(),
+            'k' => app.settings.up(),
+            'l' => app.settings.right(),
 
-                'q' => app.stop(),
-                _ => {}
+            's' => {
+                app.settings.enter();
+                app.remember_test();
             }
-        }
+            'd' => {
+                app.settings.escape();
+            }
+
+            'e' => app.change_to_theme_editor(),
+
+            '+' | '>' | '-' | '<' => {
+                app.settings.bump(matches!(c, '+' | '>'));
+                app.remember_test();
+            }
+
+            'q' => app.stop(),
+            _ => {}
+        },
 
         KeyCode::Left => app.settings.left(),
         KeyCode::Down => app.settings.down(),
         KeyCode::Up => app.settings.up(),
         KeyCode::Right => app.settings.right(),
 
-        KeyCode::Enter => app.settings.enter(),
+        KeyCode::Enter => {
+            app.settings.enter();
+            app.remember_test();
+        }
         _ => (),
     }
 }
+
+/// Keys while a word list waits to be deleted or renamed,
+/// anything that doesn't confirm backs out
+fn handle_list_edit(key: KeyEvent, app: &mut App) {
+    if app.keys.is(Action::Quit, &key) {
+        return app.stop();
+    }
+
+    let renaming = matches!(app.settings.list_edit, Some(ListEdit::Rename { .. }));
+    match key.code {
+        KeyCode::Enter => {
+            app.settings.confirm_list_edit();
+            app.remember_test();
+        }
+        KeyCode::Char('y') if !renaming => {
+            app.settings.confirm_list_edit();
+            app.remember_test();
+        }
+        KeyCode::Char(c) if renaming => app.settings.push_list_edit(c),
+        KeyCode::Backspace if renaming => app.settings.pop_list_edit(),
+        _ => app.settings.list_edit = None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(app: &mut App, code: KeyCode) {
+        handle(KeyEvent::from(code), app);
+    }
+
+    #[test]
+    fn test_vim_keys_match_arrows() {
+        let mut vim = App::default();
+        let mut arrows = App::default();
+
+        let pairs = [
+            ('j', KeyCode::Down),
+            ('l', KeyCode::Right),
+            ('k', KeyCode::Up),
+            ('h', KeyCode::Left),
+            ('j', KeyCode::Down),
+            ('j', KeyCode::Down),
+            ('l', KeyCode::Right),
+            ('k', KeyCode::Up),
+            // off the tests list where letters are a filter
+            ('k', KeyCode::Up),
+        ];
+
+        for (c, code) in pairs {
+            press(&mut vim, KeyCode::Char(c));
+            press(&mut arrows, code);
+            assert!(vim.settings.hovered == arrows.settings.hovered);
+        }
+
+        // s and Enter both select, d and Esc both deselect
+        press(&mut vim, KeyCode::Char('s'));
+        press(&mut arrows, KeyCode::Enter);
+        assert!(vim.settings.hovered == SetList::Nil);
+        assert!(vim.settings.active == arrows.settings.active);
+
+        press(&mut vim, KeyCode::Char('d'));
+        press(&mut arrows, KeyCode::Esc);
+        assert!(vim.settings.active == SetList::Nil);
+        assert!(vim.settings.hovered == arrows.settings.hovered);
+        assert!(arrows.is_alive);
+
+        // escape with nothing selected leaves
+        press(&mut arrows, KeyCode::Esc);
+        assert!(!arrows.is_alive);
+    }
+
+    #[test]
+    fn test_typing_filters_tests() {
+        let mut app = App::default();
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert!(app.settings.active == SetList::Test);
+
+        // q and d are part of the query here
+        for c in "qd".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        assert!(app.is_alive);
+        assert_eq!(app.settings.test_query, "qd");
+
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(app.settings.test_query, "q");
+
+        // first escape clears the query, second one leaves the list
+        press(&mut app, KeyCode::Esc);
+        assert!(app.settings.test_query.is_empty());
+        assert!(app.settings.active == SetList::Test);
+        press(&mut app, KeyCode::Esc);
+        assert!(app.settings.active == SetList::Nil);
+        assert!(app.is_alive);
+    }
+
+    #[test]
+    fn test_list_edit_keys() {
+        let mut app = App::default();
+        app.settings.active = SetList::Test;
+        app.settings.tests_list.items = vec!["english".to_string(), "#!script".to_string()];
+        app.settings.tests_list.state.select(Some(0));
+
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        handle(ctrl('r'), &mut app);
+        for c in "-1k".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(
+            app.settings.list_edit,
+            Some(ListEdit::Rename {
+                from: "english".to_string(),
+                to: "english-1".to_string()
+            })
+        );
+        // anything but enter backs out and the list keeps its name
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.settings.list_edit, None);
+        assert!(app.settings.test_query.is_empty());
+
+        handle(ctrl('d'), &mut app);
+        assert_eq!(
+            app.settings.list_edit,
+            Some(ListEdit::Delete("english".to_string()))
+        );
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.settings.list_edit, None);
+
+        // scripts aren't word lists
+        app.settings.tests_list.state.select(Some(1));
+        handle(ctrl('d'), &mut app);
+        assert_eq!(app.settings.list_edit, None);
+        assert!(app.is_alive);
+    }
+
+    #[test]
+    fn test_plus_bumps_word_pool() {
+        let mut app = App::default();
+        app.settings.hovered = SetList::Frequency;
+        app.settings.frequency_list.state.select(Some(0));
+
+        press(&mut app, KeyCode::Char('+'));
+        let list = &app.settings.frequency_list;
+        assert_eq!(list.state.selected(), Some(1));
+        assert_eq!(app.settings.test_cfg.word_pool.to_string(), list.items[1]);
+        // the hovered list stays as it was
+        assert!(app.settings.hovered == SetList::Frequency);
+        assert!(app.settings.active == SetList::Nil);
+
+        press(&mut app, KeyCode::Char('<'));
+        let list = &app.settings.frequency_list;
+        assert_eq!(app.settings.test_cfg.word_pool.to_string(), list.items[0]);
+        // the start doesn't wrap around
+        press(&mut app, KeyCode::Char('-'));
+        assert_eq!(app.settings.frequency_list.state.selected(), Some(0));
+
+        // anywhere else it's the length
+        app.settings.hovered = SetList::Mods;
+        app.settings.length_list.state.select(Some(0));
+        press(&mut app, KeyCode::Char('>'));
+        assert_eq!(app.settings.length_list.state.selected(), Some(1));
+        assert_eq!(
+            app.settings.test_cfg.length.to_string(),
+            app.settings.length_list.items[1]
+        );
+    }
+}
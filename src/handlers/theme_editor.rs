@@ -0,0 +1,90 @@
+use crate::application::App;
+use crate::colorscheme::Channel;
+use crate::keys::Action;
+use crossterm::event::{KeyCode, KeyEvent};
+
+pub fn handle(key: KeyEvent, app: &mut App) {
+    match app.keys.action(&key) {
+        Some(Action::Quit) => return app.stop(),
+        // unsaved changes are dropped
+        Some(Action::Settings) => {
+            app.theme_draft = None;
+            return app.change_to_settings();
+        }
+        Some(Action::Restart) | None => (),
+    }
+
+    if let KeyCode::Char('s') | KeyCode::Enter = key.code {
+        app.save_theme_draft();
+        app.theme_draft = None;
+        return app.change_to_settings();
+    }
+
+    let draft = match app.theme_draft.as_mut() {
+        Some(draft) => draft,
+        None => return app.change_to_settings(),
+    };
+
+    match key.code {
+        KeyCode::Char(c) => match c {
+            'j' => draft.next_field(),
+            'k' => draft.previous_field(),
+            'h' => draft.shift(-1),
+            'l' => draft.shift(1),
+
+            'r' => draft.pick_channel(Some(Channel::Red)),
+            'g' => draft.pick_channel(Some(Channel::Green)),
+            'b' => draft.pick_channel(Some(Channel::Blue)),
+            'n' => draft.pick_channel(None),
+            _ => (),
+        },
+
+        KeyCode::Down => draft.next_field(),
+        KeyCode::Up => draft.previous_field(),
+        KeyCode::Left => draft.shift(-1),
+        KeyCode::Right => draft.shift(1),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colorscheme::Theme;
+    use crate::handlers::{self, KeyHandler};
+    use tui::style::Color;
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_key_event(KeyEvent::from(code));
+    }
+
+    #[test]
+    fn test_theme_editor_keys() {
+        let mut app = App::default();
+        app.change_to_settings();
+        press(&mut app, KeyCode::Char('e'));
+        assert_eq!(
+            app.key_handler as usize,
+            handlers::theme_editor::handle as KeyHandler as usize
+        );
+
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Right);
+        press(&mut app, KeyCode::Char('b'));
+        press(&mut app, KeyCode::Char('l'));
+        let draft = app.theme_draft.as_ref().unwrap();
+        assert_eq!(draft.name, "custom");
+        // red moves on to green and gets a bit of blue
+        assert_eq!(draft.theme.mistake, Color::Rgb(0, 205, 8));
+        assert_eq!(draft.theme.done, Theme::default().done);
+
+        // esc leaves without touching the theme
+        press(&mut app, KeyCode::Esc);
+        assert!(app.theme_draft.is_none());
+        assert_eq!(app.settings.theme, Theme::default());
+        assert_eq!(
+            app.key_handler as usize,
+            handlers::settings::handle as KeyHandler as usize
+        );
+    }
+}
@@ -1,72 +1,211 @@
 use crate::application::App;
+use crate::keys::Action;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
 /// handles keys during test
 pub fn handle(key: KeyEvent, app: &mut App) {
+    if handle_actions(key, app) {
+        return;
+    }
+
     let test = &mut app.test;
     // well doing this in terminal was a bad idea XD
     // Ctrl + Backspace registers as weird thing in terminals
     // I got ctrl(h) and ctrl(7) among others
     // but the ctrl is always there
-    // The following code thus interprets everything with ctrl mod except ctrl+c
-    // as ctrl + backspace
+    // The following code thus interprets everything with ctrl mod
+    // except quit, ctrl+p and ctrl+z as ctrl + backspace
     // not pretty but it is what is for now
     if let KeyModifiers::CONTROL = key.modifiers {
         if let KeyCode::Char(c) = key.code {
-            if c == 'c' {
-                app.stop();
+            if c == 'p' {
+                test.toggle_pause();
+                return;
+            }
+            if c == 'z' {
+                if !test.is_paused() {
+                    test.undo_deletion();
+                }
                 return;
             }
         }
 
-        test.undo_word();
-        test.set_next_char();
-
+        if !test.is_paused() {
+            test.undo_word();
+        }
         return;
     }
 
+    // typing is ignored until the test is resumed
+    if test.is_paused() {
+        if let KeyCode::Char(_) | KeyCode::Backspace = key.code {
+            return;
+        }
+    }
+
     match key.code {
         KeyCode::Char(c) => {
-            if test.on_char(c) {
-                // TEST ENDS
-                // we summarize and write to db?
-                let summary = test.summarize();
-                app.settings.save_test_results(summary);
-
-                // app.settings.test_cfg.test_summary = summary;
-
-                // // If record is beat the historic_max_wpm but the
-                // // previous one is cached so it can be displayed in
-                // // the post screen
-                // let history_max_wpm: f64 =
-                //     app.settings.get_current_historic_max_wpm().unwrap_or(0.);
-                // app.postbox.cached_historic_wpm = history_max_wpm;
-                // let final_wpm = app.settings.test_cfg.test_summary.wpm;
-
-                // if final_wpm > history_max_wpm {
-                //     app.settings.update_historic_max_wpm(final_wpm);
-                // }
-
-                // app.settings.save_run_to_database();
-                app.change_to_post();
+            let missed = test.pmiss;
+            let test_ended = test.on_char(test.key_char(c));
+            if test.pmiss > missed && app.settings.test_cfg.bell {
+                app.ring_bell();
             }
+
+            after_keys(app, test_ended);
         }
 
         KeyCode::Backspace => test.undo_char(),
-        KeyCode::Tab => app.reset_test(),
-        KeyCode::Esc => app.change_to_settings(),
+        // endless tests only end when they're told to
+        KeyCode::Enter if test.endless && !test.first => end_test(app),
+        code => handle_function_keys(code, app),
+    }
+}
+
+/// handles keys during test in the submit layout
+/// where a line is typed out first and checked on Enter
+pub fn handle_submit(key: KeyEvent, app: &mut App) {
+    if handle_actions(key, app) {
+        return;
+    }
+
+    let test = &mut app.test;
+    if let KeyModifiers::CONTROL = key.modifiers {
+        match key.code {
+            KeyCode::Char('p') => test.toggle_pause(),
+            // same as in the other layouts anything else is ctrl + backspace
+            _ if !test.is_paused() => {
+                let kept = test.line_buffer.trim_end().rfind(' ').map_or(0, |i| i + 1);
+                test.line_buffer.truncate(kept);
+            }
+            _ => (),
+        }
+        return;
+    }
+
+    if test.is_paused() {
+        if let KeyCode::Char(_) | KeyCode::Backspace | KeyCode::Enter = key.code {
+            return;
+        }
+    }
+
+    match key.code {
+        KeyCode::Char(c) => test.push_line_char(test.key_char(c)),
+        KeyCode::Backspace => {
+            test.line_buffer.pop();
+        }
+        // an empty line finishes endless tests
+        KeyCode::Enter if test.endless && !test.first && test.line_buffer.is_empty() => {
+            end_test(app)
+        }
+        KeyCode::Enter if !test.line_buffer.is_empty() => {
+            let missed = test.pmiss;
+            let test_ended = test.submit_line();
+            if test.pmiss > missed && app.settings.test_cfg.bell {
+                app.ring_bell();
+            }
+            after_keys(app, test_ended);
+        }
+        code => handle_function_keys(code, app),
+    }
+}
+
+/// leaving, quitting and restarting, returns whether the key was taken
+fn handle_actions(key: KeyEvent, app: &mut App) -> bool {
+    if app.confirm_leave.is_some() {
+        confirm_leave(key, app);
+        return true;
+    }
+
+    match app.keys.action(&key) {
+        Some(Action::Quit) => app.stop(),
+        Some(Action::Restart) => {
+            app.reset_test();
+            // a timed test counts down again
+            app.change_to_test();
+        }
+        Some(Action::Settings) => app.leave_test(),
+        None => return false,
+    }
+    true
+}
+
+/// keys that don't type anything
+fn handle_function_keys(code: KeyCode, app: &mut App) {
+    match code {
+        KeyCode::F(2) => app.test.toggle_pause(),
+        KeyCode::F(3) => app.change_to_history(),
+        KeyCode::F(4) => app.warmup = !app.warmup,
+        KeyCode::BackTab => {
+            app.previous_test();
+            app.change_to_test();
+        }
         _ => (),
     }
 }
 
+/// restarts failed tests, ends finished ones and tops up the rest
+fn after_keys(app: &mut App, test_ended: bool) {
+    let test = &mut app.test;
+    if test.failed() {
+        app.restart_test();
+    } else if test_ended {
+        end_test(app);
+    } else {
+        test.top_up(&app.settings.test_cfg);
+    }
+}
+
+/// the settings key again or y leaves the test, quit still quits
+/// and any other key is swallowed to get back to typing
+fn confirm_leave(key: KeyEvent, app: &mut App) {
+    match app.keys.action(&key) {
+        Some(Action::Quit) => app.stop(),
+        Some(Action::Settings) => app.leave_test(),
+        _ if key.code == KeyCode::Char('y') => app.leave_test(),
+        _ => app.stay_in_test(),
+    }
+}
+
+/// samples wpm for the graph and ends timed tests once the time is up,
+/// the wpm and the clock keep changing while the test goes on so it gets repainted
+pub fn tick(app: &mut App) {
+    if !app.test.first && !app.test.is_paused() {
+        app.dirty = true;
+    }
+    app.test.update_wpm_history();
+    if app.test.time_is_up() {
+        end_test(app);
+    }
+}
+
+/// summarizes the test, writes it to db and moves on to the post screen
+fn end_test(app: &mut App) {
+    let summary = app.test.summarize();
+    let elapsed = app.test.elapsed();
+    app.run_saved = !app.warmup && app.settings.test_cfg.worth_saving(&summary, elapsed);
+    match app.run_saved {
+        // only shown on the post screen
+        false => app.settings.test_cfg.test_summary = summary,
+        true => {
+            app.settings.save_test_results(summary);
+            app.settings.save_missed_words(&app.test.missed_words);
+            app.report_result();
+        }
+    }
+    app.change_to_post();
+}
+
 // TODO i can write some db test here too I guess
 #[cfg(test)]
 mod tests {
-    use crate::application::App;
+    use crate::application::{App, TestLayout};
     use crate::database::{init::init_db, RunHistoryDatbase};
-    use crate::settings::Settings;
+    use crate::handlers::{post, KeyHandler};
+    use crate::settings::{SetList, Settings, TestMod, TestVariant};
+    use crate::typer::CharDiff;
     use crossterm::event::{KeyCode, KeyEvent};
     use rusqlite::Connection;
+    use std::collections::HashMap;
     use std::thread;
     use std::time::Duration;
 
@@ -133,6 +272,490 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_endless_ends_with_enter() {
+        let mut app = get_test_app();
+        app.settings.test_cfg.endless = true;
+        // the way picking it from the length list does
+        app.settings.cache_historic_max_wpm();
+        app.reset_test();
+        let generated = app.test.total_words;
+
+        // enter before the first key does nothing
+        app.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        assert!(app.test.first);
+
+        // way past the first batch of words
+        while app.test.total_words - app.test.words_left() <= generated {
+            app.feed_keys(&[KeyEvent::from(KeyCode::Char(app.test.current_char))]);
+        }
+        assert!(app.test.total_words > generated);
+        let test_handler: KeyHandler = super::handle;
+        assert_eq!(app.key_handler as usize, test_handler as usize);
+
+        app.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        let post_handler: KeyHandler = post::handle;
+        assert_eq!(app.key_handler as usize, post_handler as usize);
+        assert_eq!(app.settings.test_cfg.test_summary.mistakes, 0);
+    }
+
+    #[test]
+    fn test_feed_keys_types_a_quote() {
+        let mut app = get_test_app();
+        app.settings.test_cfg.variant = TestVariant::Quote;
+        app.settings.test_cfg.name = "classics".to_string();
+        app.reset_test();
+
+        let text = test_text(&app);
+        let keys: Vec<KeyEvent> = text
+            .chars()
+            .map(|c| KeyEvent::from(KeyCode::Char(c)))
+            .collect();
+        app.feed_keys(&keys);
+
+        let summary = &app.settings.test_cfg.test_summary;
+        assert_eq!(summary.mistakes, 0);
+        assert_eq!(summary.acc, 100.);
+        assert_eq!(summary.correct_chars, text.chars().count());
+        assert!(app.test.quote_author.is_some());
+        // landed on the post screen
+        let post_handler: KeyHandler = post::handle;
+        assert_eq!(app.key_handler as usize, post_handler as usize);
+    }
+
+    #[test]
+    fn test_back_tab_goes_to_previous_test() {
+        let mut app = get_test_app();
+        app.change_to_settings();
+        app.settings.active = SetList::Length;
+        for length in ["25", "60s", "50"] {
+            let at = app
+                .settings
+                .length_list
+                .items
+                .iter()
+                .position(|i| i == length);
+            app.settings.length_list.state.select(at);
+            app.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        }
+        assert_eq!(app.settings.test_cfg.length, 50);
+        app.change_to_test();
+        // switching setups leaves the rest of the settings alone
+        app.settings.test_query = "eng".to_string();
+        app.settings
+            .script_cache
+            .insert("marker".to_string(), Some(1.));
+
+        let back_tab = KeyEvent::from(KeyCode::BackTab);
+        app.handle_key_event(back_tab);
+        assert_eq!(app.settings.test_cfg.time_limit, Some(60));
+        assert_eq!(app.test.time_limit, Some(Duration::from_secs(60)));
+        let test_handler: KeyHandler = super::handle;
+        assert_eq!(app.key_handler as usize, test_handler as usize);
+
+        app.handle_key_event(back_tab);
+        assert_eq!(app.settings.test_cfg.length, 25);
+        assert_eq!(app.settings.test_cfg.time_limit, None);
+        // and around again to the latest
+        app.handle_key_event(back_tab);
+        assert_eq!(app.settings.test_cfg.length, 50);
+        assert_eq!(app.settings.test_cfg.time_limit, None);
+
+        assert_eq!(app.settings.test_query, "eng");
+        assert_eq!(app.settings.script_cache["marker"], Some(1.));
+        // still the in-memory database
+        assert_eq!(
+            app.settings.database.conn.path(),
+            Connection::open_in_memory().unwrap().path()
+        );
+    }
+
+    #[test]
+    fn test_curly_quotes_are_expected() {
+        let mut app = get_test_app();
+        let mut weights: HashMap<char, u16> = ".?!,;:(<{['-".chars().map(|c| (c, 0)).collect();
+        weights.insert('"', 500);
+        app.settings.test_cfg.punctuation_weights = Some(weights);
+        app.settings.test_cfg.seed = Some(2137);
+        app.settings.test_cfg.mods.insert(TestMod::Punctuation);
+        app.settings.test_cfg.curly_quotes = true;
+        app.settings.test_cfg.length = 100;
+        app.settings.cache_historic_max_wpm();
+        app.reset_test();
+
+        let text = test_text(&app);
+        let opener = text.find('“').expect("a test this long has quotes");
+        for c in text[..opener].chars() {
+            app.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+        }
+        assert_eq!(app.test.current_char, '“');
+        // the straight quote is just a wrong key
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('"')));
+        assert_eq!(app.test.mistakes, 1);
+    }
+
+    #[test]
+    fn test_warmup_isnt_saved() {
+        let mut app = get_test_app();
+        app.settings.cache_historic_max_wpm();
+        app.handle_key_event(KeyEvent::from(KeyCode::F(4)));
+        assert!(app.warmup);
+
+        let text = test_text(&app);
+        let keys: Vec<KeyEvent> = text
+            .chars()
+            .map(|c| KeyEvent::from(KeyCode::Char(c)))
+            .collect();
+        app.feed_keys(&keys);
+        let post_handler: KeyHandler = post::handle;
+        assert_eq!(app.key_handler as usize, post_handler as usize);
+        assert_eq!(
+            app.settings.test_cfg.test_summary.correct_chars,
+            text.chars().count()
+        );
+        assert!(app.settings.database.recent_results(10).unwrap().is_empty());
+
+        // off again the next run lands in the history
+        app.warmup = false;
+        app.reset_test();
+        app.change_to_test();
+        let text = test_text(&app);
+        let keys: Vec<KeyEvent> = text
+            .chars()
+            .map(|c| KeyEvent::from(KeyCode::Char(c)))
+            .collect();
+        app.feed_keys(&keys);
+        assert_eq!(app.settings.database.recent_results(10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_flubbed_runs_arent_saved() {
+        let mut app = get_test_app();
+        app.settings.test_cfg.save_min_acc = Some(50.);
+        app.settings.cache_historic_max_wpm();
+        app.reset_test();
+
+        // most of the letters wrong
+        let text = test_text(&app);
+        let keys: Vec<KeyEvent> = text
+            .chars()
+            .enumerate()
+            .map(|(i, c)| match i % 4 != 0 && c != ' ' {
+                true => KeyEvent::from(KeyCode::Char('ź')),
+                false => KeyEvent::from(KeyCode::Char(c)),
+            })
+            .collect();
+        app.feed_keys(&keys);
+        assert!(app.settings.test_cfg.test_summary.acc < 50.);
+        assert!(!app.run_saved);
+        assert!(app.settings.database.recent_results(10).unwrap().is_empty());
+
+        app.reset_test();
+        app.change_to_test();
+        let text = test_text(&app);
+        let keys: Vec<KeyEvent> = text
+            .chars()
+            .map(|c| KeyEvent::from(KeyCode::Char(c)))
+            .collect();
+        app.feed_keys(&keys);
+        assert!(app.run_saved);
+        assert_eq!(app.settings.database.recent_results(10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_practice_missed_words() {
+        let mut app = get_test_app();
+        app.settings.test_cfg.length = 200;
+        app.settings.test_cfg.practice = true;
+
+        // nothing missed yet, the test is generated as usual
+        app.settings.load_problem_words();
+        app.reset_test();
+        assert!(!test_text(&app).contains("quixotic"));
+
+        // a finished test with the first word mistyped
+        app.settings.test_cfg.length = 3;
+        app.settings.cache_historic_max_wpm();
+        app.reset_test();
+        let first_word = test_text(&app).split(' ').next().unwrap().to_string();
+        let mut keys = generate_key_events_passing_standart_test(&app);
+        keys[0] = KeyEvent::from(KeyCode::Char('ź'));
+        app.feed_keys(&keys);
+        let post_handler: KeyHandler = post::handle;
+        assert_eq!(app.key_handler as usize, post_handler as usize);
+        assert_eq!(app.settings.test_cfg.problem_words, vec![first_word]);
+
+        // the word missed the most is picked the most
+        let history = std::collections::HashMap::from([("quixotic".to_string(), 5)]);
+        app.settings.save_missed_words(&history);
+        app.settings.test_cfg.length = 200;
+        app.reset_test();
+        let text = test_text(&app);
+        let practiced = text.split_whitespace().filter(|w| *w == "quixotic").count();
+        // 60% of the words are split between the two missed ones
+        assert!(practiced > 40, "quixotic came up {} times", practiced);
+
+        app.settings.test_cfg.practice = false;
+        app.reset_test();
+        assert!(!test_text(&app).contains("quixotic"));
+    }
+
+    fn test_text(app: &App) -> String {
+        let mut text = String::new();
+        for line in [&app.test.active, &app.test.down]
+            .into_iter()
+            .chain(app.test.backburner.iter().rev())
+        {
+            for span in line {
+                text.push_str(&span.content);
+            }
+        }
+        text
+    }
+
+    fn wrong_key(app: &App) -> KeyEvent {
+        match app.test.current_char {
+            'ź' => KeyEvent::from(KeyCode::Char('a')),
+            _ => KeyEvent::from(KeyCode::Char('ź')),
+        }
+    }
+
+    #[test]
+    fn test_repaints_only_when_needed() {
+        let mut app = get_test_app();
+        assert!(app.dirty);
+        app.dirty = false;
+
+        // nothing changes on screen before the test starts
+        app.tick();
+        assert!(!app.dirty);
+
+        app.handle_key_event(KeyEvent::from(KeyCode::Char(app.test.current_char)));
+        assert!(app.dirty);
+        app.dirty = false;
+        // the live wpm keeps changing
+        app.tick();
+        assert!(app.dirty);
+
+        app.test.toggle_pause();
+        app.dirty = false;
+        app.tick();
+        assert!(!app.dirty);
+
+        app.handle_key_event(KeyEvent::from(KeyCode::Esc));
+        app.dirty = false;
+        app.tick();
+        assert!(!app.dirty);
+        app.resize(80);
+        assert!(app.dirty);
+    }
+
+    #[test]
+    fn test_ctrl_z_brings_back_the_word() {
+        use crossterm::event::KeyModifiers;
+
+        let mut app = get_test_app();
+        let key_events = generate_key_events_passing_standart_test(&app);
+        app.feed_keys(&key_events[..5]);
+        let done = app.test.done;
+
+        app.handle_key_event(KeyEvent::new(KeyCode::Backspace, KeyModifiers::CONTROL));
+        assert!(app.test.done < done);
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL));
+        assert_eq!(app.test.done, done);
+
+        // restarting forgets the deletions
+        app.handle_key_event(KeyEvent::new(KeyCode::Backspace, KeyModifiers::CONTROL));
+        app.restart_test();
+        assert!(app.test.undo_stack.is_empty());
+    }
+
+    #[test]
+    fn test_esc_asks_before_leaving() {
+        let settings_handler: KeyHandler = crate::handlers::settings::handle;
+        let on_settings = |app: &App| app.key_handler as usize == settings_handler as usize;
+
+        // nothing typed yet, nothing to lose
+        let mut app = get_test_app();
+        app.handle_key_event(KeyEvent::from(KeyCode::Esc));
+        assert!(on_settings(&app));
+
+        let mut app = get_test_app();
+        let key_events = generate_key_events_passing_standart_test(&app);
+        app.feed_keys(&key_events[..3]);
+        let done = app.test.done;
+        app.handle_key_event(KeyEvent::from(KeyCode::Esc));
+        assert!(!on_settings(&app));
+        assert!(app.test.is_paused());
+
+        // any other key stays, it isn't typed either
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('n')));
+        assert!(!app.test.is_paused());
+        assert_eq!(app.test.done, done);
+
+        app.handle_key_event(KeyEvent::from(KeyCode::Esc));
+        assert!(!on_settings(&app));
+        app.handle_key_event(KeyEvent::from(KeyCode::Esc));
+        assert!(on_settings(&app));
+        assert!(app.is_alive);
+    }
+
+    #[test]
+    fn test_remapped_quit() {
+        use crate::keys::KeyBind;
+        use crossterm::event::KeyModifiers;
+
+        let mut app = get_test_app();
+        app.keys.quit = KeyBind::parse("ctrl+q").unwrap();
+
+        // ctrl+c is back to deleting a word
+        app.feed_keys(&generate_key_events_passing_standart_test(&app)[..3]);
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+        assert!(app.is_alive);
+        assert_eq!(app.test.done, 0);
+
+        app.handle_key_event(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL));
+        assert!(!app.is_alive);
+
+        // other screens listen to the new key too
+        let mut app = get_test_app();
+        app.keys.quit = KeyBind::parse("f10").unwrap();
+        app.change_to_history();
+        app.handle_key_event(KeyEvent::from(KeyCode::F(10)));
+        assert!(!app.is_alive);
+    }
+
+    #[test]
+    fn test_bell_on_mistake() {
+        let mut app = get_test_app();
+        let key_events = generate_key_events_passing_standart_test(&app);
+
+        // off by default
+        app.feed_keys(&[wrong_key(&app)]);
+        assert!(!app.take_bell());
+
+        app.settings.test_cfg.bell = true;
+        app.feed_keys(&key_events[1..3]);
+        assert!(!app.take_bell());
+        app.feed_keys(&[wrong_key(&app)]);
+        assert!(app.take_bell());
+        assert!(!app.take_bell());
+
+        // a burst of mistakes rings just once
+        app.feed_keys(&[wrong_key(&app), wrong_key(&app)]);
+        assert!(!app.take_bell());
+    }
+
+    #[test]
+    fn test_hardcore_restarts_on_mistake() {
+        let mut app = get_test_app();
+        app.settings.test_cfg.max_mistakes = Some(0);
+        app.reset_test();
+        let text = test_text(&app);
+        let key_events = generate_key_events_passing_standart_test(&app);
+
+        // a short first word has its blank stepped over as well
+        app.feed_keys(&key_events[..3]);
+        assert!(app.test.done >= 3);
+        assert_eq!(app.test.pmiss, 0);
+        app.feed_keys(&[wrong_key(&app)]);
+
+        // back at the very start of the same text
+        assert_eq!(test_text(&app), text);
+        assert_eq!((app.test.done, app.test.pdone), (0, 0));
+        assert_eq!((app.test.mistakes, app.test.pmiss), (0, 0));
+        assert_eq!(app.test.cursor_x(), 1);
+        assert!(app.test.first);
+        let test_handler: KeyHandler = super::handle;
+        assert_eq!(app.key_handler as usize, test_handler as usize);
+        app.settings.load_history();
+        assert!(app.settings.history.items.is_empty());
+
+        // a clean run goes through
+        app.feed_keys(&key_events);
+        let post_handler: KeyHandler = post::handle;
+        assert_eq!(app.key_handler as usize, post_handler as usize);
+        assert_eq!(app.settings.test_cfg.test_summary.acc, 100.);
+    }
+
+    #[test]
+    fn test_hardcore_min_acc() {
+        let mut app = get_test_app();
+        app.settings.test_cfg.min_acc = Some(90.);
+        app.reset_test();
+        let key_events = generate_key_events_passing_standart_test(&app);
+
+        // early typos don't count before there are enough keystrokes
+        app.feed_keys(&[wrong_key(&app)]);
+        assert_eq!(app.test.pmiss, 1);
+        app.feed_keys(&key_events[1..12]);
+        assert_eq!(app.test.pmiss, 1);
+
+        app.feed_keys(&[wrong_key(&app)]);
+        assert_eq!(app.test.done + app.test.pdone, 0);
+        assert_eq!(app.test.pmiss, 0);
+    }
+
+    #[test]
+    fn test_json_output() {
+        use crate::application::JsonOutput;
+        use std::fs;
+
+        let mut app = get_test_app();
+        app.json_output = Some(JsonOutput::Stdout);
+        for _ in 0..2 {
+            let key_events = generate_key_events_passing_standart_test(&app);
+            app.feed_keys(&key_events);
+            app.handle_key_event(KeyEvent::from(KeyCode::Tab));
+        }
+        assert_eq!(app.json_reports.len(), 2);
+        let report: serde_json::Value = serde_json::from_str(&app.json_reports[0]).unwrap();
+        assert_eq!(report["summary"]["mistakes"], 0);
+        assert_eq!(report["length"], app.settings.test_cfg.length);
+
+        let path = std::env::temp_dir().join("smokey_test_json_output.jsonl");
+        let _ = fs::remove_file(&path);
+        app.json_output = Some(JsonOutput::File(path.clone()));
+        for _ in 0..2 {
+            let key_events = generate_key_events_passing_standart_test(&app);
+            app.feed_keys(&key_events);
+            app.handle_key_event(KeyEvent::from(KeyCode::Tab));
+        }
+        // file output doesn't wait for the app to quit
+        assert_eq!(app.json_reports.len(), 2);
+        let written = fs::read_to_string(&path).unwrap();
+        assert_eq!(written.lines().count(), 2);
+        for line in written.lines() {
+            let report: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(report["name"], "english");
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_replay_uses_the_same_text() {
+        let mut app = get_test_app();
+        let text = test_text(&app);
+        let key_events = generate_key_events_passing_standart_test(&app);
+        app.feed_keys(&key_events);
+        let net_wpm = app.settings.test_cfg.test_summary.net_wpm;
+
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('r')));
+        assert_eq!(test_text(&app), text);
+        assert_eq!(app.test.done, 0);
+        assert_eq!(app.test.previous_wpm, Some(net_wpm));
+
+        // the replayed text can be typed through again
+        app.feed_keys(&key_events);
+        let post_handler: KeyHandler = post::handle;
+        assert_eq!(app.key_handler as usize, post_handler as usize);
+        assert_eq!(app.settings.test_cfg.test_summary.mistakes, 0);
+
+        // a new test isn't compared to anything
+        app.handle_key_event(KeyEvent::from(KeyCode::Tab));
+        assert_eq!(app.test.previous_wpm, None);
+    }
+
     #[test]
     fn go_thorugh_test_five_times() {
         go_thorugh_test_n_times(5)
@@ -176,7 +799,7 @@ mod tests {
             app.handle_key_event(kv);
         }
 
-        let final_wpm = app.test.summarize().wpm;
+        let final_wpm = app.test.summarize().net_wpm;
 
         // final_wmp can be lower by a thin margin given in the toleranca variable
         // than the requested wpm, but it can never be higher
@@ -250,4 +873,108 @@ mod tests {
         }
         assert_eq!(app.test.done, 0);
     }
+
+    // Timed tests
+
+    fn get_timed_test_app<'a>(limit: Duration) -> App<'a> {
+        let mut app = get_test_app();
+        // select 15s through the settings so the max wpm gets cached
+        app.settings.hovered = SetList::Length;
+        app.settings.enter();
+        let idx = app
+            .settings
+            .length_list
+            .items
+            .iter()
+            .position(|i| i == "15s");
+        app.settings.length_list.state.select(idx);
+        app.settings.enter();
+        assert_eq!(app.settings.test_cfg.time_limit, Some(15));
+
+        app.reset_test();
+        app.test.time_limit = Some(limit);
+        app
+    }
+
+    #[test]
+    fn test_timed_test_ends_on_tick() {
+        let mut app = get_timed_test_app(Duration::from_millis(300));
+        let klucznik_ptr = app.key_handler as usize;
+
+        // the clock doesn't start before the first key
+        thread::sleep(Duration::from_millis(350));
+        app.tick();
+        assert_eq!(klucznik_ptr, app.key_handler as usize);
+
+        app.handle_key_event(KeyEvent::from(KeyCode::Char(app.test.current_char)));
+        app.tick();
+        assert_eq!(klucznik_ptr, app.key_handler as usize);
+
+        thread::sleep(Duration::from_millis(350));
+        app.tick();
+        assert_ne!(klucznik_ptr, app.key_handler as usize);
+        assert_eq!(app.settings.test_cfg.test_summary.correct_chars, 1);
+    }
+
+    #[test]
+    fn test_timed_test_tops_up() {
+        let mut app = get_timed_test_app(Duration::from_secs(600));
+        let klucznik_ptr = app.key_handler as usize;
+        let key_events = generate_key_events_passing_standart_test(&app);
+        let initial_len = key_events.len();
+
+        for kv in key_events {
+            app.handle_key_event(kv);
+        }
+
+        // typing the whole initial batch doesn't end the test
+        assert_eq!(klucznik_ptr, app.key_handler as usize);
+        assert_eq!(
+            app.test.pdone + app.test.done - app.test.blanks,
+            initial_len
+        );
+        assert!(!app.test.down.is_empty());
+    }
+
+    #[test]
+    fn test_replay_timed_test_keeps_topped_up_text() {
+        let mut app = get_timed_test_app(Duration::from_secs(600));
+        let text = test_text(&app);
+        let key_events = generate_key_events_passing_standart_test(&app);
+        app.feed_keys(&key_events);
+
+        app.replay_test();
+        let replayed = test_text(&app);
+        assert!(replayed.starts_with(&format!("{} ", text)));
+        assert!(replayed.len() > text.len() + 1);
+    }
+
+    #[test]
+    fn test_submit_lines() {
+        let mut app = get_test_app();
+        app.warmup = true;
+        app.layout = TestLayout::Submit;
+        app.change_to_test();
+        app.test.start_on_text("the cat sat");
+        app.test.set_line_width(Some(8));
+        assert_eq!(app.test.active_line(), "the cat ");
+
+        let submit = |app: &mut App, line: &str| {
+            for c in line.chars() {
+                app.handle_key_event(KeyEvent::from(KeyCode::Char(c)));
+            }
+            app.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        };
+        // Enter counts as the space at the end of the line
+        submit(&mut app, "the cxt");
+        assert_eq!(app.test.active_line(), "sat");
+        assert_eq!(app.test.last_line[5], CharDiff::Wrong('a', 'x'));
+
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('q')));
+        app.handle_key_event(KeyEvent::from(KeyCode::Backspace));
+        submit(&mut app, "sat");
+        let post_handler: KeyHandler = post::handle;
+        assert_eq!(app.key_handler as usize, post_handler as usize);
+        assert_eq!(app.settings.test_cfg.test_summary.mistakes, 1);
+    }
 }
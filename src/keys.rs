@@ -0,0 +1,172 @@
+//! Control keys that can be rebound in the [keys] table of smokey.toml
+//! typing itself always goes through the plain chars
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Actions that can be rebound
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Quit,
+    Restart,
+    Settings,
+}
+
+/// A key with the modifiers that have to be held along
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyBind {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBind {
+    pub const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    /// Parses keys written like "ctrl+c", "alt+r", "tab", "esc" or "f5"
+    /// ```
+    /// use crossterm::event::{KeyCode, KeyModifiers};
+    /// use smokey::keys::KeyBind;
+    ///
+    /// let bind = KeyBind::parse("Ctrl+q").unwrap();
+    /// assert_eq!(bind, KeyBind::new(KeyCode::Char('q'), KeyModifiers::CONTROL));
+    /// assert_eq!(KeyBind::parse("f5").unwrap().code, KeyCode::F(5));
+    /// assert!(KeyBind::parse("ctrl+").is_none());
+    /// ```
+    pub fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim().to_lowercase();
+        let mut parts: Vec<&str> = raw.split('+').map(str::trim).collect();
+        let key = parts.pop()?;
+
+        let mut modifiers = KeyModifiers::NONE;
+        for part in parts {
+            modifiers |= match part {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return None,
+            };
+        }
+
+        let code = match key {
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "enter" => KeyCode::Enter,
+            "backspace" => KeyCode::Backspace,
+            "space" => KeyCode::Char(' '),
+            _ if key.starts_with('f') && key.len() > 1 => KeyCode::F(key[1..].parse().ok()?),
+            _ => {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => return None,
+                }
+            }
+        };
+
+        Some(Self::new(code, modifiers))
+    }
+
+    /// Whether the key event is this key, shift doesn't matter for chars
+    /// since the char itself already tells whether it was held
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        match (self.code, key.code) {
+            (KeyCode::Char(bound), KeyCode::Char(pressed)) => {
+                bound.eq_ignore_ascii_case(&pressed)
+                    && self.modifiers - KeyModifiers::SHIFT == key.modifiers - KeyModifiers::SHIFT
+            }
+            (bound, pressed) => bound == pressed && self.modifiers == key.modifiers,
+        }
+    }
+}
+
+/// Keys bound to the actions, unbound ones keep the defaults
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyBindings {
+    pub quit: KeyBind,
+    pub restart: KeyBind,
+    pub settings: KeyBind,
+}
+
+impl KeyBindings {
+    pub fn get(&self, action: Action) -> KeyBind {
+        match action {
+            Action::Quit => self.quit,
+            Action::Restart => self.restart,
+            Action::Settings => self.settings,
+        }
+    }
+
+    pub fn set(&mut self, action: Action, bind: KeyBind) {
+        match action {
+            Action::Quit => self.quit = bind,
+            Action::Restart => self.restart = bind,
+            Action::Settings => self.settings = bind,
+        }
+    }
+
+    /// Whether the key triggers the action
+    pub fn is(&self, action: Action, key: &KeyEvent) -> bool {
+        self.get(action).matches(key)
+    }
+
+    /// The action the key is bound to if any
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        [Action::Quit, Action::Restart, Action::Settings]
+            .into_iter()
+            .find(|&action| self.is(action, key))
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            quit: KeyBind::new(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            restart: KeyBind::new(KeyCode::Tab, KeyModifiers::NONE),
+            settings: KeyBind::new(KeyCode::Esc, KeyModifiers::NONE),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_keys() {
+        let ctrl = KeyModifiers::CONTROL;
+        assert_eq!(
+            KeyBind::parse("ctrl + alt + x"),
+            Some(KeyBind::new(KeyCode::Char('x'), ctrl | KeyModifiers::ALT))
+        );
+        assert_eq!(
+            KeyBind::parse("Esc"),
+            Some(KeyBind::new(KeyCode::Esc, KeyModifiers::NONE))
+        );
+        assert_eq!(KeyBind::parse("f").unwrap().code, KeyCode::Char('f'));
+        assert!(KeyBind::parse("hyper+x").is_none());
+        assert!(KeyBind::parse("fx").is_none());
+        assert!(KeyBind::parse("").is_none());
+    }
+
+    #[test]
+    fn test_matches() {
+        let quit = KeyBind::parse("ctrl+q").unwrap();
+        assert!(quit.matches(&KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL)));
+        assert!(quit.matches(&KeyEvent::new(
+            KeyCode::Char('Q'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT
+        )));
+        assert!(!quit.matches(&KeyEvent::from(KeyCode::Char('q'))));
+        assert!(!quit.matches(&KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)));
+
+        let keys = KeyBindings::default();
+        assert!(keys.is(Action::Restart, &KeyEvent::from(KeyCode::Tab)));
+        assert!(!keys.is(Action::Settings, &KeyEvent::from(KeyCode::Tab)));
+        assert_eq!(
+            keys.action(&KeyEvent::from(KeyCode::Esc)),
+            Some(Action::Settings)
+        );
+        assert_eq!(keys.action(&KeyEvent::from(KeyCode::Enter)), None);
+    }
+}
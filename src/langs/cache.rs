@@ -0,0 +1,538 @@
+//! Keeps the word pool of the last standard test in memory
+//! so restarting the same test doesn't reread the word file
+
+use crate::settings::{TypingTestConfig, WordWeighting};
+use crate::storage::{self, StorageError};
+use fastrand::Rng as FastRng;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default)]
+pub struct WordCache {
+    // the path follows the test name
+    words_file: PathBuf,
+    word_pool: usize,
+    words: Vec<String>,
+    // pool of the word list mixed in
+    mix: Option<Box<WordCache>>,
+}
+
+impl WordCache {
+    /// Random words for the test, the word file is only read
+    /// when the test name or word_pool changed since the last call
+    /// a word file that can't be used is swapped for english,
+    /// storage without english gets the built-in copy of it
+    pub fn shuffled_words(&mut self, config: &TypingTestConfig) -> Vec<String> {
+        let english = storage::get_word_list_path("english");
+        let mut words = match self.shuffled_for(&config.get_words_file_path(), config) {
+            Ok(words) => words,
+            Err(e) => {
+                warn!("{}, using english", e);
+                self.shuffled_for(&english, config).unwrap_or_else(|e| {
+                    warn!("{}, using the built-in english", e);
+                    self.cache_builtin_english(&english, config.word_pool);
+                    self.shuffled_for(&english, config).unwrap_or_default()
+                })
+            }
+        };
+        if let Some(mix) = &config.mix {
+            words = self.mixed_in(words, &storage::get_word_list_path(mix), config);
+        }
+        // the pool keeps the casing so turning it off doesn't need a reread
+        if config.lowercase {
+            for word in words.iter_mut() {
+                *word = word.to_lowercase();
+            }
+        }
+        words
+    }
+
+    /// Swaps each word for one from the mix file with a chance of mix_ratio,
+    /// the mix gets its own word_pool cut and a missing mix leaves the words as they are
+    fn mixed_in(
+        &mut self,
+        words: Vec<String>,
+        mix_file: &Path,
+        config: &TypingTestConfig,
+    ) -> Vec<String> {
+        if config.mix_ratio <= 0. {
+            return words;
+        }
+        let mix = self.mix.get_or_insert_with(Box::default);
+        if !mix.is_cached(mix_file, config.word_pool) && !storage::is_valid_word_list(mix_file) {
+            warn!("can't mix in {:?}, it isn't a word list", mix_file);
+            return words;
+        }
+
+        // seeded tests stay the same but don't pick the same spots from both lists
+        let mixed = match mix.shuffled_words_within(
+            mix_file,
+            words.len(),
+            config.word_pool,
+            config.seed.map(|seed| seed.wrapping_add(1)),
+            config.word_len_range(),
+            config.word_weighting,
+        ) {
+            Ok(mixed) => mixed,
+            Err(e) => {
+                warn!("can't mix in {:?}: {}", mix_file, e);
+                return words;
+            }
+        };
+        let rng = match config.seed {
+            Some(seed) => FastRng::with_seed(seed.wrapping_add(2)),
+            None => FastRng::new(),
+        };
+        words
+            .into_iter()
+            .zip(mixed)
+            .map(|(word, other)| match rng.f64() < config.mix_ratio {
+                true => other,
+                false => word,
+            })
+            .collect()
+    }
+
+    fn shuffled_for(
+        &mut self,
+        words_file: &Path,
+        config: &TypingTestConfig,
+    ) -> Result<Vec<String>, StorageError> {
+        self.shuffled_words_within(
+            words_file,
+            config.words_to_generate(),
+            config.word_pool,
+            config.seed,
+            config.word_len_range(),
+            config.word_weighting,
+        )
+    }
+
+    /// caches the pool of the english list built into the binary under its storage path
+    fn cache_builtin_english(&mut self, english: &Path, word_pool: usize) {
+        self.words =
+            read_pool_from(storage::FALLBACK_ENGLISH, english, word_pool).unwrap_or_default();
+        self.words_file = english.to_path_buf();
+        self.word_pool = word_pool;
+    }
+
+    pub fn is_cached(&self, words_file: &Path, word_pool: usize) -> bool {
+        !self.words.is_empty() && self.words_file == words_file && self.word_pool == word_pool
+    }
+
+    /// only words with a char count in word_len are picked,
+    /// word_pool still cuts off the file before they're filtered
+    fn shuffled_words_within(
+        &mut self,
+        words_file: &Path,
+        amount: usize,
+        word_pool: usize,
+        seed: Option<u64>,
+        word_len: RangeInclusive<usize>,
+        weighting: WordWeighting,
+    ) -> Result<Vec<String>, StorageError> {
+        if !self.is_cached(words_file, word_pool) {
+            self.words = read_word_pool(words_file, word_pool)?;
+            self.words_file = words_file.to_path_buf();
+            self.word_pool = word_pool;
+        }
+
+        let rng = match seed {
+            Some(seed) => FastRng::with_seed(seed),
+            None => FastRng::new(),
+        };
+
+        let mut fitting: Vec<&String> = self
+            .words
+            .iter()
+            .filter(|w| word_len.contains(&w.chars().count()))
+            .collect();
+        if fitting.is_empty() {
+            warn!(
+                "no words in {:?} are {:?} chars long, ignoring the word length",
+                words_file, word_len
+            );
+            fitting = self.words.iter().collect();
+        }
+
+        // picks repeat when there's less words than the test needs
+        if let WordWeighting::Uniform = weighting {
+            return Ok((0..amount)
+                .map(|_| fitting[rng.usize(..fitting.len())].clone())
+                .collect());
+        }
+
+        let cumulative = cumulative_weights(fitting.len(), weighting);
+        let total = cumulative[cumulative.len() - 1];
+        Ok((0..amount)
+            .map(|_| {
+                let target = rng.f64() * total;
+                let i = cumulative.partition_point(|&w| w <= target);
+                fitting[i.min(fitting.len() - 1)].clone()
+            })
+            .collect())
+    }
+}
+
+/// running sum of weights of words ranked from the most common one
+fn cumulative_weights(len: usize, weighting: WordWeighting) -> Vec<f64> {
+    let weight = |rank: usize| match weighting {
+        WordWeighting::Uniform => 1.,
+        WordWeighting::Linear => (len - rank) as f64,
+        WordWeighting::Zipf => 1. / (rank + 1) as f64,
+    };
+
+    (0..len)
+        .scan(0., |sum, rank| {
+            *sum += weight(rank);
+            Some(*sum)
+        })
+        .collect()
+}
+
+/// first word_pool words of the file, blank lines and lines that aren't utf-8 are skipped
+/// word_pool can exceed the length of a small custom list,
+/// a file without a single usable line is an error
+fn read_word_pool(words_file: &Path, word_pool: usize) -> Result<Vec<String>, StorageError> {
+    let file = File::open(words_file).map_err(|e| StorageError::from_io(words_file, e))?;
+    read_pool_from(BufReader::new(file), words_file, word_pool)
+}
+
+/// read_word_pool from any reader, words_file is only for the errors
+fn read_pool_from(
+    mut reader: impl BufRead,
+    words_file: &Path,
+    word_pool: usize,
+) -> Result<Vec<String>, StorageError> {
+    let mut garbled = 0;
+    let mut words: Vec<String> = vec![];
+
+    // single pass with one buffer, only the lines that are kept get allocated
+    let mut buf: Vec<u8> = Vec::new();
+    while words.len() < word_pool.max(1) {
+        buf.clear();
+        let read = reader
+            .read_until(b'\n', &mut buf)
+            .map_err(|e| StorageError::from_io(words_file, e))?;
+        if read == 0 {
+            break;
+        }
+        match line_to_word(&buf) {
+            Some(word) if !word.trim().is_empty() => words.push(word.to_string()),
+            Some(_) => (),
+            None => garbled += 1,
+        }
+    }
+    if garbled > 0 {
+        warn!(
+            "skipped {} lines of {:?} that aren't utf-8",
+            garbled, words_file
+        );
+    }
+
+    match (words.is_empty(), garbled > 0) {
+        (false, _) => Ok(words),
+        (true, true) => Err(StorageError::NotUtf8(words_file.to_path_buf())),
+        (true, false) => Err(StorageError::NoWords(words_file.to_path_buf())),
+    }
+}
+
+/// the line without its line ending, None if it isn't utf-8
+fn line_to_word(line: &[u8]) -> Option<&str> {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    std::str::from_utf8(line).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const ANY_LEN: RangeInclusive<usize> = 0..=usize::MAX;
+
+    fn temp_words(test_name: &str, contents: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(test_name);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("words");
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_word_pool_exceeds_file() {
+        let path = temp_words(
+            "smokey_test_word_pool_exceeds_file",
+            "one\ntwo\nthree\nfour\nfive\n",
+        );
+
+        let words = WordCache::default()
+            .shuffled_words_within(&path, 100, 5000, None, ANY_LEN, WordWeighting::Uniform)
+            .unwrap();
+        assert_eq!(words.len(), 100);
+        let known = ["one", "two", "three", "four", "five"];
+        assert!(words.iter().all(|w| known.contains(&w.as_str())));
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_mix_word_lists() {
+        let path = temp_words("smokey_test_mix_word_lists", "uno\ndos\ntres\n");
+        let words = || vec!["one".to_string(); 200];
+        let mut config = TypingTestConfig {
+            seed: Some(7),
+            mix_ratio: 0.5,
+            ..Default::default()
+        };
+        let mut cache = WordCache::default();
+
+        let mixed = cache.mixed_in(words(), &path, &config);
+        assert_eq!(mixed.len(), 200);
+        let ones = mixed.iter().filter(|w| *w == "one").count();
+        assert!(
+            (50..150).contains(&ones),
+            "{} of 200 from the first list",
+            ones
+        );
+        assert!(mixed
+            .iter()
+            .all(|w| ["one", "uno", "dos", "tres"].contains(&w.as_str())));
+        // the same seed mixes the same way
+        assert_eq!(cache.mixed_in(words(), &path, &config), mixed);
+
+        config.mix_ratio = 1.;
+        assert!(cache
+            .mixed_in(words(), &path, &config)
+            .iter()
+            .all(|w| w != "one"));
+        config.mix_ratio = 0.;
+        assert_eq!(cache.mixed_in(words(), &path, &config), words());
+
+        config.mix_ratio = 0.5;
+        let missing = path.with_file_name("missing");
+        assert_eq!(cache.mixed_in(words(), &missing, &config), words());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_lowercase_words() {
+        let mut config = TypingTestConfig {
+            length: 50,
+            word_pool: 3,
+            ..Default::default()
+        };
+        let mut cache = WordCache {
+            words_file: config.get_words_file_path(),
+            word_pool: config.word_pool,
+            words: vec!["Paris".to_string(), "the".to_string(), "ÉLAN".to_string()],
+            mix: None,
+        };
+        assert!(cache.shuffled_words(&config).iter().any(|w| w == "Paris"));
+
+        config.lowercase = true;
+        let words = cache.shuffled_words(&config);
+        assert!(words.iter().any(|w| w == "élan"));
+        assert!(words.iter().all(|w| !w.chars().any(char::is_uppercase)));
+    }
+
+    #[test]
+    fn test_invalid_utf8_lines() {
+        let dir = std::env::temp_dir().join("smokey_test_invalid_utf8_lines");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("words");
+        fs::write(&path, b"one\n\xff\xfeoops\ntwo\nthree").unwrap();
+
+        let words = WordCache::default()
+            .shuffled_words_within(&path, 100, 5000, None, ANY_LEN, WordWeighting::Uniform)
+            .unwrap();
+        assert_eq!(words.len(), 100);
+        assert!(words
+            .iter()
+            .all(|w| ["one", "two", "three"].contains(&w.as_str())));
+        // the pool is cut off after valid words only
+        assert_eq!(read_word_pool(&path, 2).unwrap(), vec!["one", "two"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_only_invalid_lines() {
+        let dir = std::env::temp_dir().join("smokey_test_only_invalid_lines");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("words");
+        fs::write(&path, b"\xff\xfeoops\n\xc3\x28\n").unwrap();
+
+        let mut cache = WordCache::default();
+        assert!(matches!(
+            cache.shuffled_words_within(&path, 10, 5000, None, ANY_LEN, WordWeighting::Uniform),
+            Err(StorageError::NotUtf8(_))
+        ));
+        assert!(!cache.is_cached(&path, 5000));
+        fs::write(&path, "\n  \n").unwrap();
+        assert!(matches!(
+            read_word_pool(&path, 5000),
+            Err(StorageError::NoWords(_))
+        ));
+
+        // a test on a list that can't be read gets english words
+        let config = TypingTestConfig {
+            name: "surely_no_such_word_file".to_string(),
+            length: 10,
+            ..TypingTestConfig::default()
+        };
+        assert_eq!(cache.shuffled_words(&config).len(), 10);
+        assert!(cache.is_cached(&storage::get_word_list_path("english"), config.word_pool));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_builtin_english() {
+        let english = std::env::temp_dir()
+            .join("smokey_test_builtin_english")
+            .join("english");
+        assert!(!english.exists());
+
+        let mut cache = WordCache::default();
+        cache.cache_builtin_english(&english, 1000);
+        assert!(cache.is_cached(&english, 1000));
+        let words = cache
+            .shuffled_words_within(&english, 50, 1000, None, ANY_LEN, WordWeighting::Uniform)
+            .unwrap();
+        assert_eq!(words.len(), 50);
+        assert!(words.iter().all(|w| !w.trim().is_empty()));
+    }
+
+    #[test]
+    fn test_words_from_crlf_file() {
+        // no trailing newline on purpose
+        let path = temp_words("smokey_test_words_from_crlf_file", "one\r\ntwo\r\nthree");
+
+        let words = WordCache::default()
+            .shuffled_words_within(&path, 50, 3, None, ANY_LEN, WordWeighting::Uniform)
+            .unwrap();
+        assert_eq!(words.len(), 50);
+        assert!(words
+            .iter()
+            .all(|w| ["one", "two", "three"].contains(&w.as_str())));
+        // 50 picks out of 3 have to repeat
+        assert!(words.iter().any(|w| w == "three"));
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_blank_lines_skipped() {
+        let path = temp_words("smokey_test_blank_lines_skipped", "one\n\ntwo\n\n\n");
+
+        let words = WordCache::default()
+            .shuffled_words_within(&path, 50, 5000, None, ANY_LEN, WordWeighting::Uniform)
+            .unwrap();
+        assert!(words.iter().all(|w| w == "one" || w == "two"));
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_cache_invalidation() {
+        let path = temp_words("smokey_test_cache_invalidation", "old\nold\n");
+        let mut cache = WordCache::default();
+        cache
+            .shuffled_words_within(&path, 10, 2, None, ANY_LEN, WordWeighting::Uniform)
+            .unwrap();
+        assert!(cache.is_cached(&path, 2));
+
+        // same test reuses the pool without reading the file
+        fs::write(&path, "new\nnew\n").unwrap();
+        let words = cache
+            .shuffled_words_within(&path, 10, 2, None, ANY_LEN, WordWeighting::Uniform)
+            .unwrap();
+        assert!(words.iter().all(|w| w == "old"));
+
+        // different word_pool rereads it
+        let words = cache
+            .shuffled_words_within(&path, 10, 1, None, ANY_LEN, WordWeighting::Uniform)
+            .unwrap();
+        assert!(!cache.is_cached(&path, 2));
+        assert!(words.iter().all(|w| w == "new"));
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_word_length_filter() {
+        let path = temp_words(
+            "smokey_test_word_length_filter",
+            "a\nto\nword\nlonger\nlengthy\nżółwiki\nextraordinary\n",
+        );
+        let mut cache = WordCache::default();
+
+        let words = cache
+            .shuffled_words_within(
+                &path,
+                100,
+                5000,
+                None,
+                6..=usize::MAX,
+                WordWeighting::Uniform,
+            )
+            .unwrap();
+        assert_eq!(words.len(), 100);
+        assert!(words.iter().all(|w| w.chars().count() >= 6));
+        // chars are counted not bytes
+        let words = cache
+            .shuffled_words_within(&path, 100, 5000, None, 0..=5, WordWeighting::Uniform)
+            .unwrap();
+        assert!(words
+            .iter()
+            .all(|w| ["a", "to", "word"].contains(&w.as_str())));
+
+        // word_pool cuts off the file first
+        let words = cache
+            .shuffled_words_within(&path, 20, 4, None, 6..=usize::MAX, WordWeighting::Uniform)
+            .unwrap();
+        assert_eq!(words.len(), 20);
+        assert!(words.iter().all(|w| w == "longer"));
+
+        // nothing fits so the word length is ignored
+        let words = cache
+            .shuffled_words_within(&path, 20, 3, None, 6..=usize::MAX, WordWeighting::Uniform)
+            .unwrap();
+        assert_eq!(words.len(), 20);
+        assert!(words
+            .iter()
+            .all(|w| ["a", "to", "word"].contains(&w.as_str())));
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_weighted_words() {
+        let words: Vec<String> = (0..10).map(|i| format!("w{}", i)).collect();
+        let path = temp_words("smokey_test_weighted_words", &words.join("\n"));
+        let mut cache = WordCache::default();
+
+        let count = |picks: &[String], word: &str| picks.iter().filter(|w| *w == word).count();
+        for weighting in [WordWeighting::Linear, WordWeighting::Zipf] {
+            let picks = cache
+                .shuffled_words_within(&path, 10_000, 10, Some(3), ANY_LEN, weighting)
+                .unwrap();
+            assert_eq!(picks.len(), 10_000);
+            let (first, last) = (count(&picks, "w0"), count(&picks, "w9"));
+            assert!(first > 3 * last, "{:?}: {} vs {}", weighting, first, last);
+            // every word still shows up
+            assert!(words.iter().all(|w| count(&picks, w) > 0));
+        }
+
+        let picks = cache
+            .shuffled_words_within(&path, 10_000, 10, Some(3), ANY_LEN, WordWeighting::Uniform)
+            .unwrap();
+        let (first, last) = (count(&picks, "w0"), count(&picks, "w9"));
+        assert!(first < 2 * last && last < 2 * first);
+
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,68 @@
+//! Drills are tests made up only of the chars being practiced
+//! e.g. just digits, in random groups of a couple chars
+
+use fastrand::Rng as FastRng;
+
+/// name of a drill and chars it is made of
+pub const DRILLS: &[(&str, &str)] = &[
+    ("digits", "0123456789"),
+    ("brackets", "()[]{}<>"),
+    ("symbols", "!@#$%^&*-_=+\\|/~`"),
+    ("punctuation", ".,;:'\"?!-"),
+];
+
+/// drill groups are between these many chars long
+const MIN_GROUP_LEN: usize = 2;
+const MAX_GROUP_LEN: usize = 5;
+
+/// chars of a known drill, any other name is taken as the chars themselves
+pub fn drill_chars(name: &str) -> Vec<char> {
+    let chars = match DRILLS.iter().find(|(drill, _)| *drill == name) {
+        Some((_, chars)) => chars,
+        None => name,
+    };
+    chars.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// groups of random chars from the set separated by spaces
+pub fn generate_drill(chars: &[char], groups: usize, seed: Option<u64>) -> String {
+    let rng = match seed {
+        Some(seed) => FastRng::with_seed(seed),
+        None => FastRng::new(),
+    };
+
+    let mut drill = String::new();
+    for _ in 0..groups.max(1) {
+        let len = rng.usize(MIN_GROUP_LEN..=MAX_GROUP_LEN);
+        drill.extend((0..len).map(|_| chars[rng.usize(..chars.len())]));
+        drill.push(' ');
+    }
+    drill
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drill_chars() {
+        assert_eq!(drill_chars("digits").len(), 10);
+        assert_eq!(drill_chars("{ }"), vec!['{', '}']);
+        for (name, _) in DRILLS {
+            assert!(!drill_chars(name).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_generate_drill() {
+        let chars = drill_chars("brackets");
+        let drill = generate_drill(&chars, 50, Some(7));
+        let groups: Vec<&str> = drill.split_whitespace().collect();
+        assert_eq!(groups.len(), 50);
+        assert!(groups
+            .iter()
+            .all(|g| (MIN_GROUP_LEN..=MAX_GROUP_LEN).contains(&g.chars().count())));
+        assert!(drill.chars().all(|c| c == ' ' || chars.contains(&c)));
+        assert_eq!(drill, generate_drill(&chars, 50, Some(7)));
+    }
+}
@@ -11,6 +11,20 @@ impl SpanIntake for Vec<Span<'_>> {
     }
 }
 
+/// Pushes the word with its first letter uppercased,
+/// letters that uppercase to several chars (ß -> SS) get a span for each
+pub fn push_capitalized(container: &mut Vec<Span>, word: &str, color: Color) {
+    let mut chars = word.chars();
+    if let Some(first) = chars.next() {
+        for upper_char in first.to_uppercase() {
+            container.push_styled_char(upper_char, color);
+        }
+    }
+    for c in chars {
+        container.push_styled_char(c, color);
+    }
+}
+
 /// Remembers that the next word needs to be capitalized
 #[derive(Default)]
 pub struct Capitalize {
@@ -1,67 +1,149 @@
+mod cache;
+mod drills;
 mod helpers;
 mod punctuation;
+mod quotes;
+
+pub use cache::WordCache;
+pub use drills::DRILLS;
+pub use quotes::Quote;
 
 use crate::colorscheme::ToForeground;
 use crate::settings::{TestMod, TestVariant, TypingTestConfig};
+use crate::storage;
 use crate::typer::TestColors;
-use helpers::{Capitalize, SpanIntake};
-use punctuation::{InnerWord, Punctuation, PunctuationInsertFrequency};
+use anyhow::bail;
+use helpers::{push_capitalized, Capitalize, SpanIntake};
+use punctuation::{InnerWord, Punctuation, PunctuationInsertFrequency, SymbolFrequency};
 use std::ffi::OsStr;
 use std::process::Command;
 
-use super::utils::randorst::Randorst;
-use fastrand::Rng as FastRng;
 use rand::prelude::*;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
 
 use tui::text::Span;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-const SYMBOLS: [char; 14] = [
-    '@', '#', '$', '%', '^', '&', '*', '_', '=', '+', '-', '/', '|', '\\',
-];
+/// columns in a generated line, wide chars take up two
+pub const LIMIT: usize = 65;
 
-const LIMIT: usize = 65;
+/// Fraction of words swapped for a symbol cluster when the symbols mod is on
+const SYMBOLS_RATIO: f64 = 0.1;
+/// Numbers have from 1 up to MAX_NUMBER_DIGITS digits
+const MAX_NUMBER_DIGITS: u32 = 4;
+/// Fraction of words swapped for a missed word in practice tests
+const PRACTICE_RATIO: f64 = 0.6;
 
-pub fn prepare_test<'a>(config: &TypingTestConfig, colors: &TestColors) -> Vec<Vec<Span<'a>>> {
+pub fn prepare_test<'a>(
+    config: &TypingTestConfig,
+    colors: &TestColors,
+    cache: &mut WordCache,
+) -> Vec<Vec<Span<'a>>> {
     match config.variant {
-        TestVariant::Standard => prepare_standart_test(config, colors),
-        TestVariant::Script => prepare_script_test(config, colors),
+        TestVariant::Standard if config.numbers_only && config.mods.contains(&TestMod::Numbers) => {
+            prepare_numbers_test(config, colors)
+        }
+        TestVariant::Standard => prepare_standart_test(config, colors, cache),
+        TestVariant::Script => prepare_script_test(config, colors, cache),
+        TestVariant::Quote => match Quote::random_from(config.get_quotes_file_path()) {
+            Ok(quote) => prepare_quote_test(&quote, colors),
+            Err(e) => {
+                warn!("{}, falling back to english", e);
+                prepare_standart_test(&TypingTestConfig::default(), colors, cache)
+            }
+        },
+        TestVariant::Drill => prepare_drill_test(config, colors),
     }
 }
 
-fn prepare_script_test<'a>(config: &TypingTestConfig, colors: &TestColors) -> Vec<Vec<Span<'a>>> {
-    let script_output = call_script(config.get_scripts_file_path());
-    to_testable_span(&script_output, colors)
+/// length is the number of char groups in the drill
+fn prepare_drill_test<'a>(config: &TypingTestConfig, colors: &TestColors) -> Vec<Vec<Span<'a>>> {
+    let chars = drills::drill_chars(&config.name);
+    assert!(!chars.is_empty(), "drill has no chars to practice");
+    let drill = drills::generate_drill(&chars, config.length, config.seed);
+    to_testable_span(&drill, colors)
+}
+
+/// nothing but numbers, the group lengths are picked from digit_groups
+/// so e.g. [3, 4] reads like phone numbers
+fn prepare_numbers_test<'a>(config: &TypingTestConfig, colors: &TestColors) -> Vec<Vec<Span<'a>>> {
+    let mut rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let numbers: Vec<String> = (0..config.length.max(1))
+        .map(|_| gen_digit_group(&mut rng, &config.digit_groups))
+        .collect();
+    plain_lines(&numbers, colors)
+}
+
+/// the quote is typed as it is, punctuation and capitalization included
+pub fn prepare_quote_test<'a>(quote: &Quote, colors: &TestColors) -> Vec<Vec<Span<'a>>> {
+    // to_testable_span only takes in words followed by whitespace
+    to_testable_span(&format!("{}\n", quote.text), colors)
+}
+
+/// the script's output is the test,
+/// a script that fails or prints nothing gets the default english test instead
+fn prepare_script_test<'a>(
+    config: &TypingTestConfig,
+    colors: &TestColors,
+    cache: &mut WordCache,
+) -> Vec<Vec<Span<'a>>> {
+    prepare_script_test_from(config.get_scripts_file_path(), colors, cache)
+}
+
+fn prepare_script_test_from<'a>(
+    script_path: impl AsRef<OsStr>,
+    colors: &TestColors,
+    cache: &mut WordCache,
+) -> Vec<Vec<Span<'a>>> {
+    match call_script(&script_path) {
+        Ok(script_output) => to_testable_span(&script_output, colors),
+        Err(e) => {
+            warn!(
+                "script {:?} failed, falling back to english: {}",
+                script_path.as_ref(),
+                e
+            );
+            prepare_standart_test(&TypingTestConfig::default(), colors, cache)
+        }
+    }
 }
 
-fn prepare_standart_test<'a>(config: &TypingTestConfig, colors: &TestColors) -> Vec<Vec<Span<'a>>> {
-    let prep = get_shuffled_words(config);
+fn prepare_standart_test<'a>(
+    config: &TypingTestConfig,
+    colors: &TestColors,
+    cache: &mut WordCache,
+) -> Vec<Vec<Span<'a>>> {
+    let prep = mix_in_problem_words(cache.shuffled_words(config), config);
+
+    match config.mods.is_empty() && config.random_capitals == 0. {
+        true => plain_lines(&prep, colors),
+        false => prepare_modded_test(config, &prep, colors),
+    }
+}
 
+/// the words as they are wrapped into lines
+fn plain_lines<'a>(words: &[String], colors: &TestColors) -> Vec<Vec<Span<'a>>> {
     let mut test: Vec<Vec<Span>> = vec![];
     let mut tmp: Vec<Vec<Span>> = vec![vec![]];
     let mut count = 0;
 
-    match config.mods.is_empty() {
-        true => {
-            for word in &prep {
-                count += word.len() + 1;
-                if count > LIMIT {
-                    test.append(&mut tmp);
-                    count = word.len();
-                    tmp.push(vec![]);
-                }
-
-                for c in word.chars() {
-                    tmp[0].push_styled_char(c, colors.todo);
-                }
+    for word in words {
+        count += word.width() + 1;
+        // a word longer than the whole line still gets a line of its own
+        if count > LIMIT && !tmp[0].is_empty() {
+            test.append(&mut tmp);
+            count = word.width();
+            tmp.push(vec![]);
+        }
 
-                add_space_with_blank(&mut tmp[0], colors);
-            }
+        for c in word.chars() {
+            tmp[0].push_styled_char(c, colors.todo);
         }
 
-        false => return prepare_modded_test(config, &prep, colors),
-    };
+        add_space_with_blank(&mut tmp[0], colors);
+    }
 
     let last = tmp.len() - 1;
     tmp[last].pop();
@@ -71,45 +153,44 @@ fn prepare_standart_test<'a>(config: &TypingTestConfig, colors: &TestColors) ->
     test.into_iter().rev().collect()
 }
 
-fn get_shuffled_words(config: &TypingTestConfig) -> Vec<String> {
-    // This is quick and bad
-    // TODO impl more robust system
-    let words_file = config.get_words_file_path();
-
-    let file = File::open(words_file).expect("couldn't open file");
-    let reader = BufReader::new(file);
-    let mut line_iter = reader.lines();
-    let mut container: Vec<String> = Vec::new();
-
-    let mut prng = Randorst::gen(config.length, 0..config.word_pool);
-    let mut last = prng.next().unwrap();
-    let out = line_iter.nth(last).unwrap().unwrap();
-    container.push(out);
-    let mut cached_word: usize = container.len() - 1;
-
-    for (i, val) in prng.enumerate() {
-        if val == last {
-            container.push(container[cached_word].to_string());
-            continue;
-        }
-        container.push(line_iter.nth(val - last - 1).unwrap().unwrap());
-        cached_word = i + 1;
-        last = val;
+/// practice tests swap most of the words for ones missed in earlier runs,
+/// with no missed words yet the words are left as they are
+fn mix_in_problem_words(mut words: Vec<String>, config: &TypingTestConfig) -> Vec<String> {
+    if !config.practice || config.problem_words.is_empty() {
+        return words;
     }
 
-    FastRng::new().shuffle(&mut container);
-    container
+    let mut rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    for word in words.iter_mut() {
+        if rng.gen_bool(PRACTICE_RATIO) {
+            if let Some(problem) = config.problem_words.choose(&mut rng) {
+                word.clone_from(problem);
+            }
+        }
+    }
+    words
 }
 
-fn add_space_with_blank(container: &mut Vec<Span>, colors: &TestColors) {
-    container.push(Span::styled("", colors.wrong.fg()));
+pub fn add_space_with_blank(container: &mut Vec<Span>, colors: &TestColors) {
+    container.push(Span::styled("", colors.extra.fg()));
     container.push(Span::styled(" ", colors.todo.fg()));
 }
 
-// calls script
-fn call_script(script_path: impl AsRef<OsStr>) -> String {
-    let output = Command::new(script_path).output().unwrap();
-    String::from_utf8(output.stdout).unwrap()
+/// runs the script and returns its stdout
+fn call_script(script_path: impl AsRef<OsStr>) -> anyhow::Result<String> {
+    let output = Command::new(script_path).output()?;
+    if !output.status.success() {
+        bail!("exited with {}", output.status);
+    }
+
+    let stdout = String::from_utf8(output.stdout)?;
+    if stdout.trim().is_empty() {
+        bail!("printed nothing");
+    }
+    Ok(stdout)
 }
 
 fn to_testable_span<'a>(text: &str, colors: &TestColors) -> Vec<Vec<Span<'a>>> {
@@ -120,7 +201,7 @@ fn to_testable_span<'a>(text: &str, colors: &TestColors) -> Vec<Vec<Span<'a>>> {
     let mut duplicate_whitespace_flag: bool = false;
 
     for c in text.chars() {
-        count += 1;
+        count += c.width().unwrap_or(1);
         if c.is_whitespace() {
             if !duplicate_whitespace_flag {
                 add_space_with_blank(&mut word, colors);
@@ -130,9 +211,9 @@ fn to_testable_span<'a>(text: &str, colors: &TestColors) -> Vec<Vec<Span<'a>>> {
         } else {
             duplicate_whitespace_flag = false;
             word.push_styled_char(c, colors.todo);
-            if count > LIMIT {
+            if count > LIMIT && !tmp[0].is_empty() {
                 lines.append(&mut tmp);
-                count = word.len();
+                count = word.iter().map(|s| s.content.width()).sum();
                 tmp.push(vec![]);
             }
         }
@@ -150,13 +231,26 @@ fn prepare_modded_test<'a>(
     words: &[String],
     colors: &TestColors,
 ) -> Vec<Vec<Span<'a>>> {
-    let p = PunctuationInsertFrequency::from_test_mods(&config.mods);
+    let p = PunctuationInsertFrequency::from_test_mods(
+        &config.mods,
+        config.punctuation_weights.as_ref(),
+        config.punctuation_kinds.as_ref(),
+        config.curly_quotes,
+        &config.name,
+    );
+    let symbols = match &config.symbols {
+        Some(name) => SymbolFrequency::from_file(&storage::get_symbols_path(name)),
+        None => SymbolFrequency::default(),
+    };
 
     let mut test: Vec<Vec<Span>> = vec![];
     let mut tmp: Vec<Vec<Span>> = vec![vec![]];
     let mut count = 0;
 
-    let mut rng = thread_rng();
+    let mut rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
 
     struct Capitalizer {
         opt_capitalize: Option<Capitalize>,
@@ -200,15 +294,34 @@ fn prepare_modded_test<'a>(
     let mut end: Option<char>;
     let mut inner_word: Option<InnerWord>;
 
-    for word in words {
-        count += word.len() + 1;
-        if count > LIMIT {
-            test.append(&mut tmp);
-            count = word.len();
-            tmp.push(vec![]);
-        }
+    let numbers = config.mods.contains(&TestMod::Numbers);
+    let symbol_clusters = config.mods.contains(&TestMod::Symbols);
+    let mut number: String;
 
-        let punct = p.choose(&mut rng);
+    for (i, word) in words.iter().enumerate() {
+        // token type is decided first, punctuation decorates whatever comes out
+        let token = if numbers && rng.gen_bool(config.numbers_ratio) {
+            Token::Number
+        } else if symbol_clusters && rng.gen_bool(SYMBOLS_RATIO) {
+            Token::Symbol
+        } else {
+            Token::Word
+        };
+
+        let word: &str = match token {
+            Token::Word => word,
+            Token::Number => {
+                number = gen_number(&mut rng);
+                &number
+            }
+            Token::Symbol => symbols.choose(&mut rng),
+        };
+
+        // symbol clusters are left bare, "&&," or "(=>)" is just noise
+        let punct = match token {
+            Token::Symbol => Punctuation::Nil,
+            _ => p.choose(&mut rng),
+        };
 
         inner_word = None;
         match punct {
@@ -221,24 +334,18 @@ fn prepare_modded_test<'a>(
                 capitalizer.signal();
                 begin = None;
                 end = Some(c);
-                count += 1;
             }
 
             Punctuation::Normal(c) => {
                 begin = None;
                 end = Some(c);
-                count += 1;
             }
 
             Punctuation::Paired(a, z) => {
                 begin = Some(a);
                 end = Some(z);
-                count += 2;
             }
 
-            // TODO implement this bullshit
-            // i am kinda fed up with what this became
-            // need to think it through
             Punctuation::InBetweener(in_betweener) => {
                 begin = None;
                 end = None;
@@ -246,30 +353,51 @@ fn prepare_modded_test<'a>(
             }
         }
 
+        // whatever goes in between is picked upfront so the whole
+        // word + in_between unit can be counted toward the line limit
+        let in_between: Option<&str> = match inner_word {
+            // a dash dangling at the very end of the test is just ugly
+            Some(InnerWord::Dash) if i == words.len() - 1 => None,
+            Some(InnerWord::Dash) => Some("-"),
+            None => None,
+        };
+
+        let token_len = word.width()
+            + 1
+            + begin.is_some() as usize
+            + end.is_some() as usize
+            + in_between.map_or(0, |ib| ib.width() + 1);
+
+        count += token_len;
+        if count > LIMIT && !tmp[0].is_empty() {
+            test.append(&mut tmp);
+            count = token_len - 1;
+            tmp.push(vec![]);
+        }
+
         if let Some(c) = begin {
             tmp[0].push_styled_char(c, colors.todo);
         }
 
         // the part where actual word is inserted
-        // First letter
-        let mut iter_chars = word.chars();
-
-        if let Some(b) = capitalizer.capitalize() {
-            if b {
-                let upper = iter_chars
-                    .next()
-                    .expect("word should never be empty")
-                    .to_uppercase();
-                for upper_char in upper {
-                    tmp[0].push_styled_char(upper_char, colors.todo);
-                }
+        // capitalizer is asked every word so it keeps track of sentence starts,
+        // symbols pass the capital on to the next word
+        let capitalize = match token {
+            Token::Symbol => false,
+            _ => {
+                let sentence_start = capitalizer.capitalize() == Some(true);
+                let random_capital =
+                    config.random_capitals > 0. && rng.gen_bool(config.random_capitals);
+                sentence_start || random_capital
             }
-        }
+        };
 
-        // rest of the word
-        for c in iter_chars {
-            // repetition btw
-            tmp[0].push_styled_char(c, colors.todo);
+        if capitalize {
+            push_capitalized(&mut tmp[0], word, colors.todo);
+        } else {
+            for c in word.chars() {
+                tmp[0].push_styled_char(c, colors.todo);
+            }
         }
 
         if let Some(c) = end {
@@ -278,34 +406,10 @@ fn prepare_modded_test<'a>(
 
         add_space_with_blank(&mut tmp[0], colors);
 
-        if let Some(ib) = inner_word {
-            // TODO: do I care for occasional dashes at the end?
-            // propably not but they are kinda ugly not gonna lie
-            match ib {
-                InnerWord::Dash => {
-                    tmp[0].push_styled_char('-', colors.todo);
-                    count += 1;
-                }
-
-                InnerWord::Number => {
-                    let number = rng.gen_range(0..=999).to_string();
-                    count += number.len();
-                    for c in number.chars() {
-                        tmp[0].push_styled_char(c, colors.todo);
-                    }
-                }
-
-                InnerWord::Symbol => {
-                    let times = rng.gen_range(1..=3);
-                    count += times;
-                    for _ in 0..times {
-                        let symbol = SYMBOLS
-                            .choose(&mut rng)
-                            .expect("SYMBOlS shouldn't be empty");
-
-                        tmp[0].push_styled_char(*symbol, colors.todo);
-                    }
-                }
+        // word - word
+        if let Some(ib) = in_between {
+            for c in ib.chars() {
+                tmp[0].push_styled_char(c, colors.todo);
             }
             add_space_with_blank(&mut tmp[0], colors);
         }
@@ -319,11 +423,44 @@ fn prepare_modded_test<'a>(
     test.into_iter().rev().collect()
 }
 
+/// What a token of the modded test is made of
+enum Token {
+    Word,
+    Number,
+    Symbol,
+}
+
+/// random digits as many as one of the group lengths,
+/// without any lengths they go from 1 to MAX_NUMBER_DIGITS
+/// leading zeros are kept, it's a group of digits rather than a number
+fn gen_digit_group(rng: &mut impl Rng, lengths: &[usize]) -> String {
+    let len = match lengths.choose(rng) {
+        Some(&len) => len,
+        None => rng.gen_range(1..=MAX_NUMBER_DIGITS as usize),
+    };
+    (0..len)
+        .map(|_| char::from(b'0' + rng.gen_range(0..10)))
+        .collect()
+}
+
+/// random number with 1 to MAX_NUMBER_DIGITS digits
+/// each digit count is equally likely so short numbers don't get drowned out
+fn gen_number(rng: &mut impl Rng) -> String {
+    let digits = rng.gen_range(1..=MAX_NUMBER_DIGITS);
+    let low = if digits == 1 {
+        0
+    } else {
+        10_u32.pow(digits - 1)
+    };
+    rng.gen_range(low..10_u32.pow(digits)).to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::settings::TypingTestConfig;
+    use crate::settings::{PunctuationKind, TypingTestConfig};
     use crate::typer::TestColors;
+    use std::collections::{HashMap, HashSet};
 
     #[test]
     fn test_prep() {
@@ -335,7 +472,7 @@ mod tests {
         let mut words = 1;
         let mut char_count = 0;
 
-        let result = prepare_test(&cfg, &TestColors::default());
+        let result = prepare_test(&cfg, &TestColors::default(), &mut WordCache::default());
         for line in &result {
             for span in line {
                 if span.content == " " {
@@ -353,4 +490,539 @@ mod tests {
 
         assert_eq!(words, cfg.length);
     }
+
+    fn collect_words(test: &[Vec<Span>]) -> Vec<String> {
+        let mut text = String::new();
+        for line in test.iter().rev() {
+            for span in line {
+                text.push_str(&span.content);
+            }
+        }
+        text.split_whitespace().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn test_prep_seeded() {
+        let mut cfg = TypingTestConfig {
+            length: 100,
+            seed: Some(2137),
+            ..Default::default()
+        };
+        cfg.mods.insert(TestMod::Punctuation);
+        cfg.mods.insert(TestMod::Numbers);
+
+        let colors = TestColors::default();
+        let first = prepare_test(&cfg, &colors, &mut WordCache::default());
+        let second = prepare_test(&cfg, &colors, &mut WordCache::default());
+        assert_eq!(first, second);
+
+        cfg.mods.clear();
+        let plain = prepare_test(&cfg, &colors, &mut WordCache::default());
+        assert_eq!(
+            plain,
+            prepare_test(&cfg, &colors, &mut WordCache::default())
+        );
+
+        cfg.seed = Some(2138);
+        assert_ne!(
+            plain,
+            prepare_test(&cfg, &colors, &mut WordCache::default())
+        );
+    }
+
+    #[test]
+    fn test_prep_words_longer_than_line() {
+        let long = "a".repeat(LIMIT * 2);
+        let colors = TestColors::default();
+        let mut cfg = TypingTestConfig::default();
+        cfg.mods.insert(TestMod::Capitalization);
+
+        let words = vec![long.clone(), "b".to_string(), long.clone()];
+        let tests = [
+            prepare_modded_test(&cfg, &words, &colors),
+            to_testable_span(&format!("{} b {}\n", long, long), &colors),
+        ];
+        for test in tests {
+            assert!(test.iter().all(|line| !line.is_empty()));
+            assert_eq!(collect_words(&test).len(), 3);
+        }
+    }
+
+    #[test]
+    fn test_prep_punctuation_without_capitals() {
+        let colors = TestColors::default();
+        let words: Vec<String> = (0..300).map(|_| "ab".to_string()).collect();
+        let mut cfg = TypingTestConfig {
+            seed: Some(2137),
+            ..Default::default()
+        };
+        cfg.mods.insert(TestMod::Punctuation);
+
+        // sentences still end but the next word stays lowercase
+        let text: String = collect_words(&prepare_modded_test(&cfg, &words, &colors)).concat();
+        assert!(text.contains(['.', '?', '!']));
+        assert!(!text.chars().any(char::is_uppercase));
+
+        cfg.mods.insert(TestMod::Capitalization);
+        let text: String = collect_words(&prepare_modded_test(&cfg, &words, &colors)).concat();
+        assert!(text.contains("Ab"));
+    }
+
+    #[test]
+    fn test_prep_random_capitals() {
+        let colors = TestColors::default();
+        let words: Vec<String> = (0..200).map(|_| "ßa".to_string()).collect();
+        let mut cfg = TypingTestConfig {
+            random_capitals: 0.5,
+            seed: Some(2137),
+            ..Default::default()
+        };
+
+        let test = prepare_modded_test(&cfg, &words, &colors);
+        let typed = collect_words(&test);
+        let capitalized = typed.iter().filter(|w| *w == "SSa").count();
+        assert!(capitalized > 50 && capitalized < 150);
+        assert!(typed.iter().all(|w| w == "SSa" || w == "ßa"));
+        // every uppercased char is its own span
+        assert!(test
+            .iter()
+            .flatten()
+            .all(|s| s.content.chars().count() <= 1));
+
+        cfg.random_capitals = 1.;
+        let test = prepare_modded_test(&cfg, &words, &colors);
+        assert!(collect_words(&test).iter().all(|w| w == "SSa"));
+
+        // capitals show up in standard tests without any mods
+        let test = prepare_test(&cfg, &colors, &mut WordCache::default());
+        let typed = collect_words(&test);
+        assert_eq!(typed.len(), cfg.length);
+        assert!(typed
+            .iter()
+            .all(|w| w.chars().next().unwrap().is_uppercase()));
+
+        cfg.random_capitals = 0.;
+        let test = prepare_modded_test(&cfg, &words, &colors);
+        assert!(collect_words(&test).iter().all(|w| w == "ßa"));
+    }
+
+    #[test]
+    fn test_prep_wide_chars() {
+        let colors = TestColors::default();
+        // 4 chars but 8 columns each
+        let words: Vec<String> = (0..60).map(|_| "漢字漢字".to_string()).collect();
+        let mut modded = TypingTestConfig::default();
+        modded.mods.insert(TestMod::Capitalization);
+
+        let tests = [
+            prepare_modded_test(&modded, &words, &colors),
+            to_testable_span(&format!("{}\n", words.join(" ")), &colors),
+        ];
+        for test in tests {
+            assert_eq!(collect_words(&test).len(), words.len());
+            for line in &test {
+                let columns: usize = line.iter().map(|s| s.content.width()).sum();
+                // there can be a space at the end
+                assert!(columns <= LIMIT + 1, "line takes up {} columns", columns);
+                let chars: usize = line.iter().map(|s| s.content.chars().count()).sum();
+                assert!(chars < columns);
+            }
+        }
+    }
+
+    #[test]
+    fn test_prep_drill() {
+        let cfg = TypingTestConfig {
+            name: "digits".to_string(),
+            variant: TestVariant::Drill,
+            length: 200,
+            ..Default::default()
+        };
+
+        let result = prepare_test(&cfg, &TestColors::default(), &mut WordCache::default());
+        // wrapped like any other test
+        assert!(result.len() > 1);
+        for line in &result {
+            let char_count = line.iter().filter(|s| !s.content.is_empty()).count();
+            assert!(char_count <= LIMIT + 1);
+        }
+
+        let groups = collect_words(&result);
+        assert_eq!(groups.len(), cfg.length);
+        assert!(groups.iter().all(|g| g.chars().all(|c| c.is_ascii_digit())));
+    }
+
+    #[test]
+    fn test_prep_numbers_only() {
+        let mut cfg = TypingTestConfig {
+            length: 120,
+            numbers_only: true,
+            digit_groups: vec![3, 4],
+            ..Default::default()
+        };
+        cfg.mods.insert(TestMod::Numbers);
+
+        let result = prepare_test(&cfg, &TestColors::default(), &mut WordCache::default());
+        let mut text = String::new();
+        for line in &result {
+            let line: String = line.iter().map(|s| s.content.as_ref()).collect();
+            assert!(line.width() <= LIMIT + 1);
+            text.push_str(&line);
+        }
+        assert!(text.chars().all(|c| c.is_ascii_digit() || c == ' '));
+
+        let groups = collect_words(&result);
+        assert_eq!(groups.len(), cfg.length);
+        assert!(groups.iter().all(|g| g.len() == 3 || g.len() == 4));
+
+        // no group lengths means the usual number lengths
+        cfg.digit_groups.clear();
+        let groups = collect_words(&prepare_test(
+            &cfg,
+            &TestColors::default(),
+            &mut WordCache::default(),
+        ));
+        assert!(groups
+            .iter()
+            .all(|g| (1..=MAX_NUMBER_DIGITS as usize).contains(&g.len())));
+
+        // without the numbers mod it's the regular test
+        cfg.mods.clear();
+        let words = collect_words(&prepare_test(
+            &cfg,
+            &TestColors::default(),
+            &mut WordCache::default(),
+        ));
+        assert!(words.iter().any(|w| w.chars().any(|c| c.is_alphabetic())));
+    }
+
+    #[test]
+    fn test_prep_numbers() {
+        let mut cfg = TypingTestConfig {
+            length: 200,
+            ..Default::default()
+        };
+        cfg.mods.insert(TestMod::Numbers);
+
+        let result = prepare_test(&cfg, &TestColors::default(), &mut WordCache::default());
+        for line in &result {
+            let char_count = line.iter().filter(|s| !s.content.is_empty()).count();
+            assert!(char_count <= LIMIT + 1);
+        }
+
+        let words = collect_words(&result);
+        assert_eq!(words.len(), cfg.length);
+
+        let numbers: Vec<&String> = words
+            .iter()
+            .filter(|w| w.chars().all(|c| c.is_ascii_digit()))
+            .collect();
+        assert!(!numbers.is_empty());
+        assert!(numbers.iter().all(|n| n.len() as u32 <= MAX_NUMBER_DIGITS));
+
+        // the ratio decides how many of the words become numbers
+        let is_number = |w: &String| w.chars().all(|c| c.is_ascii_digit());
+        cfg.numbers_ratio = 1.;
+        let words = collect_words(&prepare_test(
+            &cfg,
+            &TestColors::default(),
+            &mut WordCache::default(),
+        ));
+        assert!(words.iter().all(is_number));
+        cfg.numbers_ratio = 0.;
+        let words = collect_words(&prepare_test(
+            &cfg,
+            &TestColors::default(),
+            &mut WordCache::default(),
+        ));
+        assert!(!words.iter().any(is_number));
+    }
+
+    #[test]
+    fn test_prep_symbols() {
+        let mut cfg = TypingTestConfig {
+            length: 300,
+            ..Default::default()
+        };
+        cfg.mods.insert(TestMod::Symbols);
+
+        let result = prepare_test(&cfg, &TestColors::default(), &mut WordCache::default());
+
+        // trailing space and blank are popped even if a cluster went last
+        let last_span = result[0].last().unwrap();
+        assert_ne!(last_span.content, " ");
+        assert!(!last_span.content.is_empty());
+
+        let words = collect_words(&result);
+        assert!(words
+            .iter()
+            .any(|w| w.chars().all(|c| !c.is_alphanumeric())));
+    }
+
+    #[test]
+    fn test_prep_all_mods() {
+        let mut cfg = TypingTestConfig {
+            length: 1000,
+            seed: Some(7),
+            ..Default::default()
+        };
+        cfg.mods.insert(TestMod::Punctuation);
+        cfg.mods.insert(TestMod::Numbers);
+        cfg.mods.insert(TestMod::Symbols);
+
+        let result = prepare_test(&cfg, &TestColors::default(), &mut WordCache::default());
+        let words = collect_words(&result);
+        let is_bare = |w: &str, f: fn(&char) -> bool| w.chars().all(|c| f(&c));
+
+        assert!(words.iter().any(|w| is_bare(w, char::is_ascii_digit)));
+        assert!(words.iter().any(|w| is_bare(w, |c| c.is_alphabetic())));
+        assert!(words
+            .iter()
+            .any(|w| w.chars().any(char::is_alphabetic) && w.ends_with(['.', ','])));
+
+        // symbol clusters are never decorated with punctuation
+        let symbols = SymbolFrequency::default();
+        let clusters: Vec<&String> = words
+            .iter()
+            .filter(|w| w.chars().all(|c| !c.is_alphanumeric()) && w.as_str() != "-")
+            .collect();
+        assert!(!clusters.is_empty());
+        assert!(clusters.iter().all(|w| symbols.contains(w)));
+
+        // the dash is the only thing thrown in between so the rest adds up
+        let dashes = words.iter().filter(|w| w.as_str() == "-").count();
+        assert_eq!(words.len() - dashes, cfg.length);
+    }
+
+    #[test]
+    fn test_prep_dashes() {
+        let mut cfg = TypingTestConfig {
+            length: 500,
+            // random words could leave out dashes now and then
+            seed: Some(2137),
+            ..Default::default()
+        };
+        cfg.mods.insert(TestMod::Punctuation);
+
+        let result = prepare_test(&cfg, &TestColors::default(), &mut WordCache::default());
+        for line in &result {
+            let char_count = line.iter().filter(|s| !s.content.is_empty()).count();
+            assert!(char_count <= LIMIT + 1);
+        }
+
+        let words = collect_words(&result);
+        assert!(words.contains(&"-".to_string()));
+        assert_ne!(words[0], "-");
+        assert_ne!(words[words.len() - 1], "-");
+
+        // dash always stands alone in between two words
+        for (i, _) in words.iter().enumerate().filter(|(_, w)| *w == "-") {
+            assert_ne!(words[i - 1], "-");
+            assert_ne!(words[i + 1], "-");
+        }
+    }
+
+    #[test]
+    fn test_prep_punctuation_weights() {
+        let mut weights: HashMap<char, u16> =
+            ['.', '?', '!', ',', ';', ':', '<', '{', '[', '"', '\'', '-']
+                .into_iter()
+                .map(|c| (c, 0))
+                .collect();
+        weights.insert('(', 500);
+
+        let mut cfg = TypingTestConfig {
+            length: 300,
+            ..Default::default()
+        }
+        .with_punctuation_weights(weights);
+        cfg.mods.insert(TestMod::Punctuation);
+
+        let result = prepare_test(&cfg, &TestColors::default(), &mut WordCache::default());
+        let text: String = collect_words(&result).join(" ");
+        assert!(text.contains('('));
+        assert!(!text.contains(|c| ".?!,;:<{[\"'-".contains(c)));
+    }
+
+    #[test]
+    fn test_prep_punctuation_kinds() {
+        let words: Vec<String> = (0..2000).map(|_| "word".to_string()).collect();
+        let mut cfg = TypingTestConfig {
+            seed: Some(2137),
+            punctuation_kinds: Some([PunctuationKind::End].into_iter().collect()),
+            ..Default::default()
+        };
+        cfg.mods.insert(TestMod::Punctuation);
+        let colors = TestColors::default();
+
+        let text = collect_words(&prepare_modded_test(&cfg, &words, &colors)).join(" ");
+        assert!(text.contains('.'));
+        assert!(!text.contains(|c| ",;:()[]{}<>\"'-".contains(c)));
+
+        // nothing enabled is the same as no punctuation at all
+        cfg.punctuation_kinds = Some(HashSet::new());
+        let text = collect_words(&prepare_modded_test(&cfg, &words, &colors)).join(" ");
+        assert!(text.chars().all(|c| c.is_alphabetic() || c == ' '));
+    }
+
+    #[test]
+    fn test_symbols_from_file() {
+        let dir = std::env::temp_dir().join("smokey_test_symbols_from_file");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("rust");
+        std::fs::write(&path, "=> 9\n\n::\n|| lots\n<> 0\n").unwrap();
+
+        let symbols = SymbolFrequency::from_file(&path);
+        assert!(symbols.contains("=>") && symbols.contains("::"));
+        // malformed and zero weight lines are skipped
+        assert!(!symbols.contains("||") && !symbols.contains("<>"));
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let arrows = (0..10_000)
+            .filter(|_| symbols.choose(&mut rng) == "=>")
+            .count();
+        assert!((8_500..9_500).contains(&arrows), "{} arrows", arrows);
+
+        // missing and empty files fall back to the built-in clusters
+        assert!(SymbolFrequency::from_file(&dir.join("missing")).contains("&&"));
+        std::fs::write(&path, "\n").unwrap();
+        assert!(SymbolFrequency::from_file(&path).contains("&&"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_prep_curly_quotes() {
+        let mut weights: HashMap<char, u16> = ".?!,;:(<{[-".chars().map(|c| (c, 0)).collect();
+        weights.insert('"', 100);
+        weights.insert('\'', 100);
+        let words: Vec<String> = (0..300).map(|_| "word".to_string()).collect();
+        let mut cfg = TypingTestConfig {
+            seed: Some(2137),
+            curly_quotes: true,
+            ..Default::default()
+        }
+        .with_punctuation_weights(weights);
+        cfg.mods.insert(TestMod::Punctuation);
+        let colors = TestColors::default();
+
+        let text = collect_words(&prepare_modded_test(&cfg, &words, &colors)).join(" ");
+        assert!(text.contains(|c| "“”‘’".contains(c)));
+        assert!(!text.contains(|c| "\"'".contains(c)));
+
+        cfg.curly_quotes = false;
+        let text = collect_words(&prepare_modded_test(&cfg, &words, &colors)).join(" ");
+        assert!(!text.contains(|c| "“”‘’".contains(c)));
+    }
+
+    #[test]
+    fn test_prep_punctuation_profiles() {
+        let has_pair = |word_list: &str, open: char| {
+            punctuation::punctuation_profile(word_list)
+                .iter()
+                .any(|(p, _)| matches!(p, Punctuation::Paired(o, _) if *o == open))
+        };
+        assert!(has_pair("french", '«'));
+        assert!(has_pair("French_10k", '«'));
+        assert!(has_pair("german", '„'));
+        assert!(!has_pair("english", '«'));
+        // no profile falls back to english
+        assert!(has_pair("klingon", '"') && has_pair("klingon", '{'));
+
+        let words: Vec<String> = (0..500).map(|_| "mot".to_string()).collect();
+        let mut cfg = TypingTestConfig {
+            name: "french".to_string(),
+            seed: Some(2137),
+            ..Default::default()
+        };
+        cfg.mods.insert(TestMod::Punctuation);
+        let colors = TestColors::default();
+
+        let text = collect_words(&prepare_modded_test(&cfg, &words, &colors)).join(" ");
+        assert!(text.contains('«') && text.contains('»'));
+        assert!(!text.contains(|c| "<{['".contains(c)));
+
+        cfg.name = "english".to_string();
+        let text = collect_words(&prepare_modded_test(&cfg, &words, &colors)).join(" ");
+        assert!(!text.contains('«'));
+    }
+
+    #[test]
+    fn test_prep_all_punctuation_weights_zero() {
+        let weights: HashMap<char, u16> = "(.?!,;:<{[\"'-".chars().map(|c| (c, 0)).collect();
+        let mut cfg = TypingTestConfig::default().with_punctuation_weights(weights);
+        cfg.mods.insert(TestMod::Punctuation);
+
+        // would panic if WeightedIndex got only zeroes
+        let result = prepare_test(&cfg, &TestColors::default(), &mut WordCache::default());
+        assert_eq!(collect_words(&result).len(), cfg.length);
+    }
+
+    #[test]
+    fn test_prep_quote() {
+        let text = "No man ever steps in the same river twice, for it's not the same river \
+            and he's not the same man. \"Really\" - Heraclitus; 10,000 times!";
+        let quote = Quote {
+            text: text.to_string(),
+            author: None,
+        };
+
+        let result = prepare_quote_test(&quote, &TestColors::default());
+        // long enough to be wrapped
+        assert!(result.len() > 1);
+        assert_eq!(collect_words(&result).join(" "), text);
+
+        // no trailing space at the end of the test
+        let last_line = &result[0];
+        assert_ne!(last_line[last_line.len() - 1].content, " ");
+    }
+
+    #[cfg(unix)]
+    fn temp_script(test_name: &str, contents: &str) -> std::path::PathBuf {
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(test_name);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("script");
+        fs::write(&path, contents).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_prep_script() {
+        let colors = TestColors::default();
+        let mut cache = WordCache::default();
+
+        let path = temp_script(
+            "smokey_test_prep_script",
+            "#!/bin/sh\necho one two\necho three\n",
+        );
+        assert_eq!(call_script(&path).unwrap(), "one two\nthree\n");
+        let result = prepare_script_test_from(&path, &colors, &mut cache);
+        assert_eq!(collect_words(&result), vec!["one", "two", "three"]);
+
+        // failing and silent scripts end up with an english test
+        let scripts = [
+            (
+                "smokey_test_prep_script_fails",
+                "#!/bin/sh\necho one\nexit 3\n",
+            ),
+            ("smokey_test_prep_script_silent", "#!/bin/sh\necho\n"),
+        ];
+        for (test_name, contents) in scripts {
+            let path = temp_script(test_name, contents);
+            assert!(call_script(&path).is_err());
+            let result = prepare_script_test_from(&path, &colors, &mut cache);
+            assert_eq!(
+                collect_words(&result).len(),
+                TypingTestConfig::default().length
+            );
+        }
+
+        let missing = std::env::temp_dir().join("smokey_test_no_such_script");
+        assert!(call_script(missing).is_err());
+    }
 }
@@ -1,7 +1,9 @@
-use crate::settings::TestMod;
+use crate::settings::{PunctuationKind, TestMod};
 use rand::distributions::WeightedIndex;
 use rand::prelude::*;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
 
 /// InnerWord represent everything I can throw
 /// in between words like numbers symbols dashes
@@ -11,8 +13,6 @@ use std::collections::HashSet;
 #[derive(Debug, Clone, Copy)]
 pub enum InnerWord {
     Dash,
-    Number,
-    Symbol,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -28,6 +28,38 @@ pub enum Punctuation {
     Nil,
 }
 
+impl Punctuation {
+    /// char the punctuation is known by in user supplied weights
+    /// paired punctuation goes by the opening char
+    /// Nil can't be reweighted this way
+    pub fn key(&self) -> Option<char> {
+        match self {
+            Self::Normal(c) | Self::End(c) | Self::Paired(c, _) => Some(*c),
+            Self::InBetweener(InnerWord::Dash) => Some('-'),
+            _ => None,
+        }
+    }
+
+    /// straight quotes swapped for typographic curly ones
+    fn curled(self) -> Self {
+        match self {
+            Self::Paired('"', '"') => Self::Paired('“', '”'),
+            Self::Paired('\'', '\'') => Self::Paired('‘', '’'),
+            other => other,
+        }
+    }
+
+    pub fn kind(&self) -> Option<PunctuationKind> {
+        match self {
+            Self::Normal(_) => Some(PunctuationKind::Normal),
+            Self::End(_) => Some(PunctuationKind::End),
+            Self::Paired(..) => Some(PunctuationKind::Paired),
+            Self::InBetweener(InnerWord::Dash) => Some(PunctuationKind::Dash),
+            Self::Nil => None,
+        }
+    }
+}
+
 /// Creates a WeightedIndex of punctuation whis allows
 /// to modify text in certain ways
 pub struct PunctuationInsertFrequency {
@@ -39,56 +71,237 @@ impl PunctuationInsertFrequency {
     fn from_protoplast(protoplast: Vec<(Punctuation, u16)>) -> Self {
         let mut weighted_index: Vec<u16> = Vec::with_capacity(protoplast.len());
         let mut symbols: Vec<Punctuation> = Vec::with_capacity(protoplast.len());
-        for (p, w) in protoplast.into_iter() {
+        // zero weights are dropped so that WeightedIndex never sees them
+        for (p, w) in protoplast.into_iter().filter(|(_, w)| *w != 0) {
             weighted_index.push(w);
             symbols.push(p);
         }
 
-        let weighted_index = WeightedIndex::new(weighted_index).unwrap();
+        if symbols.is_empty() {
+            weighted_index.push(1);
+            symbols.push(Punctuation::Nil);
+        }
+
+        let weighted_index =
+            WeightedIndex::new(weighted_index).expect("weights are non-zero and non-empty");
         Self {
             weighted_index,
             symbols,
         }
     }
 
-    pub fn from_test_mods(test_mods: &HashSet<TestMod>) -> Self {
+    /// weights override the defaults of punctuation with the matching key,
+    /// kinds that aren't enabled are left out whatever their weights,
+    /// the punctuation itself depends on the language of the word list
+    /// curly quotes are still weighted by the straight ones
+    pub fn from_test_mods(
+        test_mods: &HashSet<TestMod>,
+        weights: Option<&HashMap<char, u16>>,
+        kinds: Option<&HashSet<PunctuationKind>>,
+        curly_quotes: bool,
+        word_list: &str,
+    ) -> Self {
         let mut protoplast: Vec<(Punctuation, u16)> = vec![(Punctuation::Nil, 750)];
         for test_mod in test_mods {
             match test_mod {
                 TestMod::Punctuation => {
-                    let mut we: Vec<(Punctuation, u16)> = vec![
-                        (Punctuation::End('.'), 65),
-                        (Punctuation::End('?'), 8),
-                        (Punctuation::End('!'), 6),
-                        (Punctuation::Normal(','), 61),
-                        (Punctuation::Normal(';'), 3),
-                        (Punctuation::Normal(':'), 3),
-                        (Punctuation::Paired('<', '>'), 2),
-                        (Punctuation::Paired('(', ')'), 5),
-                        (Punctuation::Paired('{', '}'), 2),
-                        (Punctuation::Paired('[', ']'), 2),
-                        (Punctuation::Paired('"', '"'), 13),
-                        (Punctuation::Paired('\'', '\''), 10),
-                        (Punctuation::InBetweener(InnerWord::Dash), 10),
-                    ];
-                    protoplast.append(&mut we);
+                    protoplast.append(&mut punctuation_profile(word_list));
                 }
-                TestMod::Numbers => {
-                    protoplast.push((Punctuation::InBetweener(InnerWord::Number), 150));
+                // numbers and symbols replace words instead of being thrown in between
+                TestMod::Numbers | TestMod::Symbols => {}
+                TestMod::Capitalization => {}
+            }
+        }
+
+        if let Some(weights) = weights {
+            for (p, w) in protoplast.iter_mut() {
+                if let Some(user_weight) = p.key().and_then(|k| weights.get(&k)) {
+                    *w = *user_weight;
                 }
-                // TODO symbols may be better served with their own punctuation table
-                TestMod::Symbols => {
-                    protoplast.push((Punctuation::InBetweener(InnerWord::Symbol), 71));
+            }
+        }
+
+        if let Some(kinds) = kinds {
+            for (p, w) in protoplast.iter_mut() {
+                if p.kind().is_some_and(|kind| !kinds.contains(&kind)) {
+                    *w = 0;
                 }
-                TestMod::Capitalization => {}
             }
         }
+
+        if curly_quotes {
+            for (p, _) in protoplast.iter_mut() {
+                *p = p.curled();
+            }
+        }
+
         Self::from_protoplast(protoplast)
     }
 }
 
+/// Punctuation of the language the word list is in
+/// word lists are matched by the start of their name e.g. french_10k is french
+/// anything unknown gets the english punctuation
+pub fn punctuation_profile(word_list: &str) -> Vec<(Punctuation, u16)> {
+    let word_list = word_list.to_lowercase();
+    if word_list.starts_with("french") {
+        return vec![
+            (Punctuation::End('.'), 60),
+            (Punctuation::End('?'), 8),
+            (Punctuation::End('!'), 7),
+            (Punctuation::Normal(','), 60),
+            (Punctuation::Normal(';'), 4),
+            (Punctuation::Normal(':'), 4),
+            (Punctuation::Paired('(', ')'), 5),
+            (Punctuation::Paired('«', '»'), 15),
+            (Punctuation::Paired('"', '"'), 3),
+            (Punctuation::InBetweener(InnerWord::Dash), 10),
+        ];
+    }
+
+    if word_list.starts_with("german") {
+        return vec![
+            (Punctuation::End('.'), 65),
+            (Punctuation::End('?'), 7),
+            (Punctuation::End('!'), 7),
+            (Punctuation::Normal(','), 70),
+            (Punctuation::Normal(';'), 2),
+            (Punctuation::Normal(':'), 4),
+            (Punctuation::Paired('(', ')'), 5),
+            (Punctuation::Paired('„', '“'), 15),
+            (Punctuation::Paired('»', '«'), 3),
+            (Punctuation::InBetweener(InnerWord::Dash), 10),
+        ];
+    }
+
+    vec![
+        (Punctuation::End('.'), 65),
+        (Punctuation::End('?'), 8),
+        (Punctuation::End('!'), 6),
+        (Punctuation::Normal(','), 61),
+        (Punctuation::Normal(';'), 3),
+        (Punctuation::Normal(':'), 3),
+        (Punctuation::Paired('<', '>'), 2),
+        (Punctuation::Paired('(', ')'), 5),
+        (Punctuation::Paired('{', '}'), 2),
+        (Punctuation::Paired('[', ']'), 2),
+        (Punctuation::Paired('"', '"'), 13),
+        (Punctuation::Paired('\'', '\''), 10),
+        (Punctuation::InBetweener(InnerWord::Dash), 10),
+    ]
+}
+
 impl PunctuationInsertFrequency {
-    pub fn choose(&self, rng: &mut ThreadRng) -> Punctuation {
+    pub fn choose(&self, rng: &mut impl Rng) -> Punctuation {
         self.symbols[self.weighted_index.sample(rng)]
     }
 }
+
+/// Creates a WeightedIndex of symbol clusters
+/// that are thrown in between words by the symbols mod
+/// mostly stuff one would type while programming
+pub struct SymbolFrequency {
+    weighted_index: WeightedIndex<u16>,
+    clusters: Vec<String>,
+}
+
+impl Default for SymbolFrequency {
+    fn default() -> Self {
+        let protoplast: [(&'static str, u16); 24] = [
+            ("&&", 8),
+            ("||", 8),
+            ("=>", 8),
+            ("->", 8),
+            ("::", 8),
+            ("==", 6),
+            ("!=", 6),
+            ("<=", 4),
+            (">=", 4),
+            ("+=", 4),
+            ("-=", 3),
+            ("#", 5),
+            ("$", 5),
+            ("%", 4),
+            ("@", 4),
+            ("^", 2),
+            ("&", 5),
+            ("*", 5),
+            ("_", 4),
+            ("=", 5),
+            ("+", 4),
+            ("/", 4),
+            ("|", 3),
+            ("\\", 2),
+        ];
+
+        Self::from_weights(
+            protoplast
+                .into_iter()
+                .map(|(cluster, weight)| (cluster.to_string(), weight))
+                .collect(),
+        )
+        .expect("weights are non-zero")
+    }
+}
+
+impl SymbolFrequency {
+    fn from_weights(weights: Vec<(String, u16)>) -> Option<Self> {
+        let (clusters, weights): (Vec<String>, Vec<u16>) = weights.into_iter().unzip();
+        Some(Self {
+            weighted_index: WeightedIndex::new(weights).ok()?,
+            clusters,
+        })
+    }
+
+    /// Reads a symbol file with a cluster and an optional weight per line
+    /// a missing or empty file gives the default clusters
+    pub fn from_file(path: &Path) -> Self {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                warn!("couldn't read symbols {:?}: {}", path, e);
+                return Self::default();
+            }
+        };
+
+        Self::parse(&text).unwrap_or_else(|| {
+            warn!("no symbols in {:?}, using the default ones", path);
+            Self::default()
+        })
+    }
+
+    /// Clusters written like "=> 8", clusters without a weight weigh 1
+    /// lines with a weight that can't be read are skipped,
+    /// None if no line is left
+    fn parse(text: &str) -> Option<Self> {
+        let mut weights = vec![];
+        for line in text.lines() {
+            let mut parts = line.split_whitespace();
+            let cluster = match parts.next() {
+                Some(cluster) => cluster,
+                None => continue,
+            };
+            let weight = match (parts.next(), parts.next()) {
+                (None, _) => Ok(1),
+                (Some(weight), None) => weight.parse::<u16>(),
+                _ => "".parse(),
+            };
+
+            match weight {
+                Ok(0) => (),
+                Ok(weight) => weights.push((cluster.to_string(), weight)),
+                Err(_) => warn!("skipping malformed symbol line {:?}", line),
+            }
+        }
+        Self::from_weights(weights)
+    }
+
+    pub fn choose(&self, rng: &mut impl Rng) -> &str {
+        &self.clusters[self.weighted_index.sample(rng)]
+    }
+
+    #[cfg(test)]
+    pub fn contains(&self, cluster: &str) -> bool {
+        self.clusters.iter().any(|c| c == cluster)
+    }
+}
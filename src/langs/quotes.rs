@@ -0,0 +1,77 @@
+//! Quotes are kept in files in the quotes directory of the storage
+//! one quote per line, optionally followed by a tab and the author
+
+use crate::storage;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quote {
+    pub text: String,
+    pub author: Option<String>,
+}
+
+impl Quote {
+    /// parses "text\tauthor", the author part is optional
+    pub fn from_line(line: &str) -> Self {
+        let (text, author) = match line.split_once('\t') {
+            Some((text, author)) => (text, Some(author.trim())),
+            None => (line, None),
+        };
+
+        Self {
+            text: text.trim().to_string(),
+            author: author.filter(|a| !a.is_empty()).map(String::from),
+        }
+    }
+
+    /// random quote from the quotes file, blank lines are skipped
+    /// a file that isn't text or has no quotes in it is an error
+    pub fn random_from(quotes_file: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let quotes_file = quotes_file.as_ref();
+        let contents = storage::read_text(quotes_file)?;
+        let quotes: Vec<&str> = contents.lines().filter(|l| !l.trim().is_empty()).collect();
+        anyhow::ensure!(!quotes.is_empty(), "{:?} has no quotes", quotes_file);
+
+        Ok(Self::from_line(quotes[fastrand::usize(..quotes.len())]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_quote_from_line() {
+        let quote = Quote::from_line("Well begun is half done.\tAristotle\n");
+        assert_eq!(quote.text, "Well begun is half done.");
+        assert_eq!(quote.author.as_deref(), Some("Aristotle"));
+
+        let anonymous = Quote::from_line("  Nobody said this.  ");
+        assert_eq!(anonymous.text, "Nobody said this.");
+        assert_eq!(anonymous.author, None);
+
+        assert_eq!(Quote::from_line("Empty author.\t ").author, None);
+    }
+
+    #[test]
+    fn test_random_quote() {
+        let dir = std::env::temp_dir().join("smokey_test_random_quote");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("quotes");
+        fs::write(&path, "\nOnly one.\tSomeone\n\n").unwrap();
+
+        let quote = Quote::random_from(&path).unwrap();
+        assert_eq!(quote.text, "Only one.");
+        assert_eq!(quote.author.as_deref(), Some("Someone"));
+
+        // nothing usable errors out instead of panicking
+        fs::write(&path, "\n  \n").unwrap();
+        assert!(Quote::random_from(&path).is_err());
+        fs::write(&path, b"\xff\xfe quote\n").unwrap();
+        assert!(Quote::random_from(&path).is_err());
+        assert!(Quote::random_from(dir.join("missing")).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
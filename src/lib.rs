@@ -6,6 +6,9 @@ pub mod application;
 pub mod colorscheme;
 pub mod config;
 pub mod database;
+#[cfg(feature = "fetch")]
+pub mod fetch;
+pub mod keys;
 pub mod settings;
 pub mod storage;
 pub mod typer;
@@ -18,13 +21,36 @@ use simplelog::*;
 
 use application::App;
 use crossterm::event::{poll, read, Event as CEvent};
-use std::{fs::File, io::Stdout, time::Duration};
+use std::{
+    fs::File,
+    io::{Stdout, Write},
+};
 use tui::{backend::CrosstermBackend, Terminal};
 use utils::termprep;
 
+pub use painters::Canvas;
+
 pub type Backend = CrosstermBackend<Stdout>;
 pub type Term = Terminal<Backend>;
 
+/// Runs the app until the user quits, the terminal is restored afterwards
+/// and the picked test is remembered for the next time
+/// ```no_run
+/// use smokey::application::App;
+/// use smokey::colorscheme::Theme;
+/// use smokey::settings::TypingTestConfig;
+/// use std::io::stdout;
+/// use tui::{backend::CrosstermBackend, Terminal};
+///
+/// let test_cfg = TypingTestConfig {
+///     name: String::from("english"),
+///     length: 50,
+///     ..Default::default()
+/// };
+/// let app = App::with_config(Theme::default(), test_cfg);
+/// let terminal = Terminal::new(CrosstermBackend::new(stdout())).unwrap();
+/// smokey::run(app, terminal).unwrap();
+/// ```
 pub fn run(mut app: App, terminal: Term) -> crossterm::Result<()> {
     #[cfg(debug_assertions)]
     init_logger();
@@ -33,24 +59,47 @@ pub fn run(mut app: App, terminal: Term) -> crossterm::Result<()> {
     app.reset_test();
 
     termprep::init();
-    main_loop(app, terminal)?;
+    if let Some(shape) = app.cursor.shape() {
+        termprep::set_cursor_shape(shape);
+    }
+    app.resize(terminal.size()?.width);
+    main_loop(&mut app, terminal)?;
     termprep::shutdown();
 
+    // only now that the terminal is back to normal
+    for report in &app.json_reports {
+        println!("{}", report);
+    }
+
+    config::save_last_test(&app.settings.test_cfg);
+
     Ok(())
 }
 
-fn main_loop(mut app: App, mut terminal: Term) -> crossterm::Result<()> {
+fn main_loop(app: &mut App, mut terminal: Term) -> crossterm::Result<()> {
     while app.is_alive {
-        // drawing to the screen
-        app.paint(&mut terminal);
+        // drawing to the screen, only if anything changed
+        if app.dirty {
+            app.paint(&mut terminal);
+        }
+        // the bell goes through the same stdout the frames do
+        // it doesn't move the cursor so the frame stays intact
+        if app.take_bell() {
+            let backend = terminal.backend_mut();
+            backend.write_all(b"\x07")?;
+            backend.flush()?;
+        }
 
         // handling events
-        if poll(Duration::from_millis(250))? {
-            let read = read()?;
-            if let CEvent::Key(event) = read {
-                app.handle_key_event(event)
+        if poll(app.poll_timeout)? {
+            match read()? {
+                CEvent::Key(event) => app.handle_key_event(event),
+                CEvent::Resize(width, _) => app.resize(width),
+                _ => (),
             }
         }
+
+        app.tick();
     }
     Ok(())
 }
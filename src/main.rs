@@ -6,25 +6,57 @@
 //! by ukmrs https://github.com/ukmrs/smokey
 //! A simple typing test terminal UI app
 
-use smokey::{application::App, database, storage};
+use smokey::{
+    application::{App, JsonOutput},
+    config::{self, try_parse_mods},
+    database,
+    settings::{
+        is_drill, is_quote, is_script, TestVariant, TypingTestConfig, DRILL_SIGN, TEST_MODS,
+    },
+    storage,
+};
 
 use clap::Parser;
 use std::io::stdout;
+use std::path::PathBuf;
+use std::process;
 use tui::{backend::CrosstermBackend, Terminal};
 
 fn main() -> crossterm::Result<()> {
     let opt = Opt::parse();
+    // first run without the storage build.rs copies over
+    storage::bootstrap();
     if execute_info_requests(&opt) {
         return Ok(());
     }
 
-    #[allow(unused_mut)]
-    let mut sout = stdout();
+    let mut final_config = config::get_final_config();
+    if let Some(code) = &opt.code {
+        match config::decode_config(code) {
+            Ok(ttc) => final_config.typing_test_config = ttc,
+            Err(e) => {
+                eprintln!("couldn't use the code: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+    apply_test_overrides(&opt, &mut final_config.typing_test_config);
 
-    let backend = CrosstermBackend::new(sout);
-    let terminal = Terminal::new(backend)?;
+    if opt.share {
+        println!(
+            "{}",
+            config::encode_config(&final_config.typing_test_config)
+        );
+        return Ok(());
+    }
 
-    let app = App::from_config();
+    let backend = CrosstermBackend::new(stdout());
+    let terminal = Terminal::new(backend)?;
+    let mut app = App::from_final_config(final_config);
+    app.json_output = opt.json_output.map(|path| match path {
+        Some(path) => JsonOutput::File(path),
+        None => JsonOutput::Stdout,
+    });
 
     smokey::run(app, terminal)?;
     Ok(())
@@ -44,6 +76,40 @@ struct Opt {
     /// Prints out summaries of n most recent runs
     #[structopt(short, long, name = "n")]
     recent: Option<Option<usize>>,
+
+    /// Starts with the given word list or #!script
+    #[structopt(short, long)]
+    words: Option<String>,
+
+    /// Starts with a test of the given length
+    #[structopt(short, long)]
+    length: Option<usize>,
+
+    /// Starts with the given word pool
+    #[structopt(short, long)]
+    pool: Option<usize>,
+
+    /// Starts with the given mods e.g. punctuation,numbers
+    #[structopt(short, long)]
+    mods: Option<String>,
+
+    /// Starts with the test setup from a code made by --share
+    #[structopt(long)]
+    code: Option<String>,
+
+    /// Prints a code of the test setup to share and exits
+    #[structopt(long)]
+    share: bool,
+
+    /// Prints finished tests as json lines on quit
+    /// or appends them to the given file as they finish
+    #[structopt(long, name = "path")]
+    json_output: Option<Option<PathBuf>>,
+
+    /// Downloads a word list into the storage under the given name
+    #[cfg(feature = "fetch")]
+    #[structopt(long, number_of_values = 2, value_names = &["name", "url"])]
+    fetch: Option<Vec<String>>,
 }
 
 fn execute_info_requests(opt: &Opt) -> bool {
@@ -61,8 +127,73 @@ fn execute_info_requests(opt: &Opt) -> bool {
     if let Some(us) = opt.recent {
         let history_lines = us.unwrap_or(12);
         should_exit = true;
+        database::init::debug_init_db();
         database::RunHistoryDatbase::default().print_history(history_lines);
     }
 
+    #[cfg(feature = "fetch")]
+    if let Some([name, url]) = opt.fetch.as_deref() {
+        should_exit = true;
+        match smokey::fetch::fetch_word_list(name, url) {
+            Ok(path) => println!("saved {}", path.display()),
+            Err(e) => {
+                eprintln!("couldn't fetch {}: {:#}", name, e);
+                process::exit(1);
+            }
+        }
+    }
+
     should_exit
 }
+
+/// Overwrites test config with the one requested through cli
+/// exits if mods can't be parsed
+fn apply_test_overrides(opt: &Opt, ttc: &mut TypingTestConfig) {
+    if let Some(words) = &opt.words {
+        let mut requested = TypingTestConfig::default();
+        if is_script(words) {
+            requested.variant = TestVariant::Script;
+            requested.name = words[2..].to_string();
+        } else if is_quote(words) {
+            requested.variant = TestVariant::Quote;
+            requested.name = words[2..].to_string();
+        } else if is_drill(words) {
+            requested.variant = TestVariant::Drill;
+            requested.name = words[DRILL_SIGN.len()..].to_string();
+        } else {
+            requested.name = words.to_string();
+        }
+
+        if requested.is_available() {
+            ttc.name = requested.name;
+            ttc.variant = requested.variant;
+        } else {
+            eprintln!("{} doesn't exist, falling back to {}", words, ttc.name);
+        }
+    }
+
+    if let Some(length) = opt.length {
+        if length > 0 {
+            ttc.length = length;
+        }
+    }
+
+    if let Some(pool) = opt.pool {
+        if pool > 0 {
+            ttc.word_pool = pool;
+        }
+    }
+
+    if let Some(raw_mods) = &opt.mods {
+        let raw_mods: Vec<String> = raw_mods.split(',').map(|m| m.to_string()).collect();
+        match try_parse_mods(&raw_mods) {
+            Ok(mods) => ttc.mods = mods,
+            Err(unknown) => {
+                let mut valid: Vec<&str> = TEST_MODS.left_values().copied().collect();
+                valid.sort_unstable();
+                eprintln!("unknown mod: {}\nvalid mods: {}", unknown, valid.join(", "));
+                process::exit(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use crate::application::App;
+use crate::database::RunRecord;
+
+use super::settings::render_stateful_list;
+use super::Canvas;
+use tui::{
+    layout::{Alignment, Constraint, Layout},
+    widgets::{Block, Borders, Paragraph},
+};
+
+pub fn draw_history(f: &mut Canvas, app: &mut App) {
+    let area = Layout::default()
+        .vertical_margin(app.margin)
+        .horizontal_margin(app.margin)
+        .constraints([Constraint::Percentage(100)].as_ref())
+        .split(f.size())[0];
+
+    if app.settings.history.items.is_empty() {
+        let block = Paragraph::new("no runs yet")
+            .alignment(Alignment::Center)
+            .block(Block::default().title("history").borders(Borders::ALL));
+        f.render_widget(block, area);
+        return;
+    }
+
+    let rows: Vec<String> = app.settings.history.items.iter().map(format_run).collect();
+    render_stateful_list(
+        f,
+        &rows,
+        &mut app.settings.history.state,
+        "history",
+        area,
+        Some(app.settings.colors.active),
+    );
+}
+
+/// date, test, wpm and accuracy of a run in one line
+fn format_run(run: &RunRecord) -> String {
+    format!(
+        "{}  {:>7.2}  {:>6.2}%  {}",
+        run.local_date,
+        run.wpm,
+        run.acc,
+        run.describe()
+    )
+}
@@ -1,25 +1,110 @@
 //! # painters
 //! contains **Painter** functions of the following signature
 //! ```
-//! use smokey::{application::App, Term};
-//! pub type Painter = fn(&mut Term, &mut App);
+//! use smokey::{application::App, Canvas};
+//! pub type Painter = fn(&mut Canvas, &mut App);
 //! ```
+//! painters draw on a [Canvas] that isn't tied to any backend,
+//! so the same painter draws to the real terminal and to a test buffer
 //! the idea is to assign the pointer to specific Painter function
 //! once in a while when it's appropriate instead of checking
 //! against app state all the time to determine which one to use
 //!
 //! All painters had been trained using Bob Ross' *The Joy of Painting*
 
+mod history;
 mod post;
 mod settings;
+mod theme_editor;
 mod typer;
 
-use crate::{application::App, Term};
+use crate::application::App;
+use tui::{
+    backend::Backend,
+    buffer::Buffer,
+    layout::Rect,
+    widgets::{StatefulWidget, Widget},
+    Terminal,
+};
 
 // re-exports
+pub use history::draw_history;
 pub use post::draw_post;
 pub use settings::draw_settings;
-pub use typer::draw_test_and_update;
+pub use theme_editor::draw_theme_editor;
+pub use typer::{draw_scrolling_test, draw_submit_test, draw_test};
 
 /// Signature of a function responsible for drawing to the terminal
-pub type Painter = fn(&mut Term, &mut App);
+pub type Painter = fn(&mut Canvas, &mut App);
+
+/// The part of a frame painters get to use, mirrors tui's Frame
+/// but draws into a plain buffer
+pub struct Canvas<'b> {
+    buffer: &'b mut Buffer,
+    area: Rect,
+    cursor: Option<(u16, u16)>,
+}
+
+impl Canvas<'_> {
+    pub fn size(&self) -> Rect {
+        self.area
+    }
+
+    pub fn render_widget<W: Widget>(&mut self, widget: W, area: Rect) {
+        widget.render(area, self.buffer);
+    }
+
+    pub fn render_stateful_widget<W: StatefulWidget>(
+        &mut self,
+        widget: W,
+        area: Rect,
+        state: &mut W::State,
+    ) {
+        widget.render(area, self.buffer, state);
+    }
+
+    /// the cursor is hidden unless a painter puts it somewhere
+    pub fn set_cursor(&mut self, x: u16, y: u16) {
+        self.cursor = Some((x, y));
+    }
+}
+
+/// Draws one frame with the painter on any backend
+pub fn paint<B: Backend>(terminal: &mut Terminal<B>, app: &mut App, painter: Painter) {
+    terminal
+        .draw(|frame| {
+            let area = frame.size();
+            let mut cursor = None;
+            frame.render_widget(
+                Painting {
+                    painter,
+                    app: &mut *app,
+                    cursor: &mut cursor,
+                },
+                area,
+            );
+            if let Some((x, y)) = cursor {
+                frame.set_cursor(x, y);
+            }
+        })
+        .expect("drawing went fine");
+}
+
+/// Runs a painter as a widget so it gets the frame's buffer
+struct Painting<'a, 'c> {
+    painter: Painter,
+    app: &'a mut App<'c>,
+    cursor: &'a mut Option<(u16, u16)>,
+}
+
+impl Widget for Painting<'_, '_> {
+    fn render(self, area: Rect, buffer: &mut Buffer) {
+        let mut canvas = Canvas {
+            buffer,
+            area,
+            cursor: None,
+        };
+        (self.painter)(&mut canvas, self.app);
+        *self.cursor = canvas.cursor;
+    }
+}
@@ -1,156 +1,412 @@
 use crate::application::App;
+use crate::database;
+use crate::typer;
 
+use super::Canvas;
 use tui::{
-    backend::Backend,
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     symbols,
     text::{Span, Spans},
-    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, Paragraph},
-    Terminal,
+    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, Paragraph, Sparkline, Wrap},
 };
 
+use std::time::Duration;
+
 const WINCOLOR: Color = Color::Yellow;
 const STANDARDCOLOR: Color = Color::Cyan;
+/// tests up to this long get a single bar in the wpm per second graph
+const SHORT_TEST_SECONDS: usize = 2;
+/// most missed keys listed in the summary
+const PROBLEM_KEYS: usize = 3;
+
+pub fn draw_post(frame: &mut Canvas, app: &mut App) {
+    let summary = &app.settings.test_cfg.test_summary;
+    let test_cfg = &app.settings.test_cfg;
+    let test = &app.test;
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(15), Constraint::Percentage(85)].as_ref())
+        .vertical_margin(app.margin)
+        .horizontal_margin(app.margin)
+        .split(frame.size());
+
+    let final_wpm = format!("{}", summary.net_wpm.round());
+    let final_acc = format!("{}", summary.acc.round());
+    let diff = format!(
+        "{}",
+        summary.net_wpm - app.settings.postbox.cached_historic_wpm
+    )[..6]
+        .to_string();
+
+    let secs: f64 = test.hoarder.seconds as f64;
+    let length: f64 = test.hoarder.wpms.len() as f64;
+    let hoarder_samples = test.hoarder.get_min_max_wpm();
+    let history_max_wpm: f64 = app.settings.postbox.cached_historic_wpm;
+
+    let mut wpm_line_style = Style::default().fg(STANDARDCOLOR);
+
+    if summary.net_wpm > history_max_wpm {
+        wpm_line_style = Style::default().fg(WINCOLOR);
+    }
+
+    let mut up_txt = vec![
+        Spans::from(vec![
+            Span::raw("wpm: "),
+            Span::styled(final_wpm, Style::default().fg(wpm_line_style.fg.unwrap())),
+        ]),
+        Spans::from(vec![
+            Span::raw("raw: "),
+            Span::raw(format!("{}", summary.raw_wpm.round())),
+        ]),
+        Spans::from(vec![
+            Span::raw("acc: "),
+            Span::styled(final_acc, Style::default().fg(Color::Green)),
+        ]),
+        Spans::from(vec![
+            Span::raw("con: "),
+            Span::raw(format!("{}%", summary.consistency.round())),
+        ]),
+        Spans::from(vec![
+            Span::raw("mis: "),
+            Span::styled(
+                format!("{}", summary.mistakes),
+                Style::default().fg(test.colors.wrong),
+            ),
+        ]),
+        Spans::from(vec![Span::styled(diff, wpm_line_style)]),
+        Spans::from(vec![
+            Span::raw("pb: "),
+            Span::raw(format_stat(app.settings.postbox.stats.map(|s| s.best))),
+        ]),
+        Spans::from(vec![
+            Span::raw(format!("avg{}: ", database::RECENT_RUNS_AMOUNT)),
+            Span::raw(format_stat(
+                app.settings.postbox.stats.map(|s| s.recent_avg),
+            )),
+        ]),
+    ];
+
+    let problem_keys = test.problem_keys(PROBLEM_KEYS);
+    if !problem_keys.is_empty() {
+        up_txt.push(Spans::from(vec![
+            Span::raw("keys: "),
+            Span::styled(
+                format_problem_keys(&problem_keys),
+                Style::default().fg(test.colors.wrong),
+            ),
+        ]));
+    }
+
+    if let Some(prev) = test.previous_wpm {
+        up_txt.push(Spans::from(vec![
+            Span::raw("prev: "),
+            Span::raw(format!(
+                "{} ({:+})",
+                prev.round(),
+                (summary.net_wpm - prev).round()
+            )),
+        ]));
+    }
+
+    if !app.run_saved {
+        up_txt.push(Spans::from("not saved"));
+    }
+
+    if let Some(secs) = app.advance_left() {
+        up_txt.push(Spans::from(format!("next: {}s", secs)));
+    }
+
+    // TODO move this logic to TypingTestConfig???;
+    let graph_title = match &test.quote_author {
+        Some(author) => format!("{} - {}", test_cfg, author),
+        None => format!("{}", test_cfg),
+    };
+
+    let block =
+        Paragraph::new(up_txt).block(Block::default().title("summary").borders(Borders::ALL));
+
+    frame.render_widget(block, chunks[0]);
+
+    let mut wpm_dataset: Vec<(f64, f64)> = Vec::with_capacity(length as usize);
+    let mut pb_dataset: Vec<(f64, f64)> = Vec::with_capacity(length as usize);
+    let mut target_dataset: Vec<(f64, f64)> = vec![];
+
+    for (i, wpm) in test.hoarder.wpms.iter().enumerate() {
+        let sec = (i + 1) as f64 * secs;
+        wpm_dataset.push((sec, *wpm));
+        pb_dataset.push((sec, history_max_wpm));
+        if let Some(target) = app.target_wpm {
+            target_dataset.push((sec, target));
+        }
+    }
+
+    let mut wpm_datasets = vec![
+        Dataset::default()
+            // .name("pb")
+            .marker(symbols::Marker::Braille)
+            .style(Style::default().fg(Color::Blue))
+            .graph_type(GraphType::Line)
+            .data(&pb_dataset),
+        Dataset::default()
+            // .name("wpm")
+            .marker(symbols::Marker::Braille)
+            .style(wpm_line_style)
+            .graph_type(GraphType::Line)
+            .data(&wpm_dataset),
+    ];
+    if app.target_wpm.is_some() {
+        wpm_datasets.insert(
+            0,
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .style(Style::default().fg(app.settings.theme.hover))
+                .graph_type(GraphType::Line)
+                .data(&target_dataset),
+        );
+    }
+
+    let x_labels: Vec<Span> = vec![
+        wpm_dataset[0].0,
+        wpm_dataset[(wpm_dataset.len() - 1) / 2].0,
+        wpm_dataset[wpm_dataset.len() - 1].0,
+    ]
+    .into_iter()
+    .map(|i| Span::styled(format!("{}", i), Style::default().fg(Color::Blue)))
+    .collect();
+
+    let (y_lower_bound, y_upper_bound) =
+        wpm_bounds(hoarder_samples, history_max_wpm, app.target_wpm);
+
+    let y_label_lower_bound = y_lower_bound.div_euclid(10.) as i32;
+
+    let y_labels: Vec<Span> = (y_label_lower_bound..=y_upper_bound.div_euclid(10.) as i32)
+        .map(|i| Span::styled(format!("{}", i * 10), Style::default().fg(Color::Blue)))
+        .collect();
+
+    let chart = Chart::new(wpm_datasets)
+        .block(
+            Block::default()
+                .title(Span::styled(
+                    graph_title,
+                    Style::default()
+                        .fg(Color::Blue)
+                        .add_modifier(Modifier::BOLD),
+                ))
+                .borders(Borders::ALL),
+        )
+        .x_axis(
+            Axis::default()
+                .title("time (s)")
+                .style(Style::default().fg(Color::Gray))
+                .bounds([secs, length * secs])
+                .labels(x_labels)
+                .labels_alignment(Alignment::Center),
+        )
+        .y_axis(
+            Axis::default()
+                // .title("wpm")
+                .style(Style::default().fg(Color::Gray))
+                .bounds([y_lower_bound, y_upper_bound])
+                .labels(y_labels),
+        );
+
+    // blind tests reveal what was typed wrong here
+    let constraints = match app.blind {
+        true => [
+            Constraint::Percentage(55),
+            Constraint::Percentage(20),
+            Constraint::Percentage(25),
+        ]
+        .to_vec(),
+        false => [Constraint::Percentage(75), Constraint::Percentage(25)].to_vec(),
+    };
+    let graph_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(chunks[1]);
+
+    let speed_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(graph_chunks[1]);
+
+    frame.render_widget(chart, graph_chunks[0]);
+    draw_wpm_per_second(frame, &test.key_times, speed_chunks[0]);
+    draw_wpm_per_word(frame, &summary.wpm_per_word, speed_chunks[1]);
+    if app.blind {
+        let typed = Paragraph::new(vec![
+            Spans::from(test.up.clone()),
+            Spans::from(test.active[..test.done].to_vec()),
+        ])
+        .block(Block::default().title("typed").borders(Borders::ALL))
+        .wrap(Wrap { trim: false });
+        frame.render_widget(typed, graph_chunks[2]);
+    }
+}
 
-pub fn draw_post<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) {
-    terminal
-        .draw(|frame| {
-            let summary = &app.settings.test_cfg.test_summary;
-            let test_cfg = &app.settings.test_cfg;
-            let test = &app.test;
-
-            let chunks = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints([Constraint::Percentage(15), Constraint::Percentage(85)].as_ref())
-                .vertical_margin(app.margin)
-                .horizontal_margin(app.margin)
-                .split(frame.size());
-
-            let final_wpm = format!("{}", summary.wpm.round());
-            let final_acc = format!("{}", summary.acc.round());
-            let diff = format!("{}", summary.wpm - app.settings.postbox.cached_historic_wpm)[..6]
-                .to_string();
-
-            let secs: f64 = test.hoarder.seconds as f64;
-            let length: f64 = test.hoarder.wpms.len() as f64;
-            let hoarder_max_wpm: f64 = test.hoarder.get_max_wpm();
-            let (hmin_wpm, hmax_wpm) = test.hoarder.get_min_max_wpm();
-            let history_max_wpm: f64 = app.settings.postbox.cached_historic_wpm;
-
-            let mut wpm_line_style = Style::default().fg(STANDARDCOLOR);
-
-            if summary.wpm > history_max_wpm {
-                wpm_line_style = Style::default().fg(WINCOLOR);
-            }
-
-            let highest = f64::max(hoarder_max_wpm, history_max_wpm);
-
-            let up_txt = vec![
-                Spans::from(vec![
-                    Span::raw("wpm: "),
-                    Span::styled(final_wpm, Style::default().fg(wpm_line_style.fg.unwrap())),
-                ]),
-                Spans::from(vec![
-                    Span::raw("acc: "),
-                    Span::styled(final_acc, Style::default().fg(Color::Green)),
-                ]),
-                Spans::from(vec![
-                    Span::raw("mis: "),
-                    Span::styled(
-                        format!("{}", summary.mistakes),
-                        Style::default().fg(test.colors.wrong),
-                    ),
-                ]),
-                Spans::from(vec![Span::styled(diff, wpm_line_style)]),
-            ];
-
-            // TODO move this logic to TypingTestConfig???;
-            let graph_title = format!("{}", test_cfg);
-
-            let block = Paragraph::new(up_txt)
-                .block(Block::default().title("summary").borders(Borders::ALL));
-
-            frame.render_widget(block, chunks[0]);
-
-            let mut wpm_dataset: Vec<(f64, f64)> = Vec::with_capacity(length as usize);
-            let mut pb_dataset: Vec<(f64, f64)> = Vec::with_capacity(length as usize);
-
-            for (i, wpm) in test.hoarder.wpms.iter().enumerate() {
-                let sec = (i + 1) as f64 * secs;
-                wpm_dataset.push((sec, *wpm));
-                pb_dataset.push((sec, history_max_wpm));
-            }
-
-            let wpm_datasets = vec![
-                Dataset::default()
-                    // .name("pb")
-                    .marker(symbols::Marker::Braille)
-                    .style(Style::default().fg(Color::Blue))
-                    .graph_type(GraphType::Line)
-                    .data(&pb_dataset),
-                Dataset::default()
-                    // .name("wpm")
-                    .marker(symbols::Marker::Braille)
-                    .style(wpm_line_style)
-                    .graph_type(GraphType::Line)
-                    .data(&wpm_dataset),
-            ];
-
-            let x_labels: Vec<Span> = vec![
-                wpm_dataset[0].0,
-                wpm_dataset[(wpm_dataset.len() - 1) / 2].0,
-                wpm_dataset[wpm_dataset.len() - 1].0,
-            ]
-            .into_iter()
-            .map(|i| Span::styled(format!("{}", i), Style::default().fg(Color::Blue)))
+/// Lower and upper bounds of the wpm axis for the samples' min and max,
+/// the personal best and the target lines always fit in between
+fn wpm_bounds(samples: (f64, f64), history_max_wpm: f64, target: Option<f64>) -> (f64, f64) {
+    let (hmin_wpm, hmax_wpm) = samples;
+    // the higher of the two flat lines
+    let top_line = f64::max(history_max_wpm, target.unwrap_or(0.));
+    let highest = f64::max(hmax_wpm, top_line);
+    let lowest = f64::min(hmin_wpm, target.unwrap_or(hmin_wpm));
+
+    // Only apply larger margin if personal best line would be unsightly close
+    // to the chart upper frame
+    let margin = if hmax_wpm - top_line > 10. || top_line % 10.0 < 8. {
+        10.
+    } else {
+        20.
+    };
+
+    let upper = highest.div_euclid(10.) * 10. + margin;
+    let lower = f64::max(0., lowest.div_euclid(10.) * 10. - 10.);
+    (lower, upper)
+}
+
+/// bars of wpm in each second stretched over the whole area
+/// tests shorter than a couple of seconds get a single bar of their average
+pub fn draw_wpm_per_second(frame: &mut Canvas, key_times: &[u32], area: Rect) {
+    let mut wpms = typer::wpm_per_second(key_times);
+    if wpms.len() <= SHORT_TEST_SECONDS && !wpms.is_empty() {
+        let millis = key_times[key_times.len() - 1].max(1000);
+        wpms = vec![typer::words_per_minute(
+            key_times.len(),
+            Duration::from_millis(millis as u64),
+        )];
+    }
+
+    let samples: Vec<u64> = wpms.iter().map(|w| w.round() as u64).collect();
+    let width = area.width.saturating_sub(2) as usize;
+    let data = fit_to_width(&samples, width);
+
+    let sparkline = Sparkline::default()
+        .block(
+            Block::default()
+                .title("wpm per second")
+                .borders(Borders::ALL),
+        )
+        .style(Style::default().fg(STANDARDCOLOR))
+        .data(&data);
+    frame.render_widget(sparkline, area);
+}
+
+/// bars of wpm of each word, skipped words are left as gaps
+pub fn draw_wpm_per_word(frame: &mut Canvas, wpms: &[f64], area: Rect) {
+    let samples: Vec<u64> = wpms
+        .iter()
+        .map(|w| match w.is_nan() {
+            true => 0,
+            false => w.round() as u64,
+        })
+        .collect();
+    let width = area.width.saturating_sub(2) as usize;
+    let data = fit_to_width(&samples, width);
+
+    let sparkline = Sparkline::default()
+        .block(Block::default().title("wpm per word").borders(Borders::ALL))
+        .style(Style::default().fg(STANDARDCOLOR))
+        .data(&data);
+    frame.render_widget(sparkline, area);
+}
+
+/// repeats samples so they fill the width
+/// or averages them in chunks if there's too many of them
+fn fit_to_width(samples: &[u64], width: usize) -> Vec<u64> {
+    if samples.is_empty() || width == 0 {
+        return vec![];
+    }
+
+    if samples.len() <= width {
+        let repeat = width / samples.len();
+        return samples
+            .iter()
+            .flat_map(|&s| std::iter::repeat_n(s, repeat))
             .collect();
+    }
 
-            // Only apply larger margin if personal best line would be unsightly close
-            // to the chart upper frame
-            let margin = if hmax_wpm - history_max_wpm > 10. || history_max_wpm % 10.0 < 8. {
-                10.
-            } else {
-                20.
-            };
-
-            let y_upper_bound: f64 = highest.div_euclid(10.) * 10. + margin;
-            let y_lower_bound: f64 = f64::max(0., hmin_wpm.div_euclid(10.) * 10. - 10.);
-
-            let y_label_lower_bound = y_lower_bound.div_euclid(10.) as i32;
-
-            let y_labels: Vec<Span> = (y_label_lower_bound..=y_upper_bound.div_euclid(10.) as i32)
-                .map(|i| Span::styled(format!("{}", i * 10), Style::default().fg(Color::Blue)))
-                .collect();
-
-            let chart = Chart::new(wpm_datasets)
-                .block(
-                    Block::default()
-                        .title(Span::styled(
-                            graph_title,
-                            Style::default()
-                                .fg(Color::Blue)
-                                .add_modifier(Modifier::BOLD),
-                        ))
-                        .borders(Borders::ALL),
-                )
-                .x_axis(
-                    Axis::default()
-                        .title("time (s)")
-                        .style(Style::default().fg(Color::Gray))
-                        .bounds([secs, length * secs])
-                        .labels(x_labels)
-                        .labels_alignment(Alignment::Center),
-                )
-                .y_axis(
-                    Axis::default()
-                        // .title("wpm")
-                        .style(Style::default().fg(Color::Gray))
-                        .bounds([y_lower_bound, y_upper_bound])
-                        .labels(y_labels),
-                );
-
-            frame.render_widget(chart, chunks[1]);
+    let chunk = samples.len().div_ceil(width);
+    samples
+        .chunks(chunk)
+        .map(|c| c.iter().sum::<u64>() / c.len() as u64)
+        .collect()
+}
+
+/// missed keys with how many times they were missed, space is shown as ␣
+fn format_problem_keys(keys: &[(char, usize)]) -> String {
+    keys.iter()
+        .map(|&(c, n)| match c {
+            ' ' => format!("␣{}", n),
+            c => format!("{}{}", c, n),
         })
-        .expect("drawing post went oof");
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// rounded stat or a dash if there is no history
+fn format_stat(stat: Option<f64>) -> String {
+    match stat {
+        Some(wpm) => format!("{}", wpm.round()),
+        None => String::from("—"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::painters::paint;
+    use tui::Terminal;
+
+    #[test]
+    fn test_fit_to_width() {
+        assert!(fit_to_width(&[], 10).is_empty());
+        assert!(fit_to_width(&[1, 2], 0).is_empty());
+        assert_eq!(fit_to_width(&[1, 2], 5), vec![1, 1, 2, 2]);
+        assert_eq!(fit_to_width(&[1, 3, 5, 7, 9], 2), vec![3, 8]);
+    }
+
+    #[test]
+    fn test_format_problem_keys() {
+        assert_eq!(format_problem_keys(&[]), "");
+        assert_eq!(format_problem_keys(&[('e', 3), (' ', 2)]), "e3 ␣2");
+    }
+
+    #[test]
+    fn test_wpm_bounds() {
+        // same as before there was a target
+        assert_eq!(wpm_bounds((43., 71.), 65., None), (30., 80.));
+        // personal best just under a ten gets more room
+        assert_eq!(wpm_bounds((43., 70.), 68.5, None), (30., 90.));
+
+        // a target above everything else still fits on the chart
+        let (lower, upper) = wpm_bounds((43., 71.), 65., Some(120.));
+        assert_eq!(lower, 30.);
+        assert!(upper > 120.);
+
+        // and so does one below the slowest second
+        let (lower, upper) = wpm_bounds((43., 71.), 65., Some(25.));
+        assert!(lower <= 25.);
+        assert_eq!(upper, 80.);
+        assert_eq!(wpm_bounds((3., 20.), 20., Some(1.)).0, 0.);
+    }
+
+    #[test]
+    fn test_draw_wpm_per_second() {
+        use tui::backend::TestBackend;
+
+        let mut terminal = Terminal::new(TestBackend::new(40, 6)).unwrap();
+        let mut app = App::setup();
+        let samples: [&[u32]; 3] = [&[], &[100, 300, 1200], &[0, 900, 1800, 2700, 3600]];
+        for key_times in samples {
+            app.test.key_times = key_times.to_vec();
+            paint(&mut terminal, &mut app, |f, app| {
+                let area = f.size();
+                draw_wpm_per_second(f, &app.test.key_times, area)
+            });
+        }
+    }
 }
@@ -1,44 +1,42 @@
 use crate::application::{App, APPLOGO};
-use crate::settings::{SetList, TypingTestConfig};
+use crate::colorscheme::Theme;
+use crate::settings::{ListEdit, SetList, TypingTestConfig, TEST_MODS};
 use std::collections::HashMap;
 
+use super::Canvas;
 use tui::{
-    backend::Backend,
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    text::Span,
+    text::{Span, Spans},
     widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
-    Frame, Terminal,
 };
 
-pub fn draw_settings<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) {
-    terminal
-        .draw(|f| {
-            let chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints(
-                    [
-                        Constraint::Percentage(20),
-                        Constraint::Percentage(40),
-                        Constraint::Percentage(40),
-                    ]
-                    .as_ref(),
-                )
-                .vertical_margin(app.margin)
-                .horizontal_margin(app.margin)
-                .split(f.size());
-
-            draw_title(f, chunks[0], &app.settings.test_cfg);
-
-            let color_code = app.settings.color_hover_or_active();
-
-            draw_row_with_freq_and_len(f, app, chunks[1], &color_code);
-            draw_row_with_words_and_mods(f, app, chunks[2], &color_code);
-        })
-        .expect("drawing settings");
+pub fn draw_settings(f: &mut Canvas, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage(20),
+                Constraint::Percentage(30),
+                Constraint::Percentage(30),
+                Constraint::Percentage(20),
+            ]
+            .as_ref(),
+        )
+        .vertical_margin(app.margin)
+        .horizontal_margin(app.margin)
+        .split(f.size());
+
+    draw_title(f, chunks[0], &app.settings.test_cfg);
+
+    let color_code = app.settings.color_hover_or_active();
+
+    draw_row_with_freq_and_len(f, app, chunks[1], &color_code);
+    draw_row_with_words_and_mods(f, app, chunks[2], &color_code);
+    draw_row_with_themes(f, app, chunks[3], &color_code);
 }
 
-pub fn draw_title<B: Backend>(f: &mut Frame<B>, area: Rect, tcfg: &TypingTestConfig) {
+pub fn draw_title(f: &mut Canvas, area: Rect, tcfg: &TypingTestConfig) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
@@ -51,8 +49,8 @@ pub fn draw_title<B: Backend>(f: &mut Frame<B>, area: Rect, tcfg: &TypingTestCon
     f.render_widget(block, chunks[1]);
 }
 
-pub fn draw_row_with_freq_and_len<B: Backend>(
-    f: &mut Frame<B>,
+pub fn draw_row_with_freq_and_len(
+    f: &mut Canvas,
     app: &mut App,
     rect: Rect,
     clrcode: &HashMap<SetList, Option<Color>>,
@@ -81,8 +79,8 @@ pub fn draw_row_with_freq_and_len<B: Backend>(
     );
 }
 
-pub fn draw_row_with_words_and_mods<B: Backend>(
-    f: &mut Frame<B>,
+pub fn draw_row_with_words_and_mods(
+    f: &mut Canvas,
     app: &mut App,
     rect: Rect,
     clrcode: &HashMap<SetList, Option<Color>>,
@@ -92,18 +90,42 @@ pub fn draw_row_with_words_and_mods<B: Backend>(
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
         .split(rect);
 
+    let tests_title = match (&app.settings.list_edit, app.settings.test_query.is_empty()) {
+        (Some(ListEdit::Delete(name)), _) => format!("delete {}? y/n", name),
+        (Some(ListEdit::Rename { to, .. }), _) => format!("rename to {}", to),
+        (None, true) => String::from("test"),
+        (None, false) => format!("test /{}", app.settings.test_query),
+    };
+
     render_stateful_list(
         f,
         &app.settings.tests_list.items,
         &mut app.settings.tests_list.state,
-        "test",
+        &tests_title,
         chunks[0],
         clrcode[&SetList::Test],
     );
 
+    // enabled mods are marked so toggling shows up right away
+    let mods: Vec<String> = app
+        .settings
+        .mods_list
+        .items
+        .iter()
+        .map(|name| {
+            let enabled = TEST_MODS
+                .get_by_left(name.as_str())
+                .is_some_and(|m| app.settings.test_cfg.mods.contains(m));
+            match enabled {
+                true => format!("{} *", name),
+                false => name.clone(),
+            }
+        })
+        .collect();
+
     render_stateful_list(
         f,
-        &app.settings.mods_list.items,
+        &mods,
         &mut app.settings.mods_list.state,
         "mods",
         chunks[1],
@@ -111,8 +133,49 @@ pub fn draw_row_with_words_and_mods<B: Backend>(
     );
 }
 
-pub fn render_stateful_list<B: Backend>(
-    f: &mut Frame<B>,
+pub fn draw_row_with_themes(
+    f: &mut Canvas,
+    app: &mut App,
+    rect: Rect,
+    clrcode: &HashMap<SetList, Option<Color>>,
+) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(rect);
+
+    render_stateful_list(
+        f,
+        &app.settings.themes_list.items,
+        &mut app.settings.themes_list.state,
+        "theme",
+        chunks[0],
+        clrcode[&SetList::Theme],
+    );
+
+    draw_theme_preview(f, app.settings.get_previewed_theme(), chunks[1]);
+}
+
+/// sample of typed text painted with colors of the theme
+pub fn draw_theme_preview(f: &mut Canvas, theme: Theme, area: Rect) {
+    let sample = Spans::from(vec![
+        Span::styled("smokey ", Style::default().fg(theme.done)),
+        Span::styled("tey", Style::default().fg(theme.mistake)),
+        Span::styled("pes ", Style::default().fg(theme.todo)),
+        Span::styled("comfy", Style::default().fg(theme.todo)),
+    ]);
+
+    let block = Paragraph::new(sample).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.hover))
+            .title(Span::styled("preview", Style::default().fg(theme.active))),
+    );
+    f.render_widget(block, area);
+}
+
+pub fn render_stateful_list(
+    f: &mut Canvas,
     sl: &[String],
     list_state: &mut ListState,
     title: &str,
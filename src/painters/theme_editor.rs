@@ -0,0 +1,97 @@
+use crate::application::App;
+use crate::colorscheme::{Channel, THEME_FIELDS};
+
+use super::settings::draw_theme_preview;
+use super::Canvas;
+use tui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Paragraph},
+};
+
+pub fn draw_theme_editor(f: &mut Canvas, app: &mut App) {
+    let draft = match &app.theme_draft {
+        Some(draft) => draft,
+        None => return,
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Length(THEME_FIELDS.len() as u16 + 2),
+                Constraint::Length(3),
+                Constraint::Min(1),
+            ]
+            .as_ref(),
+        )
+        .vertical_margin(app.margin)
+        .horizontal_margin(app.margin)
+        .split(f.size());
+
+    let fields: Vec<Spans> = THEME_FIELDS
+        .iter()
+        .zip(draft.theme.fields())
+        .enumerate()
+        .map(|(i, (field, color))| {
+            let style = match i == draft.field {
+                true => Style::default().add_modifier(Modifier::BOLD),
+                false => Style::default().fg(Color::Gray),
+            };
+            Spans::from(vec![
+                Span::styled(format!("{:<8}", field), style),
+                Span::styled("███ ", Style::default().fg(color)),
+                Span::styled(format!("{:?}", color), style),
+            ])
+        })
+        .collect();
+
+    let title = format!("{}.toml", draft.name);
+    let fields = Paragraph::new(fields).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(draft.theme.active))
+            .title(Span::styled(title, Style::default().fg(draft.theme.active))),
+    );
+    f.render_widget(fields, chunks[0]);
+
+    draw_theme_preview(f, draft.theme, chunks[1]);
+
+    let mode = match draft.channel {
+        Some(Channel::Red) => "red",
+        Some(Channel::Green) => "green",
+        Some(Channel::Blue) => "blue",
+        None => "named colors",
+    };
+    let help = Paragraph::new(vec![
+        Spans::from(format!("h l change {}", mode)),
+        Spans::from("r g b nudge a channel, n named colors"),
+        Spans::from("s save, esc discard"),
+    ])
+    .style(Style::default().fg(Color::DarkGray));
+    f.render_widget(help, chunks[2]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colorscheme::{Theme, ThemeDraft};
+    use tui::{backend::TestBackend, Terminal};
+
+    #[test]
+    fn test_draw_theme_editor() {
+        let mut app = App {
+            theme_draft: Some(ThemeDraft::new("dusk", Theme::default())),
+            ..App::default()
+        };
+        let mut terminal = Terminal::new(TestBackend::new(60, 20)).unwrap();
+        crate::painters::paint(&mut terminal, &mut app, draw_theme_editor);
+
+        let buffer = terminal.backend().buffer();
+        let text: String = buffer.content.iter().map(|c| c.symbol.as_str()).collect();
+        assert!(text.contains("dusk.toml"));
+        assert!(text.contains("mistake"));
+        assert!(text.contains("Red"));
+    }
+}
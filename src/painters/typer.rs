@@ -1,65 +1,663 @@
 use tui::{
-    backend::Backend,
-    layout::{Constraint, Direction, Layout},
-    style::{Color, Style},
-    text::Spans,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
     widgets::{Block, Borders, Paragraph, Wrap},
-    Terminal,
 };
 
-use crate::application::App;
-use crate::Term;
+use crate::application::{App, CursorStyle};
+use crate::typer::{CharDiff, TestState, ROLLING_SECONDS};
 
-pub fn draw_test_and_update(terminal: &mut Term, app: &mut App) {
-    draw_test(terminal, app);
-    app.test.update_wpm_history();
+use super::Canvas;
+
+/// letters right behind the cursor that stay bright when typed text fades
+const FADE_BEHIND: usize = 8;
+/// rows the test text needs above the status line, the submit layout has the most
+const STATUS_ABOVE: u16 = 5;
+
+pub fn draw_test(frame: &mut Canvas, app: &mut App) {
+    let test = &app.test;
+    let chunks = split_test_screen(frame);
+    draw_header(frame, app, chunks[0]);
+
+    let ghost_rect_width = frame.size().width.saturating_sub(app.paragraph) / 2;
+    let down_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(ghost_rect_width), Constraint::Min(60)].as_ref())
+        .split(chunks[1]);
+
+    if app.cursor != CursorStyle::Highlight {
+        frame.set_cursor(
+            down_chunks[0].width + test.cursor_x() - 1,
+            chunks[0].height + 1,
+        );
+    }
+
+    // the spans get copied for the frame anyway so fading costs nothing extra
+    let (up_faded, active_faded) = match app.fade_typed {
+        true => faded_counts(test),
+        false => (0, 0),
+    };
+    let active = blinded(app, dim_before(&test.active, active_faded));
+    let mut active = separated(app, dim_todo(app, active, test.done));
+    highlight_next(app, &mut active, test.done);
+    let txt = vec![
+        Spans::from(separated(app, blinded(app, dim_before(&test.up, up_faded)))),
+        Spans::from(active),
+        Spans::from(separated(app, dim_todo(app, test.down.clone(), 0))),
+    ];
+
+    let paragraph = Paragraph::new(txt)
+        .block(Block::default().borders(Borders::NONE))
+        .style(text_style(test))
+        // .alignment(Alignment::Center)
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(paragraph, down_chunks[1]);
+    draw_status(frame, app, down_chunks[1]);
+}
+
+/// Draws the test as one line that scrolls under the cursor
+/// the cursor stays in the middle unless there's nothing to scroll into
+pub fn draw_scrolling_test(frame: &mut Canvas, app: &mut App) {
+    let test = &app.test;
+    let chunks = split_test_screen(frame);
+    draw_header(frame, app, chunks[0]);
+
+    // the line sits where the active line of the wrapped layout does
+    let area = Rect {
+        x: chunks[1].x + app.margin,
+        y: chunks[1].y + 1,
+        width: chunks[1].width.saturating_sub(2 * app.margin),
+        height: 1.min(chunks[1].height.saturating_sub(1)),
+    };
+
+    let (up_faded, active_faded) = match app.fade_typed {
+        true => faded_counts(test),
+        false => (0, 0),
+    };
+    let mut line = blinded(app, dim_before(&test.up, up_faded));
+    line.extend(blinded(app, dim_before(&test.active, active_faded)));
+    line.extend(test.down.iter().cloned());
+    let mut line = separated(app, dim_todo(app, line, test.up.len() + test.done));
+    highlight_next(app, &mut line, test.up.len() + test.done);
+    let line = Spans::from(line);
+
+    let caret = Spans::from(test.up.clone()).width() as u16 + test.cursor_x() - 1;
+    let offset = scroll_offset(caret, line.width() as u16, area.width, test.down.is_empty());
+    if app.cursor != CursorStyle::Highlight {
+        frame.set_cursor(area.x + caret - offset, area.y);
+    }
+
+    let paragraph = Paragraph::new(line)
+        .style(text_style(test))
+        .scroll((0, offset));
+    frame.render_widget(paragraph, area);
+    draw_status(frame, app, chunks[1]);
 }
 
-pub fn draw_test<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) {
-    terminal
-        .draw(|frame| {
-            let test = &mut app.test;
+/// Draws the line to type with the typed out line under it,
+/// the last submitted line above shows how it went
+pub fn draw_submit_test(frame: &mut Canvas, app: &mut App) {
+    let test = &app.test;
+    let chunks = split_test_screen(frame);
+    draw_header(frame, app, chunks[0]);
+
+    let ghost_rect_width = frame.size().width.saturating_sub(app.paragraph) / 2;
+    let down_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(ghost_rect_width), Constraint::Min(60)].as_ref())
+        .split(chunks[1]);
+
+    let colors = &test.colors;
+    let wrong = match app.blind {
+        true => colors.done,
+        false => colors.wrong,
+    };
+    let shown = |c: char| match c {
+        ' ' => test.separator.to_string(),
+        c => c.to_string(),
+    };
+    let last_line: Vec<Span> = test
+        .last_line
+        .iter()
+        .map(|diff| match *diff {
+            CharDiff::Correct(c) => Span::styled(shown(c), Style::default().fg(colors.done)),
+            CharDiff::Wrong(_, c) | CharDiff::Extra(c) => {
+                Span::styled(shown(c), Style::default().fg(wrong))
+            }
+            // nothing was typed there so the letter is shown crossed out
+            CharDiff::Missing(c) => Span::styled(
+                shown(c),
+                Style::default()
+                    .fg(wrong)
+                    .add_modifier(Modifier::CROSSED_OUT),
+            ),
+        })
+        .collect();
 
-            let chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints([Constraint::Percentage(36), Constraint::Percentage(64)].as_ref())
-                .split(frame.size());
+    let rest = test.active[test.done.min(test.active.len())..].to_vec();
+    let buffer: String = test.line_buffer.chars().map(shown).collect();
+    let mut typed = vec![Span::raw(buffer)];
+    match app.cursor {
+        CursorStyle::Highlight => typed.push(Span::styled(
+            " ",
+            Style::default().add_modifier(Modifier::REVERSED),
+        )),
+        _ => frame.set_cursor(
+            down_chunks[1].x + Spans::from(typed.clone()).width() as u16,
+            down_chunks[1].y + 2,
+        ),
+    }
 
-            let wpm: String = test.calculate_wpm().round().to_string();
+    let txt = vec![
+        Spans::from(last_line),
+        Spans::from(separated(app, dim_todo(app, rest, 0))),
+        Spans::from(typed),
+        Spans::from(separated(app, dim_todo(app, test.down.clone(), 0))),
+    ];
+    let paragraph = Paragraph::new(txt)
+        .block(Block::default().borders(Borders::NONE))
+        .style(text_style(test));
+    frame.render_widget(paragraph, down_chunks[1]);
+    draw_status(frame, app, down_chunks[1]);
+}
 
-            #[allow(unused_mut)]
-            let mut dbg_info = String::new();
+/// the test setup on the bottom row of the text area,
+/// left out when the terminal is too short to keep it off the text
+fn draw_status(frame: &mut Canvas, app: &App, area: Rect) {
+    if area.height <= STATUS_ABOVE {
+        return;
+    }
+    let row = Rect {
+        y: area.bottom() - 1,
+        height: 1,
+        ..area
+    };
+    let status = Paragraph::new(app.settings.test_cfg.to_string())
+        .style(Style::default().fg(Color::DarkGray));
+    frame.render_widget(status, row);
+}
 
-            let up_txt = vec![Spans::from(wpm), Spans::from(dbg_info)];
+fn split_test_screen(frame: &Canvas) -> Vec<Rect> {
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(36), Constraint::Percentage(64)].as_ref())
+        .split(frame.size())
+}
 
-            let block = Paragraph::new(up_txt).block(Block::default().borders(Borders::NONE));
+/// progress, live wpm and accuracy above the test
+fn draw_header(frame: &mut Canvas, app: &App, area: Rect) {
+    let test = &app.test;
+    // repainted on ticks so these keep up while typing
+    let (wpm, acc) = test.live_stats();
 
-            frame.render_widget(block, chunks[0]);
+    let progress = match app.countdown_left() {
+        Some(secs) => format!("starting in {}", secs),
+        None => progress(test),
+    };
+    let mut up_txt = vec![Spans::from(progress), Spans::from(wpm.round().to_string())];
+    if test.endless && !test.first {
+        up_txt.push(Spans::from(format!(
+            "{} last {}s",
+            test.rolling_wpm().round(),
+            ROLLING_SECONDS
+        )));
+    }
+    // the accuracy would give the mistakes away
+    if !app.blind {
+        up_txt.push(Spans::from(format!("{}%", acc.round())));
+    }
+    if app.warmup {
+        up_txt.push(Spans::from("warmup"));
+    }
+    if app.confirm_leave.is_some() {
+        up_txt.push(Spans::from("leave the test? (y/n)"));
+    } else if test.is_paused() {
+        up_txt.push(Spans::from("paused"));
+    }
 
-            let ghost_rect_width = (frame.size().width - app.paragraph) / 2;
-            let down_chunks = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints([Constraint::Length(ghost_rect_width), Constraint::Min(60)].as_ref())
-                .split(chunks[1]);
+    let block = Paragraph::new(up_txt).block(Block::default().borders(Borders::NONE));
+    frame.render_widget(block, area);
+}
 
-            frame.set_cursor(
-                down_chunks[0].width + test.cursor_x - 1,
-                chunks[0].height + 1,
-            );
+fn text_style(test: &TestState) -> Style {
+    let text_style = Style::default().fg(Color::White);
+    match test.is_paused() {
+        true => text_style.add_modifier(Modifier::DIM),
+        false => text_style,
+    }
+}
 
-            let txt = vec![
-                Spans::from(app.test.up.clone()),
-                Spans::from(app.test.active.clone()),
-                Spans::from(app.test.down.clone()),
-            ];
+/// columns the line is scrolled by to keep the caret in the middle,
+/// the start doesn't scroll and neither does the end of the test
+fn scroll_offset(caret: u16, line_width: u16, width: u16, at_end: bool) -> u16 {
+    let offset = caret.saturating_sub(width / 2);
+    match at_end {
+        // room for the cursor right after the last letter
+        true => offset.min((line_width + 1).saturating_sub(width)),
+        false => offset,
+    }
+}
 
-            let paragraph = Paragraph::new(txt)
-                .block(Block::default().borders(Borders::NONE))
-                .style(Style::default().fg(Color::White))
-                // .alignment(Alignment::Center)
-                .wrap(Wrap { trim: false });
+/// how many spans of the line above and of the active line are far enough behind to fade
+fn faded_counts(test: &TestState) -> (usize, usize) {
+    let up = test
+        .up
+        .len()
+        .saturating_sub(FADE_BEHIND.saturating_sub(test.done));
+    (up, test.done.saturating_sub(FADE_BEHIND))
+}
 
-            frame.render_widget(paragraph, down_chunks[1]);
+/// copies the spans dimming the first upto of them
+fn dim_before<'a>(spans: &[Span<'a>], upto: usize) -> Vec<Span<'a>> {
+    spans
+        .iter()
+        .enumerate()
+        .map(|(i, span)| match i < upto {
+            true => Span::styled(span.content.clone(), span.style.add_modifier(Modifier::DIM)),
+            false => span.clone(),
         })
-        .expect("drawing test went fine");
+        .collect()
+}
+
+/// with dim_todo the spans from the one at `from` on are still to be typed and get dimmed,
+/// typed letters before them stay bright whatever their color
+fn dim_todo<'a>(app: &App, mut spans: Vec<Span<'a>>, from: usize) -> Vec<Span<'a>> {
+    if app.dim_todo {
+        for span in spans.iter_mut().skip(from) {
+            span.style = span.style.add_modifier(Modifier::DIM);
+        }
+    }
+    spans
+}
+
+/// with the highlight cursor the letter up next is drawn reversed,
+/// at the end of a line that's the first letter of the next one
+fn highlight_next(app: &App, spans: &mut [Span], at: usize) {
+    if app.cursor != CursorStyle::Highlight {
+        return;
+    }
+    if let Some(span) = spans.get_mut(at) {
+        span.style = span.style.add_modifier(Modifier::REVERSED);
+    }
+}
+
+/// in blind mode wrong and extra letters get the color of correct ones,
+/// the spans themselves keep the real style for the post screen
+fn blinded<'a>(app: &App, mut spans: Vec<Span<'a>>) -> Vec<Span<'a>> {
+    if app.blind {
+        let colors = &app.test.colors;
+        for span in spans.iter_mut() {
+            if span.style.fg == Some(colors.wrong) || span.style.fg == Some(colors.extra) {
+                span.style.fg = Some(colors.done);
+            }
+        }
+    }
+    spans
+}
+
+/// spaces between words are drawn as the separator that types them
+fn separated<'a>(app: &App, mut spans: Vec<Span<'a>>) -> Vec<Span<'a>> {
+    let separator = app.test.separator;
+    if separator != ' ' {
+        for span in spans.iter_mut().filter(|s| s.content == " ") {
+            span.content = separator.to_string().into();
+        }
+    }
+    spans
+}
+
+/// seconds left in timed tests, typed out of all words otherwise
+fn progress(test: &TestState) -> String {
+    match test.time_left() {
+        Some(secs) => format!("{}s", secs),
+        None if test.endless => format!("{}/∞", test.total_words - test.words_left()),
+        None => format!(
+            "{}/{}",
+            test.total_words - test.words_left(),
+            test.total_words
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::painters::paint;
+    use tui::Terminal;
+
+    fn type_word(app: &mut App) {
+        while app.test.current_char != ' ' {
+            app.test.on_char(app.test.current_char);
+        }
+        app.test.on_char(' ');
+    }
+
+    fn dimmed_cells(app: &mut App) -> usize {
+        use tui::backend::TestBackend;
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        paint(&mut terminal, app, draw_test);
+        terminal
+            .backend()
+            .buffer()
+            .content
+            .iter()
+            .filter(|cell| cell.modifier.contains(Modifier::DIM))
+            .count()
+    }
+
+    /// rows of the drawn test with the cells of each
+    fn render(app: &mut App, width: u16, height: u16) -> Vec<(String, Vec<tui::buffer::Cell>)> {
+        use tui::backend::TestBackend;
+
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        paint(&mut terminal, app, draw_test);
+        terminal
+            .backend()
+            .buffer()
+            .content
+            .chunks(width as usize)
+            .map(|row| {
+                (
+                    row.iter().map(|c| c.symbol.as_str()).collect(),
+                    row.to_vec(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_golden_finished_test() {
+        let mut app = App::setup();
+        app.test.start_on_text("abc de");
+        for c in "abx de".chars() {
+            app.test.on_char(c);
+        }
+        let (done, wrong) = (app.test.colors.done, app.test.colors.wrong);
+
+        let rows = render(&mut app, 100, 10);
+        let lines: Vec<&str> = rows.iter().map(|(line, _)| line.trim_end()).collect();
+        // the wpm depends on how fast the keys went in
+        assert!(lines[1].parse::<f64>().is_ok());
+        let ghost = " ".repeat(((100 - app.paragraph) / 2) as usize);
+        let typed = format!("{}abc de", ghost);
+        let mut golden = vec!["2/2", lines[1], "83%", &typed];
+        golden.resize(9, "");
+        // the test setup sits on the bottom row
+        let status = format!("{}{}", ghost, app.settings.test_cfg.to_string().trim_end());
+        golden.push(&status);
+        assert_eq!(lines, golden);
+
+        // the typed line moved up, the wrong letter keeps its color
+        let fgs: Vec<Color> = rows[3].1[ghost.len()..ghost.len() + 6]
+            .iter()
+            .map(|cell| cell.fg)
+            .collect();
+        assert_eq!(fgs, vec![done, done, wrong, done, done, done]);
+    }
+
+    #[test]
+    fn test_caret_after_extras() {
+        use tui::backend::TestBackend;
+
+        let mut app = App::setup();
+        app.test.start_on_text("abc de");
+        for c in "abcxy".chars() {
+            app.test.on_char(c);
+        }
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        paint(&mut terminal, &mut app, draw_test);
+        let (x, y) = terminal.get_cursor().unwrap();
+        let buffer = terminal.backend().buffer();
+        // right behind the last extra, on the space still to be typed
+        assert_eq!(buffer.get(x - 1, y).symbol, "y");
+        assert_eq!(buffer.get(x - 1, y).fg, app.test.colors.extra);
+        assert_eq!(buffer.get(x, y).symbol, " ");
+        assert_eq!(buffer.get(x + 1, y).symbol, "d");
+
+        paint(&mut terminal, &mut app, draw_scrolling_test);
+        let (x, y) = terminal.get_cursor().unwrap();
+        let buffer = terminal.backend().buffer();
+        assert_eq!(buffer.get(x - 1, y).symbol, "y");
+        assert_eq!(buffer.get(x + 1, y).symbol, "d");
+    }
+
+    #[test]
+    fn test_separator_between_words() {
+        let mut app = App::setup();
+        app.test.start_on_text("abc de fg");
+        app.test.separator = '_';
+        let ghost = ((100 - app.paragraph) / 2) as usize;
+
+        let rows = render(&mut app, 100, 10);
+        assert_eq!(rows[4].0[ghost..].trim_end(), "abc_de_fg");
+
+        for key in "abc_".chars() {
+            app.test.on_char(app.test.key_char(key));
+        }
+        assert_eq!(app.test.current_char, 'd');
+        assert_eq!(app.test.mistakes + app.test.extra_mistakes, 0);
+        // a space is a wrong key now
+        app.test.on_char(app.test.key_char(' '));
+        assert_eq!(app.test.mistakes, 1);
+    }
+
+    #[test]
+    fn test_status_line() {
+        let mut app = App::setup();
+        let status = app.settings.test_cfg.to_string();
+        let drawn = |app: &mut App, height: u16| -> bool {
+            render(app, 100, height)
+                .iter()
+                .any(|(row, _)| row.contains(status.trim_end()))
+        };
+        assert!(drawn(&mut app, 20));
+        // too short to fit under the text
+        assert!(!drawn(&mut app, 6));
+    }
+
+    #[test]
+    fn test_dim_todo() {
+        let mut app = App::setup();
+        app.test.start_on_text("abc de");
+        for c in "ax".chars() {
+            app.test.on_char(c);
+        }
+        let ghost = ((100 - app.paragraph) / 2) as usize;
+        let dims = |app: &mut App| -> Vec<bool> {
+            let rows = render(app, 100, 10);
+            rows[4].1[ghost..ghost + 6]
+                .iter()
+                .map(|cell| cell.modifier.contains(Modifier::DIM))
+                .collect()
+        };
+
+        app.dim_todo = true;
+        // done and wrong letters stay bright, the rest of the line is dimmed
+        assert_eq!(dims(&mut app), vec![false, false, true, true, true, true]);
+        app.dim_todo = false;
+        assert_eq!(dims(&mut app), vec![false; 6]);
+    }
+
+    #[test]
+    fn test_fade_typed() {
+        let mut app = App::setup();
+        app.fade_typed = true;
+        app.test.on_char(app.test.current_char);
+        assert_eq!(dimmed_cells(&mut app), 0);
+
+        while app.test.done <= 2 * FADE_BEHIND {
+            type_word(&mut app);
+        }
+        let dimmed = dimmed_cells(&mut app);
+        assert!(dimmed > 0);
+        // the letters right behind the cursor stay bright
+        assert!(dimmed <= app.test.done - FADE_BEHIND);
+
+        // off it's the same flat rendering as ever
+        app.fade_typed = false;
+        assert_eq!(dimmed_cells(&mut app), 0);
+    }
+
+    #[test]
+    fn test_blind_hides_mistakes() {
+        use tui::backend::TestBackend;
+
+        let mut app = App {
+            blind: true,
+            ..App::setup()
+        };
+        app.test.start_on_text("abc de");
+        app.test.on_char('ź');
+        app.test.on_char('ź');
+        app.test.on_char(app.test.current_char);
+        let wrong = app.test.colors.wrong;
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        paint(&mut terminal, &mut app, draw_test);
+        let cells = &terminal.backend().buffer().content;
+        assert!(cells.iter().all(|cell| cell.fg != wrong));
+
+        // the test still knows what was wrong
+        assert_eq!(app.test.mistakes, 2);
+        assert_eq!(app.test.active[0].style.fg, Some(wrong));
+        assert_eq!(app.test.active[1].style.fg, Some(wrong));
+
+        app.blind = false;
+        paint(&mut terminal, &mut app, draw_test);
+        let cells = &terminal.backend().buffer().content;
+        assert!(cells.iter().any(|cell| cell.fg == wrong));
+    }
+
+    /// the reversed cells and where they are
+    fn highlighted_cells(terminal: &Terminal<tui::backend::TestBackend>) -> Vec<(u16, String)> {
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content
+            .iter()
+            .enumerate()
+            .filter(|(_, cell)| cell.modifier.contains(Modifier::REVERSED))
+            .map(|(i, cell)| (buffer.pos_of(i).0, cell.symbol.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_highlight_cursor() {
+        use tui::backend::TestBackend;
+
+        let mut app = App::setup();
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        paint(&mut terminal, &mut app, draw_test);
+        // the hardware cursor styles leave the text alone
+        assert!(highlighted_cells(&terminal).is_empty());
+
+        app.cursor = CursorStyle::Highlight;
+        paint(&mut terminal, &mut app, draw_test);
+        let start = highlighted_cells(&terminal);
+        assert_eq!(start.len(), 1);
+        assert_eq!(start[0].1, app.test.current_char.to_string());
+
+        type_word(&mut app);
+        paint(&mut terminal, &mut app, draw_test);
+        let (x, letter) = highlighted_cells(&terminal).remove(0);
+        assert_eq!(letter, app.test.current_char.to_string());
+        assert!(x > start[0].0);
+
+        // across the line break it goes back to the start of the line
+        while app.test.up.is_empty() {
+            type_word(&mut app);
+        }
+        paint(&mut terminal, &mut app, draw_test);
+        assert_eq!(
+            highlighted_cells(&terminal),
+            vec![(start[0].0, app.test.current_char.to_string())]
+        );
+
+        app.layout = crate::application::TestLayout::Scroll;
+        paint(&mut terminal, &mut app, draw_scrolling_test);
+        let highlighted = highlighted_cells(&terminal);
+        assert_eq!(highlighted.len(), 1);
+        assert_eq!(highlighted[0].1, app.test.current_char.to_string());
+    }
+
+    #[test]
+    fn test_scroll_offset() {
+        // nothing to scroll into at the start
+        assert_eq!(scroll_offset(10, 200, 80, false), 0);
+        assert_eq!(scroll_offset(40, 200, 80, false), 0);
+        assert_eq!(scroll_offset(50, 200, 80, false), 10);
+        // the end of the test doesn't scroll out of the window
+        assert_eq!(scroll_offset(150, 160, 80, true), 81);
+        assert_eq!(scroll_offset(50, 60, 80, true), 0);
+    }
+
+    #[test]
+    fn test_scrolling_caret_stays_centered() {
+        use crate::application::TestLayout;
+        use tui::backend::TestBackend;
+
+        let mut app = App {
+            layout: TestLayout::Scroll,
+            ..App::setup()
+        };
+        app.change_to_test();
+        let scrolling: crate::painters::Painter = draw_scrolling_test;
+        assert_eq!(app.painter as usize, scrolling as usize);
+        app.settings.test_cfg.length = 200;
+        app.reset_test();
+
+        let mut terminal = Terminal::new(TestBackend::new(60, 20)).unwrap();
+        paint(&mut terminal, &mut app, draw_scrolling_test);
+        let (start_x, row) = terminal.get_cursor().unwrap();
+        assert_eq!(start_x, app.margin);
+
+        while app.test.up.is_empty() {
+            type_word(&mut app);
+        }
+        paint(&mut terminal, &mut app, draw_scrolling_test);
+        assert_eq!(terminal.get_cursor().unwrap(), (app.margin + 28, row));
+
+        // the text under the cursor is the letter that's up next
+        let cell = terminal.backend().buffer().get(app.margin + 28, row);
+        assert_eq!(cell.symbol, app.test.current_char.to_string());
+    }
+
+    #[test]
+    fn test_progress() {
+        let mut app = App::setup();
+        let total = app.settings.test_cfg.length;
+        assert_eq!(progress(&app.test), format!("0/{}", total));
+
+        // a word counts once its space is typed
+        app.test.on_char(app.test.current_char);
+        assert_eq!(progress(&app.test), format!("0/{}", total));
+        type_word(&mut app);
+        assert_eq!(progress(&app.test), format!("1/{}", total));
+
+        // extra mistakes, skipped blanks and wrong letters don't throw it off
+        while app.test.current_char != ' ' {
+            app.test.on_char('ź');
+        }
+        app.test.on_char('x');
+        app.test.on_char(' ');
+        assert_eq!(progress(&app.test), format!("2/{}", total));
+        app.test.undo_char();
+        assert_eq!(progress(&app.test), format!("1/{}", total));
+        app.test.on_char(' ');
+
+        // lines moving up still count
+        while app.test.up.is_empty() {
+            type_word(&mut app);
+        }
+        let typed = app.test.up.iter().filter(|s| s.content == " ").count();
+        assert_eq!(progress(&app.test), format!("{}/{}", typed, total));
+
+        app.test.time_limit = Some(std::time::Duration::from_secs(30));
+        let secs = progress(&app.test);
+        let secs: u64 = secs.strip_suffix('s').unwrap().parse().unwrap();
+        assert!(secs <= 30);
+    }
 }
@@ -1,15 +1,25 @@
-use crate::database::{self, RunHistoryDatbase};
+use crate::colorscheme::Theme;
+use crate::database::{self, RunHistoryDatbase, RunRecord};
+use crate::langs;
 use crate::storage;
-use crate::utils::{count_lines_from_path, termprep, StatefulList};
+use crate::utils::{count_words, count_words_from_path, StatefulList};
 use crate::vec_of_strings;
+use anyhow::Context;
 use rusqlite::Connection;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde_derive::Serialize;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::hash::Hash;
-use std::path::PathBuf;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
 use tui::style::Color;
 
 pub const SCRIPT_SIGN: &str = "#!";
+pub const QUOTE_SIGN: &str = "> ";
+pub const DRILL_SIGN: &str = "~ ";
 
 use bimap::BiMap;
 use lazy_static::lazy_static;
@@ -18,6 +28,24 @@ pub const PUNCTUATION_SHORTHAND: &str = "!?";
 pub const NUMBERS_SHORTHAND: &str = "17";
 pub const SYMBOLS_SHORTHAND: &str = "#$";
 
+/// Amount of words generated at once for timed and endless tests
+/// more get appended as the test goes on
+pub const TIMED_WORDS_CHUNK: usize = 100;
+/// Default cap on the test length, anything longer is better off endless
+pub const MAX_TEST_LENGTH: usize = 5000;
+/// Item of the length list for tests that go on until they're finished with Enter
+pub const ENDLESS: &str = "endless";
+/// Smallest word pool, anything less leaves no words to pick from
+pub const MIN_WORD_POOL: usize = 1;
+/// Runs listed on the history screen
+pub const HISTORY_LENGTH: usize = 100;
+/// Default cap of wrong letters appended to a word
+pub const MAX_EXTRA_MISTAKES: usize = 3;
+/// Most missed words that practice tests pick from
+pub const PRACTICE_WORDS: usize = 50;
+/// default fraction of words swapped for a number when the numbers mod is on
+pub const NUMBERS_RATIO: f64 = 0.15;
+
 lazy_static! {
     pub static ref TEST_MODS: BiMap<&'static str, TestMod> = [
         ("punctuation", TestMod::Punctuation),
@@ -49,19 +77,112 @@ pub fn is_script(text: &str) -> bool {
     &text[..2] == SCRIPT_SIGN
 }
 
+pub fn is_quote(text: &str) -> bool {
+    text.starts_with(QUOTE_SIGN)
+}
+
+pub fn is_drill(text: &str) -> bool {
+    text.starts_with(DRILL_SIGN)
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum SetList {
     Length,
     Frequency,
     Test,
     Mods,
+    Theme,
     Nil,
 }
 
+/// Changes to a word list in the tests list waiting to be confirmed
+#[derive(Clone, Debug, PartialEq)]
+pub enum ListEdit {
+    Delete(String),
+    /// the new name is typed out before confirming
+    Rename {
+        from: String,
+        to: String,
+    },
+}
+
 #[allow(dead_code)]
 pub enum TestVariant {
     Standard,
     Script,
+    Quote,
+    /// random groups of the chars being practiced
+    Drill,
+}
+
+/// How often words show up depending on their position in the word list,
+/// lists are sorted from the most common word
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WordWeighting {
+    /// every word in the word pool is just as likely
+    #[default]
+    Uniform,
+    /// likelihood drops evenly down the list
+    Linear,
+    /// n-th word is n times less likely than the first one
+    Zipf,
+}
+
+impl WordWeighting {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "uniform" => Some(Self::Uniform),
+            "linear" => Some(Self::Linear),
+            "zipf" => Some(Self::Zipf),
+            _ => None,
+        }
+    }
+}
+
+/// What wrong letters typed past the end of a word do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExtraLetters {
+    /// they pile up after the word in the extra color
+    #[default]
+    Overflow,
+    /// they aren't shown, the miss still counts
+    Ignore,
+}
+
+impl ExtraLetters {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "overflow" => Some(Self::Overflow),
+            "ignore" => Some(Self::Ignore),
+            _ => None,
+        }
+    }
+}
+
+/// Kinds of punctuation the punctuation mod can throw in,
+/// all of them unless the [test] table picks some
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PunctuationKind {
+    /// commas, semicolons and colons
+    Normal,
+    /// full stops, question and exclamation marks
+    End,
+    /// brackets and quotes around a word
+    Paired,
+    /// dashes in between words
+    Dash,
+}
+
+impl PunctuationKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "normal" => Some(Self::Normal),
+            "end" => Some(Self::End),
+            "paired" => Some(Self::Paired),
+            "dash" => Some(Self::Dash),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -73,14 +194,10 @@ pub enum TestMod {
 }
 
 impl TestMod {
-    pub fn from_bitflag(bitflag: u8) -> Self {
-        match bitflag {
-            0b00000001 => TestMod::Punctuation,
-            0b00000010 => TestMod::Numbers,
-            0b00000100 => TestMod::Symbols,
-            0b00001000 => TestMod::Capitalization,
-            _ => unreachable!(),
-        }
+    /// None for bits that aren't assigned to any mod
+    /// e.g. written by a newer version with more mods
+    pub fn from_bitflag(bitflag: u8) -> Option<Self> {
+        BITFLAG_MODS.get_by_left(&bitflag).copied()
     }
 }
 
@@ -95,23 +212,44 @@ impl fmt::Display for TestMod {
     }
 }
 
+/// inverse of decode_test_mod_bitflags
+pub fn encode_test_mod_bitflags(test_mods: &HashSet<TestMod>) -> u8 {
+    let mut bitflag: u8 = 0;
+    for test_mod in test_mods {
+        bitflag |= BITFLAG_MODS.get_by_right(test_mod).expect("wrong mod?");
+    }
+    bitflag
+}
+
 pub fn decode_test_mod_bitflags(bitflag: u8) -> HashSet<TestMod> {
     let mut test_mods: HashSet<TestMod> = HashSet::new();
 
+    // unknown bits are skipped
     for i in 0..8 {
         if bitflag >> i & 1 == 1 {
-            test_mods.insert(TestMod::from_bitflag(2_u8.pow(i)));
+            if let Some(test_mod) = TestMod::from_bitflag(2_u8.pow(i)) {
+                test_mods.insert(test_mod);
+            }
         };
     }
 
     test_mods
 }
 
+#[derive(Serialize)]
 pub struct TestSummary {
     pub correct_chars: usize,
     pub mistakes: usize,
-    pub wpm: f64,
+    /// every typed char counts, mistakes and extras included
+    pub raw_wpm: f64,
+    /// only correct chars count, this one lands in the database
+    #[serde(rename = "wpm")]
+    pub net_wpm: f64,
     pub acc: f64,
+    /// 0 - 100 how steady the speed was
+    pub consistency: f64,
+    /// wpm of each word in order, NAN for skipped ones
+    pub wpm_per_word: Vec<f64>,
 }
 
 impl Default for TestSummary {
@@ -119,8 +257,11 @@ impl Default for TestSummary {
         Self {
             correct_chars: 0,
             mistakes: 0,
-            wpm: 0.,
+            raw_wpm: 0.,
+            net_wpm: 0.,
+            consistency: 0.,
             acc: 0.,
+            wpm_per_word: vec![],
         }
     }
 }
@@ -128,6 +269,8 @@ impl Default for TestSummary {
 #[derive(Default)]
 pub struct PostBox {
     pub cached_historic_wpm: f64,
+    /// stats of the finished test type including the latest run
+    pub stats: Option<database::AggStats>,
 }
 
 /// Basically a dupe of some of the info of ttc
@@ -138,6 +281,7 @@ pub struct TestIdentity {
     pub length: usize,
     pub word_pool: usize,
     pub mods: u8,
+    pub duration: u64,
 }
 
 /// This stuct contains information about
@@ -152,6 +296,69 @@ pub struct TypingTestConfig {
     pub length: usize,
     pub word_pool: usize,
     pub mods: HashSet<TestMod>,
+    /// seconds the timed test lasts, None means the test ends after length words
+    pub time_limit: Option<u64>,
+    /// words keep coming until the test is finished with Enter, length is ignored
+    pub endless: bool,
+    /// length can't go above this
+    pub max_length: usize,
+    /// punctuation char -> weight, overrides the defaults
+    /// paired punctuation is keyed by the opening char
+    pub punctuation_weights: Option<HashMap<char, u16>>,
+    /// punctuation the punctuation mod is limited to, None means all of it
+    pub punctuation_kinds: Option<HashSet<PunctuationKind>>,
+    /// “curly” quotes instead of "straight" ones
+    pub curly_quotes: bool,
+    /// symbol file in the storage clusters of the symbols mod come from
+    pub symbols: Option<String>,
+    /// same seed means the same words every time
+    pub seed: Option<u64>,
+    /// standard tests only get words with at least/at most this many chars
+    pub min_word_len: Option<usize>,
+    pub max_word_len: Option<usize>,
+    /// wrong letters that can pile up at the end of a word
+    pub max_extra_mistakes: usize,
+    pub extra_letters: ExtraLetters,
+    pub word_weighting: WordWeighting,
+    /// chance of any word starting with a capital, on top of the capitalization mod
+    pub random_capitals: f64,
+    /// words from the word list are lowercased so capitals only come from mods
+    pub lowercase: bool,
+    /// second word list words of standard tests are mixed in from
+    pub mix: Option<String>,
+    /// share of words that come from the mix, between 0 and 1
+    pub mix_ratio: f64,
+    /// space typed mid word jumps to the next word marking the rest wrong,
+    /// otherwise it's a wrong letter like any other and the word has to be finished
+    pub skip_on_space: bool,
+    /// wrong keys don't move on, every letter has to be typed right
+    /// before the next one and space only works once the word matches
+    pub strict: bool,
+    /// hardcore mode, the test starts over once there are more mistakes than this
+    pub max_mistakes: Option<usize>,
+    /// the test starts over once accuracy drops below this many percent
+    pub min_acc: Option<f64>,
+    /// terminal bell rings on wrong keys
+    pub bell: bool,
+    /// seconds counted down before a timed test takes any keys
+    pub countdown: Option<u64>,
+    /// standard tests are mostly made of words missed in earlier runs
+    pub practice: bool,
+    /// typed and shown between words instead of a space, e.g. _ for snake_case
+    pub separator: char,
+    /// runs below this accuracy in percent aren't saved
+    pub save_min_acc: Option<f64>,
+    /// runs shorter than this many seconds aren't saved
+    pub save_min_secs: Option<u64>,
+    /// the numbers mod makes the whole test out of digit groups
+    pub numbers_only: bool,
+    /// fraction of words swapped for a number with the numbers mod, between 0 and 1
+    pub numbers_ratio: f64,
+    /// lengths the digit groups of numbers only tests are picked from,
+    /// repeating a length makes it more likely
+    pub digit_groups: Vec<usize>,
+    /// the missed words practice tests are made of, loaded from the database
+    pub problem_words: Vec<String>,
 
     // summary
     pub test_summary: TestSummary,
@@ -168,17 +375,52 @@ impl fmt::Display for TypingTestConfig {
                 for test_mod in &self.mods {
                     mods.push_str(&format!(" {}", test_mod));
                 }
+                let length = match self.time_limit {
+                    Some(secs) => format!("{}s", secs),
+                    None if self.endless => ENDLESS.to_string(),
+                    None => self.length.to_string(),
+                };
+                let word_len = match (self.min_word_len, self.max_word_len) {
+                    (None, None) => String::new(),
+                    (min, max) => format!(
+                        " {}..{}",
+                        min.map_or(String::new(), |m| m.to_string()),
+                        max.map_or(String::new(), |m| m.to_string())
+                    ),
+                };
                 write!(
                     f,
-                    "{}: {}/{} {}",
-                    self.name, self.length, self.word_pool, mods
+                    "{}: {}/{}{} {}",
+                    self.name, length, self.word_pool, word_len, mods
                 )
             }
+            TestVariant::Drill => write!(f, "{} drill: {}", self.name, self.length),
             _ => write!(f, "{}", self.name),
         }
     }
 }
 
+/// the test along with its summary, printed out with --json-output
+impl Serialize for TypingTestConfig {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut mods: Vec<&str> = self
+            .mods
+            .iter()
+            .map(|m| *TEST_MODS.get_by_right(m).expect("every mod has a name"))
+            .collect();
+        mods.sort_unstable();
+
+        let mut state = serializer.serialize_struct("TypingTestConfig", 6)?;
+        state.serialize_field("name", &self.name)?;
+        state.serialize_field("length", &self.length)?;
+        state.serialize_field("time_limit", &self.time_limit)?;
+        state.serialize_field("word_pool", &self.word_pool)?;
+        state.serialize_field("mods", &mods)?;
+        state.serialize_field("summary", &self.test_summary)?;
+        state.end()
+    }
+}
+
 impl Default for TypingTestConfig {
     // TODO name can probably can be &str XD
     fn default() -> Self {
@@ -188,12 +430,83 @@ impl Default for TypingTestConfig {
             length: 25,
             word_pool: 5000,
             mods: HashSet::default(),
+            time_limit: None,
+            endless: false,
+            max_length: MAX_TEST_LENGTH,
+            punctuation_weights: None,
+            punctuation_kinds: None,
+            curly_quotes: false,
+            symbols: None,
+            seed: None,
+            min_word_len: None,
+            max_word_len: None,
+            max_extra_mistakes: MAX_EXTRA_MISTAKES,
+            extra_letters: ExtraLetters::default(),
+            word_weighting: WordWeighting::default(),
+            random_capitals: 0.,
+            lowercase: false,
+            mix: None,
+            mix_ratio: 0.5,
+            skip_on_space: false,
+            strict: false,
+            max_mistakes: None,
+            min_acc: None,
+            bell: false,
+            countdown: None,
+            practice: false,
+            separator: ' ',
+            save_min_acc: None,
+            save_min_secs: None,
+            numbers_only: false,
+            numbers_ratio: NUMBERS_RATIO,
+            digit_groups: vec![],
+            problem_words: vec![],
             test_summary: TestSummary::default(),
         }
     }
 }
 
 impl TypingTestConfig {
+    /// Overrides default punctuation weights,
+    /// zero weight means the punctuation won't show up at all
+    /// ```
+    /// use smokey::settings::TypingTestConfig;
+    /// use std::collections::HashMap;
+    ///
+    /// let no_semicolons = HashMap::from([(';', 0), ('(', 50)]);
+    /// let cfg = TypingTestConfig::default().with_punctuation_weights(no_semicolons);
+    /// assert!(cfg.punctuation_weights.is_some());
+    /// ```
+    pub fn with_punctuation_weights(mut self, weights: HashMap<char, u16>) -> Self {
+        self.punctuation_weights = Some(weights);
+        self
+    }
+
+    /// Whether a run is good enough to land in the history
+    /// ```
+    /// use smokey::settings::{TestSummary, TypingTestConfig};
+    /// use std::time::Duration;
+    ///
+    /// let cfg = TypingTestConfig {
+    ///     save_min_acc: Some(50.),
+    ///     save_min_secs: Some(5),
+    ///     ..Default::default()
+    /// };
+    /// let summary = TestSummary {
+    ///     acc: 80.,
+    ///     ..Default::default()
+    /// };
+    /// assert!(cfg.worth_saving(&summary, Duration::from_secs(10)));
+    /// assert!(!cfg.worth_saving(&summary, Duration::from_secs(3)));
+    /// ```
+    pub fn worth_saving(&self, summary: &TestSummary, elapsed: Duration) -> bool {
+        let accurate = self.save_min_acc.is_none_or(|min| summary.acc >= min);
+        let long = self
+            .save_min_secs
+            .is_none_or(|min| elapsed >= Duration::from_secs(min));
+        accurate && long
+    }
+
     /// checks if the file name corresponds to a valid path
     /// and whether or not the word_pool field
     /// is in bounds in respect to acutal number of words
@@ -201,36 +514,97 @@ impl TypingTestConfig {
     ///
     /// returns the maximum possible value of word_pool to be cached
     fn validate(&mut self) -> usize {
-        let path = self.get_file_path();
-
-        if !path.is_file() {
-            termprep::panic_with_friendly_message(&format!(
-                "{:?}\nthis word file does not exist",
-                path
-            ))
+        self.word_pool = self.word_pool.max(MIN_WORD_POOL);
+        if self.length > self.max_length {
+            warn!(
+                "length {} is above the cap of {}, shortened to it",
+                self.length, self.max_length
+            );
+            self.length = self.max_length;
+        }
+        // drills are generated, there's no file to check
+        if let TestVariant::Drill = self.variant {
+            return self.word_pool;
         }
 
-        let lines = count_lines_from_path(path).expect("fallback to the english word file");
-
+        let lines = self.count_words_or_english();
         if self.word_pool > lines {
             self.word_pool = lines;
         }
         lines
     }
 
+    /// words in the test file, a file that can't be used is swapped for english
+    /// and storage without english counts the built-in copy the words come from then
+    fn count_words_or_english(&mut self) -> usize {
+        match count_words_from_path(self.get_file_path()) {
+            Ok(0) => warn!("{:?} has no words, using english", self.get_file_path()),
+            Ok(words) => return words,
+            Err(e) => warn!("{}, using english", e),
+        }
+
+        self.name = "english".to_string();
+        self.variant = TestVariant::Standard;
+        match count_words_from_path(storage::get_word_list_path("english")) {
+            Ok(words) if words > 0 => words,
+            _ => count_words(storage::FALLBACK_ENGLISH).unwrap_or_default(),
+        }
+    }
+
     // TODO rename this XD
+    /// timed and endless tests don't care about length so it's zeroed
     pub fn gib_identity(&self) -> TestIdentity {
         TestIdentity {
-            length: self.length,
+            length: if self.time_limit.is_some() || self.endless {
+                0
+            } else {
+                self.length
+            },
             word_pool: self.word_pool,
-            mods: database::encode_test_mod_bitflag(&self.mods),
+            mods: encode_test_mod_bitflags(&self.mods),
+            duration: self.time_limit.unwrap_or(0),
+        }
+    }
+
+    /// chars a word can have, the whole range if there isn't a limit
+    pub fn word_len_range(&self) -> RangeInclusive<usize> {
+        self.min_word_len.unwrap_or(0)..=self.max_word_len.unwrap_or(usize::MAX)
+    }
+
+    /// how many words should be generated in one go
+    pub fn words_to_generate(&self) -> usize {
+        match self.time_limit {
+            Some(_) => TIMED_WORDS_CHUNK,
+            None if self.endless => TIMED_WORDS_CHUNK,
+            None => self.length,
+        }
+    }
+
+    /// the name as it's shown in the tests list, with the sign of its variant
+    pub fn list_name(&self) -> String {
+        match self.variant {
+            TestVariant::Standard => self.name.clone(),
+            TestVariant::Script => format!("{}{}", SCRIPT_SIGN, self.name),
+            TestVariant::Quote => format!("{}{}", QUOTE_SIGN, self.name),
+            TestVariant::Drill => format!("{}{}", DRILL_SIGN, self.name),
         }
     }
 
-    fn get_file_path(&self) -> PathBuf {
+    /// whether the test's file is there, drills are always there if they exist at all
+    pub fn is_available(&self) -> bool {
+        match self.variant {
+            TestVariant::Drill => langs::DRILLS.iter().any(|(name, _)| *name == self.name),
+            _ => self.get_file_path().is_file(),
+        }
+    }
+
+    pub fn get_file_path(&self) -> PathBuf {
         match self.variant {
             TestVariant::Standard => self.get_words_file_path(),
             TestVariant::Script => self.get_scripts_file_path(),
+            TestVariant::Quote => self.get_quotes_file_path(),
+            // drills are generated, there's no file behind them
+            TestVariant::Drill => PathBuf::new(),
         }
     }
 
@@ -241,6 +615,10 @@ impl TypingTestConfig {
     pub fn get_scripts_file_path(&self) -> PathBuf {
         storage::get_storage_dir().join("scripts").join(&self.name)
     }
+
+    pub fn get_quotes_file_path(&self) -> PathBuf {
+        storage::get_storage_dir().join("quotes").join(&self.name)
+    }
 }
 
 pub struct SettingsColors {
@@ -270,7 +648,16 @@ pub struct Settings {
     pub length_list: StatefulList<String>,
     pub frequency_list: StatefulList<String>,
     pub tests_list: StatefulList<String>,
+    // tests_list only shows the tests matching test_query
+    pub all_tests: Vec<String>,
+    pub test_query: String,
+    // delete or rename of a word list that hasn't been confirmed yet
+    pub list_edit: Option<ListEdit>,
     pub mods_list: StatefulList<String>,
+    pub themes_list: StatefulList<String>,
+    // same order as themes_list
+    pub themes: Vec<(String, Theme)>,
+    pub theme: Theme,
     // HM<test.name (file_word_amount, HM<TestIdentity, historic_max_wpm>)>
     // NaN = historic_max_wpm wasnt cached
     pub info_cache: InfoCache,
@@ -278,16 +665,19 @@ pub struct Settings {
 
     pub database: RunHistoryDatbase,
     pub postbox: PostBox,
+    // runs shown on the history screen, newest first
+    pub history: StatefulList<RunRecord>,
 }
 
 impl Default for Settings {
     fn default() -> Self {
-        let length_list = StatefulList::with_items(vec_of_strings!["10", "15", "25", "50", "100"]);
-        let words_list = storage::parse_storage_contents();
+        let length_list = create_length_list();
+        let themes = load_themes();
+        let words_list = list_tests();
         let mod_list: Vec<String> = TEST_MODS.left_values().map(|&x| x.to_string()).collect();
-        let test_cfg = TypingTestConfig::default();
+        let mut test_cfg = TypingTestConfig::default();
         let mut info_cache: InfoCache = HashMap::new();
-        let word_count = count_lines_from_path(test_cfg.get_words_file_path()).unwrap();
+        let word_count = test_cfg.count_words_or_english();
 
         // TODO
         // This code is not only ass but also a dupe
@@ -309,12 +699,19 @@ impl Default for Settings {
             frequency_list,
             info_cache,
             test_cfg,
-            tests_list: StatefulList::with_items(words_list),
+            tests_list: StatefulList::with_items(words_list.clone()),
+            all_tests: words_list,
+            test_query: String::new(),
+            list_edit: None,
             mods_list: StatefulList::with_items(mod_list),
+            themes_list: StatefulList::with_items(themes.iter().map(|t| t.0.clone()).collect()),
+            themes,
+            theme: Theme::default(),
             colors: SettingsColors::default(),
             script_cache: ScriptCache::default(),
             database: RunHistoryDatbase::default(),
             postbox: PostBox::default(),
+            history: StatefulList::new(),
         }
     }
 }
@@ -323,9 +720,10 @@ impl Settings {
     /// TODO a lot of repetitive code taken from default function
     /// restructure ?? idk
     /// I can't do ..Self::default() as that would count lines twice
-    pub fn with_config(colors: SettingsColors, ttc: TypingTestConfig) -> Self {
-        let length_list = StatefulList::with_items(vec_of_strings!["10", "15", "25", "50", "100"]);
-        let words_list = storage::parse_storage_contents();
+    pub fn with_config(theme: Theme, ttc: TypingTestConfig) -> Self {
+        let length_list = create_length_list();
+        let themes = load_themes();
+        let words_list = list_tests();
         let mod_list: Vec<String> = TEST_MODS.left_values().map(|&x| x.to_string()).collect();
 
         let mut test_cfg = ttc;
@@ -343,7 +741,7 @@ impl Settings {
         info_cache.insert(test_cfg.name.clone(), (word_count, hs));
 
         let frequency_list = create_frequency_list(word_count);
-        Self {
+        let mut settings = Self {
             hovered: SetList::Length,
             active: SetList::Nil,
 
@@ -351,21 +749,52 @@ impl Settings {
             frequency_list,
             info_cache,
             test_cfg,
-            tests_list: StatefulList::with_items(words_list),
+            tests_list: StatefulList::with_items(words_list.clone()),
+            all_tests: words_list,
+            test_query: String::new(),
+            list_edit: None,
             mods_list: StatefulList::with_items(mod_list),
+            themes_list: StatefulList::with_items(themes.iter().map(|t| t.0.clone()).collect()),
+            themes,
+            theme,
             script_cache: ScriptCache::default(),
             database: RunHistoryDatbase::default(),
             postbox: PostBox::default(),
-            colors,
+            history: StatefulList::new(),
+            colors: theme.to_settings_colors(),
+        };
+        settings.load_problem_words();
+        settings
+    }
+
+    /// Loads the most missed words for practice tests,
+    /// without any mistake history the test is generated as usual
+    pub fn load_problem_words(&mut self) {
+        if !self.test_cfg.practice {
+            return;
+        }
+        self.test_cfg.problem_words =
+            database::get_problem_words(&self.database.conn, PRACTICE_WORDS).unwrap_or_else(|e| {
+                warn!("couldn't load the missed words: {}", e);
+                vec![]
+            });
+    }
+
+    /// Adds the words missed in the finished test to the tally
+    pub fn save_missed_words(&mut self, words: &HashMap<String, usize>) {
+        if let Err(e) = self.database.save_missed_words(words) {
+            warn!("couldn't save the missed words: {}", e);
         }
+        self.load_problem_words();
     }
 
     pub fn color_hover_or_active(&self) -> HashMap<SetList, Option<Color>> {
-        let mut hm: HashMap<SetList, Option<Color>> = HashMap::with_capacity(4);
+        let mut hm: HashMap<SetList, Option<Color>> = HashMap::with_capacity(5);
         hm.insert(SetList::Length, None);
         hm.insert(SetList::Test, None);
         hm.insert(SetList::Frequency, None);
         hm.insert(SetList::Mods, None);
+        hm.insert(SetList::Theme, None);
 
         if self.hovered != SetList::Nil {
             hm.insert(self.hovered, Some(self.colors.hover));
@@ -382,10 +811,177 @@ impl Settings {
         } else {
             self.hovered = self.active;
             self.active = SetList::Nil;
+            self.clear_test_query();
             false
         }
     }
 
+    /// narrows the tests list down to tests containing the query
+    pub fn push_test_query(&mut self, c: char) {
+        self.test_query.push(c);
+        self.filter_tests();
+    }
+
+    pub fn pop_test_query(&mut self) {
+        self.test_query.pop();
+        self.filter_tests();
+    }
+
+    /// shows the full tests list again
+    pub fn clear_test_query(&mut self) {
+        self.test_query.clear();
+        self.filter_tests();
+    }
+
+    /// Asks to delete the word list selected in the tests list
+    pub fn begin_delete(&mut self) {
+        if let Some(name) = self.selected_word_list() {
+            self.list_edit = Some(ListEdit::Delete(name));
+        }
+    }
+
+    /// Starts typing a new name for the word list selected in the tests list
+    pub fn begin_rename(&mut self) {
+        if let Some(from) = self.selected_word_list() {
+            let to = from.clone();
+            self.list_edit = Some(ListEdit::Rename { from, to });
+        }
+    }
+
+    /// only word lists can be changed, scripts, quotes and drills are left alone
+    fn selected_word_list(&self) -> Option<String> {
+        if self.active != SetList::Test {
+            return None;
+        }
+        let i = self.tests_list.state.selected()?;
+        let name = self.tests_list.items.get(i)?;
+        if is_script(name) || is_quote(name) || is_drill(name) {
+            warn!("only word lists can be deleted or renamed");
+            return None;
+        }
+        Some(name.clone())
+    }
+
+    pub fn push_list_edit(&mut self, c: char) {
+        if let Some(ListEdit::Rename { to, .. }) = &mut self.list_edit {
+            to.push(c);
+        }
+    }
+
+    pub fn pop_list_edit(&mut self) {
+        if let Some(ListEdit::Rename { to, .. }) = &mut self.list_edit {
+            to.pop();
+        }
+    }
+
+    /// Carries out the pending delete or rename, failures only warn
+    pub fn confirm_list_edit(&mut self) {
+        let result = match self.list_edit.take() {
+            Some(ListEdit::Delete(name)) => {
+                self.delete_word_list_in(&storage::get_storage_dir(), &name)
+            }
+            Some(ListEdit::Rename { from, to }) => {
+                self.rename_word_list_in(&storage::get_storage_dir(), &from, &to)
+            }
+            None => return,
+        };
+        if let Err(e) = result {
+            warn!("{}", e);
+        }
+    }
+
+    /// deletes the list from the storage in dir, a deleted current test goes back to english
+    fn delete_word_list_in(&mut self, dir: &Path, name: &str) -> anyhow::Result<()> {
+        storage::delete_word_list(dir, name)?;
+        self.info_cache.remove(name);
+
+        if matches!(self.test_cfg.variant, TestVariant::Standard) && self.test_cfg.name == name {
+            self.test_cfg.name = "english".to_string();
+            let word_count = self.get_word_count();
+            self.frequency_list = create_frequency_list(word_count);
+            self.test_cfg.word_pool = self.test_cfg.word_pool.min(word_count);
+            self.cache_historic_max_wpm();
+        }
+        self.refresh_tests();
+        Ok(())
+    }
+
+    /// renames the list in the storage in dir along with what's cached under the old name
+    fn rename_word_list_in(&mut self, dir: &Path, from: &str, to: &str) -> anyhow::Result<()> {
+        let to = to.trim();
+        storage::rename_word_list(dir, from, to)?;
+        if let Some(info) = self.info_cache.remove(from) {
+            self.info_cache.insert(to.to_string(), info);
+        }
+
+        if matches!(self.test_cfg.variant, TestVariant::Standard) && self.test_cfg.name == from {
+            self.test_cfg.name = to.to_string();
+        }
+        // so the renamed list stays selected
+        for item in self
+            .tests_list
+            .items
+            .iter_mut()
+            .filter(|item| *item == from)
+        {
+            *item = to.to_string();
+        }
+        self.refresh_tests();
+        Ok(())
+    }
+
+    /// lists the storage again after it changed
+    fn refresh_tests(&mut self) {
+        self.all_tests = list_tests();
+        self.filter_tests();
+    }
+
+    /// keeps the selected test selected if it still matches
+    fn filter_tests(&mut self) {
+        let selected = self
+            .tests_list
+            .state
+            .selected()
+            .and_then(|i| self.tests_list.items.get(i).cloned());
+
+        let query = self.test_query.to_lowercase();
+        self.tests_list.items = self
+            .all_tests
+            .iter()
+            .filter(|name| name.to_lowercase().contains(&query))
+            .cloned()
+            .collect();
+
+        let i = selected
+            .and_then(|name| self.tests_list.items.iter().position(|x| *x == name))
+            .unwrap_or(0);
+        match self.tests_list.items.is_empty() {
+            true => self.tests_list.unselect(),
+            false => self.tests_list.state.select(Some(i)),
+        }
+    }
+
+    /// Switches to the test, length, word pool, time limit, mods and endless of a shared setup,
+    /// everything else including the caches and the database stays as it was
+    pub fn apply_shared_test(&mut self, shared: TypingTestConfig) {
+        self.test_cfg.name = shared.name;
+        self.test_cfg.variant = shared.variant;
+        self.test_cfg.length = shared.length;
+        self.test_cfg.word_pool = shared.word_pool;
+        self.test_cfg.time_limit = shared.time_limit;
+        self.test_cfg.mods = shared.mods;
+        self.test_cfg.endless = shared.endless;
+        self.test_cfg.validate();
+
+        // the others get their max wpm cached once they finish
+        if let TestVariant::Standard = self.test_cfg.variant {
+            let word_count = self.get_word_count();
+            self.frequency_list = create_frequency_list(word_count);
+            self.cache_historic_max_wpm();
+        }
+        self.load_problem_words();
+    }
+
     pub fn update_historic_max_wpm(&mut self, max_wpm: f64) {
         match self.test_cfg.variant {
             TestVariant::Standard => {
@@ -397,7 +993,7 @@ impl Settings {
                     .get_mut(&self.test_cfg.gib_identity())
                     .unwrap() = Some(max_wpm);
             }
-            TestVariant::Script => {
+            TestVariant::Script | TestVariant::Quote | TestVariant::Drill => {
                 *self.script_cache.get_mut(&self.test_cfg.name).unwrap() = Some(max_wpm);
             }
         }
@@ -413,13 +1009,43 @@ impl Settings {
         *self.script_cache.get(&self.test_cfg.name).unwrap()
     }
 
+    /// theme highlighted in the themes list
+    /// or the current one if the list isn't active
+    pub fn get_previewed_theme(&self) -> Theme {
+        if self.active != SetList::Theme {
+            return self.theme;
+        }
+
+        match self.themes_list.state.selected() {
+            Some(i) => self.themes[i].1,
+            None => self.theme,
+        }
+    }
+
+    /// name of the theme highlighted in the themes list
+    pub fn get_previewed_theme_name(&self) -> Option<&str> {
+        if self.active != SetList::Theme {
+            return None;
+        }
+
+        let i = self.themes_list.state.selected()?;
+        Some(&self.themes[i].0)
+    }
+
+    /// picks up themes that were saved since the settings were made
+    pub fn reload_themes(&mut self) {
+        self.themes = load_themes();
+        self.themes_list =
+            StatefulList::with_items(self.themes.iter().map(|t| t.0.clone()).collect());
+    }
+
     // ------------- TESTEND / DATABASE METHODS ---------------------
 
     /// This function performs actions needed after test termination
     /// that includes saving results to db and caching new max_wpm if need be
     pub fn save_test_results(&mut self, summary: TestSummary) {
         self.test_cfg.test_summary = summary;
-        let final_wpm = self.test_cfg.test_summary.wpm;
+        let final_wpm = self.test_cfg.test_summary.net_wpm;
 
         // If record is beat the historic_max_wpm but the
         // previous one is cached so it can be displayed in
@@ -436,12 +1062,14 @@ impl Settings {
                 self.database.save(&self.test_cfg);
             }
 
-            TestVariant::Script => {
-                // Check for cached max_wpm
+            TestVariant::Script | TestVariant::Quote | TestVariant::Drill => {
+                // Check for cached max_wpm, tests picked through config or cli
+                // never went through the settings so they can be missing
+                let conn = &self.database.conn;
                 let historic_max_wpm = self
                     .script_cache
-                    .get(&self.test_cfg.name)
-                    .expect("The name isn't registered in the script_cache")
+                    .entry(self.test_cfg.name.clone())
+                    .or_insert_with_key(|name| database::get_max_wpm_script(conn, name))
                     .unwrap_or(0.);
 
                 self.postbox.cached_historic_wpm = historic_max_wpm;
@@ -451,13 +1079,30 @@ impl Settings {
                 self.database.save(&self.test_cfg);
             }
         }
+
+        self.postbox.stats = database::stats_for(&self.database.conn, &self.test_cfg);
+    }
+
+    /// reads the most recent runs for the history screen
+    pub fn load_history(&mut self) {
+        let runs = self
+            .database
+            .recent_results(HISTORY_LENGTH)
+            .unwrap_or_else(|e| {
+                warn!("couldn't load the run history: {}", e);
+                vec![]
+            });
+        self.history = StatefulList::with_items(runs);
+        if !self.history.items.is_empty() {
+            self.history.state.select(Some(0));
+        }
     }
 
     pub fn save_run_to_database(&mut self) {
         self.database.save(&self.test_cfg);
     }
 
-    fn cache_historic_max_wpm(&mut self) {
+    pub fn cache_historic_max_wpm(&mut self) {
         let tid = self.test_cfg.gib_identity();
 
         let inner_cache = &mut self
@@ -478,8 +1123,7 @@ impl Settings {
         if let Some(info_cache) = self.info_cache.get(&self.test_cfg.name) {
             info_cache.0
         } else {
-            let word_count =
-                count_lines_from_path(storage::get_word_list_path(&self.test_cfg.name)).unwrap();
+            let word_count = self.test_cfg.count_words_or_english();
             self.info_cache
                 .insert(self.test_cfg.name.clone(), (word_count, HashMap::new()));
             word_count
@@ -501,18 +1145,53 @@ impl Settings {
 
         match self.active {
             SetList::Length => {
-                if let TestVariant::Script = self.test_cfg.variant {
+                if let TestVariant::Script | TestVariant::Quote | TestVariant::Drill =
+                    self.test_cfg.variant
+                {
                     return;
                 }
-                self.test_cfg.length = self.length_list.get_item().parse::<usize>().unwrap();
+                let item = self.length_list.get_item();
+                match parse_length(item, self.test_cfg.max_length) {
+                    Ok(TestLength::Words(length)) => {
+                        self.test_cfg.length = length;
+                        self.test_cfg.time_limit = None;
+                        self.test_cfg.endless = false;
+                    }
+                    Ok(TestLength::Seconds(secs)) => {
+                        self.test_cfg.time_limit = Some(secs);
+                        self.test_cfg.endless = false;
+                    }
+                    Ok(TestLength::Endless) => {
+                        self.test_cfg.time_limit = None;
+                        self.test_cfg.endless = true;
+                    }
+                    Err(e) => {
+                        warn!("{:#}", e);
+                        return;
+                    }
+                }
                 self.cache_historic_max_wpm();
             }
 
             SetList::Test => {
+                // nothing matches the query
+                if self.tests_list.state.selected().is_none() {
+                    return;
+                }
                 let chosen_test_name = self.tests_list.get_item();
 
-                if is_script(chosen_test_name) {
-                    self.test_cfg.variant = TestVariant::Script;
+                let signed_variant = if is_script(chosen_test_name) {
+                    Some(TestVariant::Script)
+                } else if is_quote(chosen_test_name) {
+                    Some(TestVariant::Quote)
+                } else if is_drill(chosen_test_name) {
+                    Some(TestVariant::Drill)
+                } else {
+                    None
+                };
+
+                if let Some(variant) = signed_variant {
+                    self.test_cfg.variant = variant;
 
                     // TODO change
                     self.test_cfg.name = chosen_test_name[2..].to_string();
@@ -535,19 +1214,26 @@ impl Settings {
             }
 
             SetList::Frequency => {
-                if let TestVariant::Script = self.test_cfg.variant {
+                if let TestVariant::Script | TestVariant::Quote | TestVariant::Drill =
+                    self.test_cfg.variant
+                {
                     return;
                 }
-                self.test_cfg.word_pool = self
-                    .frequency_list
-                    .get_item()
-                    .parse::<usize>()
-                    .unwrap_or(69);
+                // a bad item leaves the previous word pool as it was
+                match parse_number::<usize>(self.frequency_list.get_item()) {
+                    Ok(word_pool) => self.test_cfg.word_pool = word_pool.max(MIN_WORD_POOL),
+                    Err(e) => {
+                        warn!("{:#}", e);
+                        return;
+                    }
+                }
                 self.cache_historic_max_wpm();
             }
 
             SetList::Mods => {
-                if let TestVariant::Script = self.test_cfg.variant {
+                if let TestVariant::Script | TestVariant::Quote | TestVariant::Drill =
+                    self.test_cfg.variant
+                {
                     return;
                 }
                 let test_mod = TEST_MODS
@@ -559,16 +1245,27 @@ impl Settings {
                 }
                 self.cache_historic_max_wpm();
             }
+
+            SetList::Theme => {
+                let i = self
+                    .themes_list
+                    .state
+                    .selected()
+                    .expect("list is active so something is selected");
+                self.theme = self.themes[i].1;
+                self.colors = self.theme.to_settings_colors();
+            }
             SetList::Nil => unreachable!(),
         }
     }
 
     pub fn up(&mut self) {
         match self.hovered {
-            SetList::Length => self.hovered = SetList::Test,
+            SetList::Length => self.hovered = SetList::Theme,
             SetList::Test => self.hovered = SetList::Length,
-            SetList::Frequency => self.hovered = SetList::Mods,
+            SetList::Frequency => self.hovered = SetList::Theme,
             SetList::Mods => self.hovered = SetList::Frequency,
+            SetList::Theme => self.hovered = SetList::Test,
             SetList::Nil => {
                 self.get_list(self.active).unwrap().previous();
             }
@@ -578,9 +1275,10 @@ impl Settings {
     pub fn down(&mut self) {
         match self.hovered {
             SetList::Length => self.hovered = SetList::Test,
-            SetList::Test => self.hovered = SetList::Length,
+            SetList::Test => self.hovered = SetList::Theme,
             SetList::Frequency => self.hovered = SetList::Mods,
-            SetList::Mods => self.hovered = SetList::Frequency,
+            SetList::Mods => self.hovered = SetList::Theme,
+            SetList::Theme => self.hovered = SetList::Length,
             SetList::Nil => {
                 self.get_list(self.active).unwrap().next();
             }
@@ -593,9 +1291,12 @@ impl Settings {
             SetList::Test => self.hovered = SetList::Mods,
             SetList::Frequency => self.hovered = SetList::Length,
             SetList::Mods => self.hovered = SetList::Test,
+            // preview next to it can't be hovered
+            SetList::Theme => (),
             SetList::Nil => {
                 self.hovered = self.active;
                 self.active = SetList::Nil;
+                self.clear_test_query();
                 self.left();
             }
         }
@@ -607,25 +1308,126 @@ impl Settings {
             SetList::Test => self.hovered = SetList::Mods,
             SetList::Frequency => self.hovered = SetList::Length,
             SetList::Mods => self.hovered = SetList::Test,
+            SetList::Theme => (),
             SetList::Nil => {
                 self.hovered = self.active;
                 self.active = SetList::Nil;
+                self.clear_test_query();
                 self.right();
             }
         }
     }
 
+    /// Picks the next or previous word pool when the frequency list is hovered or selected,
+    /// the next or previous length otherwise, the ends don't wrap around
+    pub fn bump(&mut self, forward: bool) {
+        let list = match SetList::Frequency {
+            sl if sl == self.active || sl == self.hovered => sl,
+            _ => SetList::Length,
+        };
+        let around = (self.active, self.hovered);
+
+        let items = self.get_list(list).unwrap();
+        let last = items.items.len().saturating_sub(1);
+        let at = match (items.state.selected(), forward) {
+            (None, _) => 0,
+            (Some(i), true) => (i + 1).min(last),
+            (Some(i), false) => i.saturating_sub(1),
+        };
+        items.state.select(Some(at));
+
+        self.active = list;
+        self.hovered = SetList::Nil;
+        self.enter();
+        if list == SetList::Frequency {
+            self.test_cfg.validate();
+        }
+        (self.active, self.hovered) = around;
+    }
+
     pub fn get_list(&mut self, sl: SetList) -> Option<&mut StatefulList<String>> {
         match sl {
             SetList::Length => Some(&mut self.length_list),
             SetList::Frequency => Some(&mut self.frequency_list),
             SetList::Mods => Some(&mut self.mods_list),
             SetList::Test => Some(&mut self.tests_list),
+            SetList::Theme => Some(&mut self.themes_list),
             SetList::Nil => None,
         }
     }
 }
 
+/// tests from the storage followed by the drills
+pub fn list_tests() -> Vec<String> {
+    let mut tests = storage::parse_storage_contents().unwrap_or_else(|e| {
+        warn!("{}, only english is listed", e);
+        vec!["english".to_string()]
+    });
+    tests.extend(
+        langs::DRILLS
+            .iter()
+            .map(|(name, _)| format!("{}{}", DRILL_SIGN, name)),
+    );
+    tests
+}
+
+/// the built-in themes followed by themes from the storage
+fn load_themes() -> Vec<(String, Theme)> {
+    let mut themes: Vec<(String, Theme)> = Theme::builtin()
+        .into_iter()
+        .map(|(name, theme)| (name.to_string(), theme))
+        .collect();
+    for name in storage::parse_theme_names() {
+        let theme = Theme::from_file(storage::get_theme_path(&name));
+        themes.push((name, theme));
+    }
+    themes
+}
+
+/// word counts followed by durations of timed tests
+fn create_length_list() -> StatefulList<String> {
+    StatefulList::with_items(vec_of_strings![
+        "10", "15", "25", "50", "100", "15s", "30s", "60s", "120s", ENDLESS
+    ])
+}
+
+/// parses a number out of a list item, the error says which item it was
+fn parse_number<T>(item: &str) -> anyhow::Result<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    item.parse()
+        .with_context(|| format!("{:?} in the settings isn't a number", item))
+}
+
+/// What an item of the length list stands for
+#[derive(Debug, PartialEq)]
+enum TestLength {
+    Words(usize),
+    Seconds(u64),
+    Endless,
+}
+
+/// reads an item of the length list, words above max_length are clamped to it
+fn parse_length(item: &str, max_length: usize) -> anyhow::Result<TestLength> {
+    if item == ENDLESS {
+        return Ok(TestLength::Endless);
+    }
+
+    let length = match item.strip_suffix('s') {
+        Some(secs) => TestLength::Seconds(parse_number(secs)?),
+        None => TestLength::Words(parse_number::<usize>(item)?.min(max_length)),
+    };
+
+    match length {
+        TestLength::Words(0) | TestLength::Seconds(0) => {
+            anyhow::bail!("the length list has an empty test: {:?}", item)
+        }
+        length => Ok(length),
+    }
+}
+
 fn create_frequency_list(word_count: usize) -> StatefulList<String> {
     let mut initial: Vec<String> = [100, 1000, 5000, 10000, 20000, 50000]
         .iter()
@@ -642,6 +1444,33 @@ mod tests {
     use super::*;
     use std::collections::HashSet;
 
+    #[test]
+    fn test_serialize_finished_test() {
+        let mut cfg = TypingTestConfig {
+            length: 30,
+            test_summary: TestSummary {
+                correct_chars: 150,
+                mistakes: 2,
+                net_wpm: 90.,
+                acc: 98.5,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        cfg.mods.insert(TestMod::Symbols);
+        cfg.mods.insert(TestMod::Numbers);
+
+        let json: serde_json::Value = serde_json::to_value(&cfg).unwrap();
+        assert_eq!(json["name"], "english");
+        assert_eq!(json["length"], 30);
+        assert_eq!(json["time_limit"], serde_json::Value::Null);
+        assert_eq!(json["mods"], serde_json::json!(["numbers", "symbols"]));
+        assert_eq!(json["summary"]["wpm"], 90.);
+        assert_eq!(json["summary"]["acc"], 98.5);
+        assert_eq!(json["summary"]["correct_chars"], 150);
+        assert_eq!(json["summary"]["mistakes"], 2);
+    }
+
     #[test]
     fn test_create_frequency_list() {
         let large = create_frequency_list(69000);
@@ -658,6 +1487,43 @@ mod tests {
         assert_eq!(tiny.items, vec!["20"]);
     }
 
+    #[test]
+    fn test_encode_mod_bitflag() {
+        let mut tm: HashSet<TestMod> = HashSet::new();
+        let zero = encode_test_mod_bitflags(&tm);
+        assert_eq!(zero, 0_u8);
+
+        tm.insert(TestMod::Punctuation);
+        let one = encode_test_mod_bitflags(&tm);
+        assert_eq!(one, 1_u8);
+
+        tm.insert(TestMod::Symbols);
+        let five = encode_test_mod_bitflags(&tm);
+        assert_eq!(five, 5_u8);
+    }
+
+    #[test]
+    fn test_bitflags_round_trip() {
+        let all: Vec<TestMod> = BITFLAG_MODS.right_values().copied().collect();
+
+        // every subset of the mods
+        for subset in 0..(1_u32 << all.len()) {
+            let mods: HashSet<TestMod> = all
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| subset >> i & 1 == 1)
+                .map(|(_, m)| *m)
+                .collect();
+
+            let encoded = encode_test_mod_bitflags(&mods);
+            assert_eq!(decode_test_mod_bitflags(encoded), mods);
+            assert_eq!(
+                encode_test_mod_bitflags(&decode_test_mod_bitflags(encoded)),
+                encoded
+            );
+        }
+    }
+
     #[test]
     fn test_decode_bitflags() {
         let ans = decode_test_mod_bitflags(0b00000101);
@@ -668,5 +1534,370 @@ mod tests {
 
         let zero_ans = decode_test_mod_bitflags(0);
         assert!(zero_ans.is_empty());
+
+        // unknown high bits from a newer version are skipped
+        assert_eq!(decode_test_mod_bitflags(0b10000101), hs);
+        assert!(decode_test_mod_bitflags(0b11110000).is_empty());
+        assert_eq!(TestMod::from_bitflag(0b01000000), None);
+        assert_eq!(TestMod::from_bitflag(0b00000010), Some(TestMod::Numbers));
+    }
+
+    fn select_item(list: &mut StatefulList<String>, item: &str) {
+        let i = list.items.iter().position(|x| x == item).unwrap();
+        list.state.select(Some(i));
+    }
+
+    #[test]
+    fn test_toggle_mods() {
+        let mut settings = Settings::default();
+        settings.test_cfg.mods.clear();
+
+        // Length -> Frequency -> Mods
+        settings.right();
+        settings.down();
+        assert!(settings.hovered == SetList::Mods);
+        settings.enter();
+        assert!(settings.active == SetList::Mods);
+
+        select_item(&mut settings.mods_list, "numbers");
+        settings.enter();
+        assert!(settings.test_cfg.mods.contains(&TestMod::Numbers));
+        assert!(format!("{}", settings.test_cfg).contains(&TestMod::Numbers.to_string()));
+
+        select_item(&mut settings.mods_list, "symbols");
+        settings.enter();
+        assert_eq!(settings.test_cfg.mods.len(), 2);
+
+        // second enter removes it again
+        select_item(&mut settings.mods_list, "numbers");
+        settings.enter();
+        assert!(!settings.test_cfg.mods.contains(&TestMod::Numbers));
+        assert!(settings.test_cfg.mods.contains(&TestMod::Symbols));
+
+        // every mod in the list is known
+        for item in settings.mods_list.items.clone() {
+            select_item(&mut settings.mods_list, &item);
+            settings.enter();
+        }
+        assert_eq!(settings.test_cfg.mods.len(), TEST_MODS.len() - 1);
+    }
+
+    #[test]
+    fn test_bad_list_items() {
+        let mut settings = Settings::default();
+        settings.test_cfg.length = 30;
+        settings.test_cfg.word_pool = 1000;
+
+        settings.enter();
+        settings.length_list.items.push(String::from("lots"));
+        select_item(&mut settings.length_list, "lots");
+        settings.enter();
+        assert_eq!(settings.test_cfg.length, 30);
+        assert_eq!(settings.test_cfg.time_limit, None);
+
+        settings.escape();
+        settings.right();
+        settings.enter();
+        assert!(settings.active == SetList::Frequency);
+        for bad in ["", "many", "-100"] {
+            settings.frequency_list.items.push(bad.to_string());
+            select_item(&mut settings.frequency_list, bad);
+            settings.enter();
+            assert_eq!(settings.test_cfg.word_pool, 1000);
+        }
+
+        // the config is still good to go
+        assert!(settings.test_cfg.validate() >= settings.test_cfg.word_pool);
+        assert_eq!(parse_number::<u64>("15").unwrap(), 15);
+        assert!(parse_number::<u64>("15s").is_err());
+    }
+
+    #[test]
+    fn test_pick_length() {
+        let mut settings = Settings::default();
+        settings.enter();
+        select_item(&mut settings.length_list, "50");
+        settings.enter();
+        assert_eq!(settings.test_cfg.length, 50);
+        assert!(format!("{}", settings.test_cfg).contains("50/"));
+    }
+
+    #[test]
+    fn test_filter_tests() {
+        let mut settings = Settings {
+            all_tests: vec_of_strings!["english", "> classics", "polish", "$ gibberish"],
+            ..Default::default()
+        };
+        settings.clear_test_query();
+
+        // Length -> Test
+        settings.down();
+        settings.enter();
+        assert!(settings.active == SetList::Test);
+
+        for c in "LIS".chars() {
+            settings.push_test_query(c);
+        }
+        assert_eq!(
+            settings.tests_list.items,
+            vec_of_strings!["english", "polish"]
+        );
+
+        settings.test_cfg.name.clear();
+        settings.enter();
+        assert_eq!(settings.test_cfg.name, "english");
+
+        settings.push_test_query('z');
+        assert!(settings.tests_list.items.is_empty());
+        // nothing to pick or move to
+        settings.down();
+        settings.enter();
+        assert_eq!(settings.test_cfg.name, "english");
+
+        settings.pop_test_query();
+        settings.pop_test_query();
+        settings.pop_test_query();
+        assert_eq!(settings.test_query, "L");
+        assert_eq!(settings.tests_list.items.len(), 3);
+
+        // the selection follows the item not the index
+        settings.clear_test_query();
+        select_item(&mut settings.tests_list, "$ gibberish");
+        for c in "ish".chars() {
+            settings.push_test_query(c);
+        }
+        assert_eq!(settings.tests_list.state.selected(), Some(2));
+        settings.clear_test_query();
+        assert_eq!(settings.tests_list.items, settings.all_tests);
+        assert_eq!(settings.tests_list.state.selected(), Some(3));
+
+        settings.push_test_query('>');
+        settings.enter();
+        assert!(matches!(settings.test_cfg.variant, TestVariant::Quote));
+        assert_eq!(settings.test_cfg.name, "classics");
+
+        // leaving the list restores it
+        settings.escape();
+        assert!(settings.test_query.is_empty());
+        assert_eq!(settings.tests_list.items.len(), 4);
+    }
+
+    #[test]
+    fn test_pick_drill() {
+        let mut settings = Settings::default();
+        // Length -> Test
+        settings.down();
+        settings.enter();
+        select_item(&mut settings.tests_list, "~ digits");
+        settings.enter();
+
+        assert!(matches!(settings.test_cfg.variant, TestVariant::Drill));
+        assert_eq!(settings.test_cfg.name, "digits");
+        assert_eq!(format!("{}", settings.test_cfg), "digits drill: 25");
+        assert!(settings.script_cache.contains_key("digits"));
+    }
+
+    #[test]
+    fn test_timed_config() {
+        let mut ttc = TypingTestConfig::default();
+        assert_eq!(ttc.words_to_generate(), ttc.length);
+        assert_eq!(ttc.gib_identity().duration, 0);
+
+        ttc.time_limit = Some(30);
+        assert_eq!(ttc.words_to_generate(), TIMED_WORDS_CHUNK);
+
+        let tid = ttc.gib_identity();
+        assert_eq!(tid.duration, 30);
+        assert_eq!(tid.length, 0);
+        assert_eq!(format!("{}", ttc), "english: 30s/5000 ");
+    }
+
+    #[test]
+    fn test_word_len_config() {
+        let mut ttc = TypingTestConfig::default();
+        assert!(ttc.word_len_range().contains(&1));
+        assert_eq!(format!("{}", ttc), "english: 25/5000 ");
+
+        ttc.min_word_len = Some(6);
+        assert!(!ttc.word_len_range().contains(&5));
+        assert!(ttc.word_len_range().contains(&60));
+        assert_eq!(format!("{}", ttc), "english: 25/5000 6.. ");
+
+        ttc.max_word_len = Some(8);
+        assert!(!ttc.word_len_range().contains(&9));
+        assert_eq!(format!("{}", ttc), "english: 25/5000 6..8 ");
+    }
+
+    #[test]
+    fn test_parse_length() {
+        assert_eq!(parse_length("25", 100).unwrap(), TestLength::Words(25));
+        assert_eq!(parse_length("30s", 100).unwrap(), TestLength::Seconds(30));
+        assert_eq!(parse_length(ENDLESS, 100).unwrap(), TestLength::Endless);
+        // clamped to the cap
+        assert_eq!(parse_length("99999", 100).unwrap(), TestLength::Words(100));
+
+        for bad in ["", "abc", "s", "xs", "-5", "2.5", "0", "0s"] {
+            assert!(parse_length(bad, 100).is_err(), "{:?} parsed", bad);
+        }
+
+        // every item of the length list makes sense
+        for item in &create_length_list().items {
+            assert!(parse_length(item, MAX_TEST_LENGTH).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_validate_length() {
+        let mut cfg = TypingTestConfig {
+            length: usize::MAX,
+            ..Default::default()
+        };
+        cfg.validate();
+        assert_eq!(cfg.length, MAX_TEST_LENGTH);
+
+        cfg.max_length = 10;
+        cfg.length = 11;
+        cfg.validate();
+        assert_eq!(cfg.length, 10);
+    }
+
+    #[test]
+    fn test_validate_word_pool() {
+        let mut cfg = TypingTestConfig {
+            word_pool: 0,
+            ..Default::default()
+        };
+        let word_count = cfg.validate();
+        assert_eq!(cfg.word_pool, MIN_WORD_POOL);
+        assert!(word_count > MIN_WORD_POOL);
+
+        let test = crate::langs::prepare_test(
+            &cfg,
+            &crate::typer::TestColors::default(),
+            &mut crate::langs::WordCache::default(),
+        );
+        // the only word in the pool over and over
+        let text: String = test
+            .iter()
+            .rev()
+            .flatten()
+            .map(|s| s.content.as_ref())
+            .collect();
+        assert_eq!(text.split_whitespace().count(), cfg.length);
+
+        cfg.word_pool = usize::MAX;
+        assert_eq!(cfg.validate(), word_count);
+        assert_eq!(cfg.word_pool, word_count);
+
+        // drills have no word file to check against
+        let mut drill = TypingTestConfig {
+            name: "digits".to_string(),
+            variant: TestVariant::Drill,
+            word_pool: 0,
+            ..Default::default()
+        };
+        assert_eq!(drill.validate(), MIN_WORD_POOL);
+    }
+
+    #[test]
+    fn test_validate_falls_back_to_english() {
+        let mut cfg = TypingTestConfig {
+            name: "surely_no_such_word_file".to_string(),
+            ..Default::default()
+        };
+        assert!(cfg.validate() > MIN_WORD_POOL);
+        assert_eq!(cfg.name, "english");
+
+        let mut script = TypingTestConfig {
+            name: "surely_no_such_script".to_string(),
+            variant: TestVariant::Script,
+            ..Default::default()
+        };
+        script.validate();
+        assert_eq!(script.name, "english");
+        assert!(matches!(script.variant, TestVariant::Standard));
+    }
+
+    #[test]
+    fn test_builtin_themes_listed() {
+        let settings = Settings::default();
+        assert_eq!(settings.themes_list.items[0], "default");
+        for name in ["deuteranopia", "high-contrast"] {
+            assert!(settings.themes_list.items.iter().any(|t| t == name));
+        }
+    }
+
+    #[test]
+    fn test_theme_list() {
+        let mut settings = Settings::default();
+        let custom = Theme {
+            hover: Color::Cyan,
+            done: Color::Rgb(1, 2, 3),
+            ..Theme::default()
+        };
+        settings.themes.push(("custom".to_string(), custom));
+        settings.themes_list.items.push("custom".to_string());
+
+        // Length -> Test -> Theme
+        settings.down();
+        settings.down();
+        assert!(settings.hovered == SetList::Theme);
+        settings.enter();
+
+        settings
+            .themes_list
+            .state
+            .select(Some(settings.themes.len() - 1));
+        // previewed before being committed
+        assert_eq!(settings.get_previewed_theme(), custom);
+        assert_eq!(settings.theme, Theme::default());
+
+        settings.enter();
+        assert_eq!(settings.theme, custom);
+        assert_eq!(settings.colors.hover, Color::Cyan);
+
+        // leaving the list shows the committed theme
+        settings.themes_list.state.select(Some(0));
+        settings.escape();
+        assert_eq!(settings.get_previewed_theme(), custom);
+    }
+
+    #[test]
+    fn test_rename_word_list_moves_cache() {
+        let dir = std::env::temp_dir().join("smokey_test_rename_word_list_moves_cache");
+        let _ = std::fs::remove_dir_all(&dir);
+        storage::save_word_list(&dir, "old-list", b"one\ntwo\nthree\n").unwrap();
+
+        let mut settings = Settings::default();
+        settings.test_cfg.name = "old-list".to_string();
+        let identity = settings.test_cfg.gib_identity();
+        let mut maxes = HashMap::new();
+        maxes.insert(settings.test_cfg.gib_identity(), Some(88.));
+        settings
+            .info_cache
+            .insert("old-list".to_string(), (3, maxes));
+
+        settings
+            .rename_word_list_in(&dir, "old-list", "new-list")
+            .unwrap();
+        let words = dir.join("words");
+        assert!(!words.join("old-list").exists());
+        assert_eq!(
+            std::fs::read_to_string(words.join("new-list")).unwrap(),
+            "one\ntwo\nthree\n"
+        );
+        assert!(!settings.info_cache.contains_key("old-list"));
+        let (count, maxes) = &settings.info_cache["new-list"];
+        assert_eq!(*count, 3);
+        assert_eq!(maxes[&identity], Some(88.));
+        assert_eq!(settings.test_cfg.name, "new-list");
+
+        // deleting the current test goes back to english
+        settings.delete_word_list_in(&dir, "new-list").unwrap();
+        assert!(!words.join("new-list").exists());
+        assert!(!settings.info_cache.contains_key("new-list"));
+        assert_eq!(settings.test_cfg.name, "english");
+
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
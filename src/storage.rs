@@ -1,12 +1,57 @@
-use crate::settings::SCRIPT_SIGN;
+use crate::settings::{DRILL_SIGN, QUOTE_SIGN, SCRIPT_SIGN};
 use directories_next::ProjectDirs;
 use lazy_static::lazy_static;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, io};
+
+/// english word list built into the binary
+/// for installs that don't have the storage copied over
+pub static FALLBACK_ENGLISH: &[u8] = include_bytes!("../storage/words/english");
 
 lazy_static! {
     pub static ref DATABASE: PathBuf = get_storage_dir().join("run_history.db3");
 }
 
+/// What can go wrong reading files from the storage
+#[derive(Debug)]
+pub enum StorageError {
+    NotFound(PathBuf),
+    NotUtf8(PathBuf),
+    /// nothing but blank lines
+    NoWords(PathBuf),
+    Io(PathBuf, io::Error),
+}
+
+impl StorageError {
+    /// missing files get their own variant so callers can fall back
+    pub fn from_io(path: &Path, e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::NotFound => Self::NotFound(path.to_path_buf()),
+            _ => Self::Io(path.to_path_buf(), e),
+        }
+    }
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotFound(path) => write!(f, "{:?} does not exist", path),
+            Self::NotUtf8(path) => write!(f, "{:?} isn't utf8 text", path),
+            Self::NoWords(path) => write!(f, "{:?} has no words", path),
+            Self::Io(path, e) => write!(f, "couldn't read {:?}: {}", path, e),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
 fn get_project_dirs() -> ProjectDirs {
     ProjectDirs::from("pl", "ukmrs", "smokey").expect("no valid directories could be found")
 }
@@ -15,10 +60,48 @@ pub fn get_storage_dir() -> PathBuf {
     get_project_dirs().data_dir().to_path_buf()
 }
 
+/// Makes sure the storage has the bare minimum smokey needs to start
+pub fn bootstrap() {
+    let dir = get_storage_dir();
+    if let Err(e) = bootstrap_storage(&dir) {
+        warn!("couldn't set up storage in {:?}: {}", dir, e);
+    }
+}
+
+/// Creates the storage directories and writes the built-in english word list
+/// if there's none, anything already there is left alone
+pub fn bootstrap_storage(dir: &Path) -> std::io::Result<()> {
+    for subdir in ["words", "scripts", "quotes", "themes", "symbols"] {
+        fs::create_dir_all(dir.join(subdir))?;
+    }
+
+    let english = dir.join("words").join("english");
+    if !english.is_file() {
+        fs::write(english, FALLBACK_ENGLISH)?;
+    }
+    Ok(())
+}
+
 pub fn get_word_list_path(word_list_name: &str) -> PathBuf {
     get_storage_dir().join("words").join(word_list_name)
 }
 
+pub fn get_theme_path(theme_name: &str) -> PathBuf {
+    get_storage_dir()
+        .join("themes")
+        .join(format!("{}.toml", theme_name))
+}
+
+/// symbol clusters of the symbols mod, one per line
+pub fn get_symbols_path(symbols_name: &str) -> PathBuf {
+    get_storage_dir().join("symbols").join(symbols_name)
+}
+
+/// the test picked last time, saved on quit
+pub fn get_last_test_file() -> PathBuf {
+    get_storage_dir().join("config")
+}
+
 // this may be lazy_static later or something
 pub fn get_config_file() -> PathBuf {
     get_project_dirs()
@@ -27,37 +110,291 @@ pub fn get_config_file() -> PathBuf {
         .join("smokey.toml")
 }
 
-pub fn parse_storage_contents() -> Vec<String> {
-    let mut words_list: Vec<String> = get_storage_dir()
-        .join("words")
-        .read_dir()
-        .unwrap()
-        .map(|i| {
-            i.unwrap()
-                .path()
-                .iter()
-                .last()
-                .unwrap()
-                .to_string_lossy()
-                .to_string()
-        })
-        .collect();
-
-    let scripts_iterator = get_storage_dir()
-        .join("scripts")
-        .read_dir()
-        .unwrap()
-        .map(|i| {
-            i.unwrap()
-                .path()
-                .iter()
-                .last()
-                .unwrap()
-                .to_string_lossy()
-                .to_string()
+/// names of theme files in the themes directory without the .toml extension
+pub fn parse_theme_names() -> Vec<String> {
+    let mut themes: Vec<String> = match get_storage_dir().join("themes").read_dir() {
+        Ok(dir) => dir
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .filter_map(|path| Some(path.file_stem()?.to_string_lossy().to_string()))
+            .collect(),
+        Err(_) => vec![],
+    };
+    themes.sort_unstable();
+    themes
+}
+
+/// Reads a file from the storage as text
+pub fn read_text(path: &Path) -> Result<String, StorageError> {
+    let bytes = fs::read(path).map_err(|e| StorageError::from_io(path, e))?;
+    String::from_utf8(bytes).map_err(|_| StorageError::NotUtf8(path.to_path_buf()))
+}
+
+/// a usable word list is utf8 text with at least one word in it
+pub fn is_valid_word_list(path: &Path) -> bool {
+    match read_text(path) {
+        Ok(text) => is_word_list_text(text.as_bytes()),
+        Err(_) => false,
+    }
+}
+
+/// whether the bytes are newline separated words
+pub fn is_word_list_text(bytes: &[u8]) -> bool {
+    // utf8 allows NUL but no word list has any
+    if bytes.contains(&0) {
+        return false;
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.lines().any(|line| !line.trim().is_empty()),
+        Err(_) => false,
+    }
+}
+
+/// Saves the bytes as a word list in the words directory of dir,
+/// a list that isn't text or a name that isn't a plain file name leaves nothing behind
+pub fn save_word_list(dir: &Path, name: &str, bytes: &[u8]) -> anyhow::Result<PathBuf> {
+    check_test_name(name)?;
+    anyhow::ensure!(
+        is_word_list_text(bytes),
+        "that's not a newline separated word list"
+    );
+
+    let words = dir.join("words");
+    fs::create_dir_all(&words)?;
+    let path = words.join(name);
+    // written next to it first so a failed write doesn't leave half a list
+    let part = words.join(format!(".{}.part", name));
+    if let Err(e) = fs::write(&part, bytes).and_then(|_| fs::rename(&part, &path)) {
+        let _ = fs::remove_file(&part);
+        return Err(e.into());
+    }
+    Ok(path)
+}
+
+/// Tests are plain file names in their storage directory
+/// that can't be mistaken for a test of another variant
+pub fn check_test_name(name: &str) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !name.is_empty()
+            && !name.starts_with('.')
+            && !name.starts_with(SCRIPT_SIGN)
+            && !name.starts_with(QUOTE_SIGN)
+            && !name.starts_with(DRILL_SIGN)
+            && !name.contains(['/', '\\'])
+            && !name.contains("..")
+            && !Path::new(name).is_absolute(),
+        "{:?} can't be a test name",
+        name
+    );
+    Ok(())
+}
+
+/// Removes a word list from the words directory of dir,
+/// english comes with smokey and always stays
+pub fn delete_word_list(dir: &Path, name: &str) -> anyhow::Result<()> {
+    check_test_name(name)?;
+    anyhow::ensure!(
+        name != "english",
+        "english comes with smokey, it can't be deleted"
+    );
+    let path = dir.join("words").join(name);
+    fs::remove_file(&path).map_err(|e| StorageError::from_io(&path, e))?;
+    Ok(())
+}
+
+/// Renames a word list in the words directory of dir without overwriting another one,
+/// english keeps its name since it's the fallback for everything else
+pub fn rename_word_list(dir: &Path, from: &str, to: &str) -> anyhow::Result<PathBuf> {
+    check_test_name(from)?;
+    check_test_name(to)?;
+    anyhow::ensure!(
+        from != "english",
+        "english comes with smokey, it can't be renamed"
+    );
+
+    let words = dir.join("words");
+    let (old, new) = (words.join(from), words.join(to));
+    anyhow::ensure!(old.is_file(), "there's no {} word list", from);
+    anyhow::ensure!(!new.exists(), "there's already a {} word list", to);
+    fs::rename(&old, &new)?;
+    Ok(new)
+}
+
+/// paths of the files in the directory
+fn list_dir(dir: &Path) -> Result<Vec<PathBuf>, StorageError> {
+    dir.read_dir()
+        .and_then(|entries| entries.map(|entry| Ok(entry?.path())).collect())
+        .map_err(|e| StorageError::from_io(dir, e))
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// names of valid word lists in the directory
+fn parse_word_lists(dir: &Path) -> Result<Vec<String>, StorageError> {
+    Ok(list_dir(dir)?
+        .into_iter()
+        .filter(|path| {
+            let valid = is_valid_word_list(path);
+            if !valid {
+                warn!("{:?} isn't a valid word list", path);
+            }
+            valid
         })
-        .map(|s| format!("{}{}", SCRIPT_SIGN, s));
+        .map(|path| file_name(&path))
+        .collect())
+}
+
+/// Word lists, #!scripts and > quotes found in the storage
+pub fn parse_storage_contents() -> Result<Vec<String>, StorageError> {
+    let mut words_list = parse_word_lists(&get_storage_dir().join("words"))?;
+
+    let scripts = list_dir(&get_storage_dir().join("scripts"))?;
+    words_list.extend(
+        scripts
+            .iter()
+            .map(|path| format!("{}{}", SCRIPT_SIGN, file_name(path))),
+    );
+
+    // storage from older versions doesn't have the quotes directory
+    if let Ok(dir) = get_storage_dir().join("quotes").read_dir() {
+        let mut quotes: Vec<String> = dir
+            .filter_map(|entry| entry.ok())
+            .map(|entry| format!("{}{}", QUOTE_SIGN, entry.file_name().to_string_lossy()))
+            .collect();
+        quotes.sort_unstable();
+        words_list.extend(quotes);
+    }
+
+    Ok(words_list)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bootstrap_storage() {
+        let dir = std::env::temp_dir().join("smokey_test_bootstrap_storage");
+        let _ = fs::remove_dir_all(&dir);
+
+        bootstrap_storage(&dir).unwrap();
+        let english = dir.join("words").join("english");
+        assert!(is_valid_word_list(&english));
+        assert_eq!(
+            parse_word_lists(&dir.join("words")).unwrap(),
+            vec!["english"]
+        );
+        let words = crate::utils::count_words_from_path(&english).unwrap();
+        assert!(words >= crate::settings::TypingTestConfig::default().word_pool);
+
+        // an existing word list isn't overwritten
+        fs::write(&english, "mine\n").unwrap();
+        bootstrap_storage(&dir).unwrap();
+        assert_eq!(fs::read_to_string(&english).unwrap(), "mine\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_word_lists() {
+        let dir = std::env::temp_dir().join("smokey_test_parse_word_lists");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("valid"), "one\ntwo\nthree\n\n").unwrap();
+        fs::write(dir.join("empty"), "").unwrap();
+        fs::write(dir.join("blank"), "\n  \n\n").unwrap();
+        fs::write(
+            dir.join("binary"),
+            [0x7f, b'E', b'L', b'F', 0, 0, 0xff, 0xfe],
+        )
+        .unwrap();
+
+        assert_eq!(parse_word_lists(&dir).unwrap(), vec!["valid"]);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(
+            parse_word_lists(&dir),
+            Err(StorageError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_read_text() {
+        let dir = std::env::temp_dir().join("smokey_test_read_text");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("text"), "one\n").unwrap();
+        fs::write(dir.join("binary"), [0xff, 0xfe]).unwrap();
+
+        assert_eq!(read_text(&dir.join("text")).unwrap(), "one\n");
+        assert!(matches!(
+            read_text(&dir.join("binary")),
+            Err(StorageError::NotUtf8(_))
+        ));
+        assert!(matches!(
+            read_text(&dir.join("missing")),
+            Err(StorageError::NotFound(_))
+        ));
+        // directories can't be read as files
+        assert!(matches!(read_text(&dir), Err(StorageError::Io(..))));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_word_list() {
+        let dir = std::env::temp_dir().join("smokey_test_save_word_list");
+        let _ = fs::remove_dir_all(&dir);
+
+        let path = save_word_list(&dir, "english-1k", b"one\ntwo\n").unwrap();
+        assert_eq!(path, dir.join("words").join("english-1k"));
+        assert_eq!(
+            parse_word_lists(&dir.join("words")).unwrap(),
+            vec!["english-1k"]
+        );
+
+        // nothing is written for bad lists or names
+        assert!(save_word_list(&dir, "binary", &[0x7f, b'E', 0, 0xff]).is_err());
+        assert!(save_word_list(&dir, "blank", b"\n \n").is_err());
+        assert!(save_word_list(&dir, "../escape", b"one\n").is_err());
+        assert!(save_word_list(&dir, "#!script", b"one\n").is_err());
+        assert_eq!(dir.join("words").read_dir().unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_delete_and_rename_word_lists() {
+        let dir = std::env::temp_dir().join("smokey_test_delete_and_rename_word_lists");
+        let _ = fs::remove_dir_all(&dir);
+        bootstrap_storage(&dir).unwrap();
+        save_word_list(&dir, "german", b"eins\nzwei\n").unwrap();
+        save_word_list(&dir, "french", b"un\ndeux\n").unwrap();
+
+        // the bundled list stays put
+        assert!(delete_word_list(&dir, "english").is_err());
+        assert!(rename_word_list(&dir, "english", "mine").is_err());
+        assert!(dir.join("words").join("english").is_file());
+
+        // names are checked and nothing gets overwritten
+        assert!(rename_word_list(&dir, "german", "../german").is_err());
+        assert!(rename_word_list(&dir, "german", "~ digits").is_err());
+        assert!(rename_word_list(&dir, "german", "french").is_err());
+        assert!(rename_word_list(&dir, "missing", "found").is_err());
+        assert!(delete_word_list(&dir, "missing").is_err());
+
+        let path = rename_word_list(&dir, "german", "deutsch").unwrap();
+        assert_eq!(fs::read_to_string(path).unwrap(), "eins\nzwei\n");
+        delete_word_list(&dir, "french").unwrap();
+
+        let mut lists = parse_word_lists(&dir.join("words")).unwrap();
+        lists.sort_unstable();
+        assert_eq!(lists, vec!["deutsch", "english"]);
 
-    words_list.extend(scripts_iterator);
-    words_list
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -1,16 +1,192 @@
 use crate::colorscheme::ToForeground;
-use crate::langs;
+use crate::langs::{self, Quote, WordCache};
 use crate::settings::TestSummary;
-use crate::settings::TypingTestConfig;
+use crate::settings::{ExtraLetters, TestVariant, TypingTestConfig, MAX_EXTRA_MISTAKES};
+use std::collections::HashMap;
 use std::time::{Duration, Instant};
 use tui::{style::Color, text::Span};
+use unicode_width::UnicodeWidthStr;
 
 /// 0.05s tiny time offset appllied when pressed the first key
 /// 0.05s delay between chars corresponds to 240wpm
 /// which is more than the world record
 /// seems more than fair
 const INITAL_OFFSET: Duration = Duration::from_millis(50);
-const MAX_EXTRA_MISTAKES: usize = 3;
+/// after this many correct keystrokes the timings stop being recorded
+/// that is over an hour long test at 200 wpm
+const MAX_KEY_TIMES: usize = 100_000;
+/// streamed tests keep this many of the latest lines for replays
+/// so endless sessions don't grow without bound
+const MAX_REPLAY_LINES: usize = 200;
+/// seconds the rolling wpm of endless tests looks back
+pub const ROLLING_SECONDS: u64 = 10;
+/// word deletions that can be undone with Ctrl + Z
+const UNDO_DEPTH: usize = 16;
+/// accuracy isn't checked against min_acc before this many keystrokes
+/// otherwise a single early typo would be 0%
+const MIN_ACC_KEYSTROKES: usize = 10;
+
+/// a word is 5 chars, spaces included, whatever the actual words are
+pub const CHARS_PER_WORD: f64 = 5.;
+
+/// Words per minute for the chars typed in the elapsed time
+/// ```
+/// use smokey::typer::words_per_minute;
+/// use std::time::Duration;
+///
+/// assert_eq!(words_per_minute(250, Duration::from_secs(60)), 50.);
+/// assert_eq!(words_per_minute(25, Duration::from_secs(6)), 50.);
+/// assert_eq!(words_per_minute(25, Duration::ZERO), 0.);
+/// ```
+pub fn words_per_minute(chars: usize, elapsed: Duration) -> f64 {
+    let minutes = elapsed.as_secs_f64() / 60.;
+    if minutes == 0. {
+        return 0.;
+    }
+    chars as f64 / CHARS_PER_WORD / minutes
+}
+
+/// wpm in each second of the test based on the times of correct keystrokes
+/// in millis, the last second is usually only partially typed in
+pub fn wpm_per_second(key_times: &[u32]) -> Vec<f64> {
+    let last = match key_times.last() {
+        Some(ms) => *ms as usize / 1000,
+        None => return vec![],
+    };
+
+    let mut per_second = vec![0_usize; last + 1];
+    for ms in key_times {
+        per_second[*ms as usize / 1000] += 1;
+    }
+
+    per_second
+        .iter()
+        .map(|&c| words_per_minute(c, Duration::from_secs(1)))
+        .collect()
+}
+
+/// How a char of a line typed in one go compares to the line
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CharDiff {
+    Correct(char),
+    /// the char that should be there and the one that was typed
+    Wrong(char, char),
+    /// left out at the end of the line
+    Missing(char),
+    /// typed past the end of the line
+    Extra(char),
+}
+
+/// Compares the typed line to the target one char at a time
+pub fn diff_line(target: &str, typed: &str) -> Vec<CharDiff> {
+    let mut target = target.chars();
+    let mut typed = typed.chars();
+    let mut diff = vec![];
+    loop {
+        diff.push(match (target.next(), typed.next()) {
+            (Some(t), Some(c)) if t == c => CharDiff::Correct(t),
+            (Some(t), Some(c)) => CharDiff::Wrong(t, c),
+            (Some(t), None) => CharDiff::Missing(t),
+            (None, Some(c)) => CharDiff::Extra(c),
+            (None, None) => return diff,
+        });
+    }
+}
+
+/// A word finished by typing the space after it,
+/// skipping a word finishes it with a space as well
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WordEnd {
+    /// millis since the begining, same as key_times
+    pub ms: u32,
+    pub skipped: bool,
+}
+
+/// wpm of each word, the correct keystrokes since the previous space make up the word
+/// skipped words are marked with NAN, the last one ends with the test instead of a space
+/// ```
+/// use smokey::typer::{wpm_per_word, WordEnd};
+///
+/// let key_times = [200, 400, 600, 2000, 2600, 3200];
+/// let ends = [WordEnd { ms: 600, skipped: false }, WordEnd { ms: 2000, skipped: true }];
+/// let wpms = wpm_per_word(&key_times, &ends);
+/// assert_eq!(wpms[0].round(), 60.);
+/// assert!(wpms[1].is_nan());
+/// assert_eq!(wpms[2].round(), 20.);
+/// ```
+pub fn wpm_per_word(key_times: &[u32], word_ends: &[WordEnd]) -> Vec<f64> {
+    let mut keys = key_times.iter().peekable();
+    let mut since = 0;
+    let mut wpms = Vec::with_capacity(word_ends.len() + 1);
+
+    for end in word_ends {
+        let mut typed = 0;
+        while keys.next_if(|&&ms| ms <= end.ms).is_some() {
+            typed += 1;
+        }
+        wpms.push(match end.skipped {
+            true => f64::NAN,
+            false => words_per_minute(typed, Duration::from_millis((end.ms - since) as u64)),
+        });
+        since = end.ms;
+    }
+
+    let rest: Vec<u32> = keys.copied().collect();
+    if let Some(&last) = rest.last() {
+        let elapsed = Duration::from_millis(last.saturating_sub(since) as u64);
+        wpms.push(words_per_minute(rest.len(), elapsed));
+    }
+    wpms
+}
+
+/// words in the lines, every word but the last is followed by a space
+fn count_words(lines: &[Vec<Span>]) -> usize {
+    let spaces = lines.iter().flatten().filter(|s| s.content == " ").count();
+    match lines.iter().any(|line| !line.is_empty()) {
+        true => spaces + 1,
+        false => 0,
+    }
+}
+
+/// columns the text takes up in the terminal
+fn columns(text: &str) -> u16 {
+    text.width() as u16
+}
+
+/// packs words into lines at most width columns wide,
+/// a word longer than that gets a line of its own
+/// every line but the last one ends with a space
+fn wrap_spans<'a>(spans: Vec<Span<'a>>, width: usize) -> Vec<Vec<Span<'a>>> {
+    let mut lines: Vec<Vec<Span>> = vec![];
+    let mut line: Vec<Span> = vec![];
+    let mut word: Vec<Span> = vec![];
+    let mut line_len = 0;
+    let mut word_len = 0;
+
+    let mut spans = spans.into_iter().peekable();
+    while let Some(span) = spans.next() {
+        let is_space = span.content == " ";
+        word_len += span.content.width();
+        word.push(span);
+
+        if is_space || spans.peek().is_none() {
+            // the trailing space can hang over the edge
+            let fits = line.is_empty() || line_len + word_len - is_space as usize <= width;
+            if !fits {
+                lines.push(std::mem::take(&mut line));
+                line_len = 0;
+            }
+            line_len += word_len;
+            line.append(&mut word);
+            word_len = 0;
+        }
+    }
+
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
 
 pub struct WpmHoarder {
     pub wpms: Vec<f64>,
@@ -32,8 +208,8 @@ impl WpmHoarder {
         self.seconds = 1;
     }
 
-    fn is_due(&mut self, begining: Instant) -> bool {
-        let elapsed = begining.elapsed().as_secs();
+    fn is_due(&mut self, elapsed: Duration) -> bool {
+        let elapsed = elapsed.as_secs();
         let due_time = self.seconds * (self.wpms.len() as u64 + 1);
         elapsed >= due_time
     }
@@ -80,6 +256,7 @@ pub struct TestColors {
     pub wrong: Color,
     pub todo: Color,
     pub done: Color,
+    pub extra: Color,
 }
 
 impl Default for TestColors {
@@ -88,10 +265,29 @@ impl Default for TestColors {
             wrong: Color::Red,
             todo: Color::Gray,
             done: Color::White,
+            extra: Color::LightRed,
         }
     }
 }
 
+/// Everything a word deletion can change, taken right before it
+pub struct WordDeletion<'a> {
+    up: Vec<Span<'a>>,
+    active: Vec<Span<'a>>,
+    down: Vec<Span<'a>>,
+    // going back a line only ever pushes onto the backburner
+    backburner_len: usize,
+    done: usize,
+    pdone: usize,
+    length: usize,
+    blanks: usize,
+    mistakes: usize,
+    extra_mistakes: usize,
+    current_char: char,
+    // deleting a finished word drops its end
+    word_ends: Vec<WordEnd>,
+}
+
 pub struct TestState<'a> {
     pub up: Vec<Span<'a>>,
     pub active: Vec<Span<'a>>,
@@ -112,19 +308,68 @@ pub struct TestState<'a> {
     pub extra_mistakes: usize,
     pub pmiss: usize,
 
-    pub cursor_x: u16,
     pub current_char: char,
 
     pub first: bool,
     pub begining: Instant,
+    // set while the test is paused
+    pub paused_at: Option<Instant>,
+    // total time spent paused
+    pub paused_for: Duration,
     // source for generating test
     pub source: String,
 
     pub text: Vec<Span<'a>>,
     pub length: usize,
+    // words in the whole test, timed tests get more as they go
+    pub total_words: usize,
 
     pub hoarder: WpmHoarder,
 
+    // timed tests end when this much time passes since the first key
+    pub time_limit: Option<Duration>,
+    // words keep coming until the test is finished by hand
+    pub endless: bool,
+
+    // millis since the begining of each correct keystroke
+    pub key_times: Vec<u32>,
+    // spaces that finished words, backspacing over one takes it back
+    pub word_ends: Vec<WordEnd>,
+
+    // word pool of the last test, restarts reuse it
+    pub word_cache: WordCache,
+    // shown on the post screen after quote tests
+    pub quote_author: Option<String>,
+    // lines of the current test as they were generated, replays go through them again
+    pub replay_lines: Vec<Vec<Span<'a>>>,
+    // net wpm of the run this one replays
+    pub previous_wpm: Option<f64>,
+    // chars in a line when the terminal is too narrow for generated lines
+    pub line_width: Option<usize>,
+    // extra letters a word can get before the keys are ignored
+    pub max_extra_mistakes: usize,
+    // whether extra letters show up at all
+    pub extra_letters: ExtraLetters,
+    // space typed mid word skips to the next one instead of being a wrong letter
+    pub skip_on_space: bool,
+    /// typed in place of the space between words, the spans keep the space
+    pub separator: char,
+    // wrong keys don't advance, see TypingTestConfig
+    pub strict: bool,
+    // hardcore limits the test is held to, see TypingTestConfig
+    pub max_mistakes: Option<usize>,
+    pub min_acc: Option<f64>,
+    // wrong keys by the char that should have been typed, extras count toward the space
+    pub missed_keys: HashMap<char, usize>,
+    // wrong keys by the word they were typed in, lowercased without punctuation
+    pub missed_words: HashMap<String, usize>,
+    // word deletions since the last typed key, the latest one at the end
+    pub undo_stack: Vec<WordDeletion<'a>>,
+    // the submit layout types the line here and checks it on Enter
+    pub line_buffer: String,
+    // how the last submitted line went
+    pub last_line: Vec<CharDiff>,
+
     pub colors: TestColors,
 }
 
@@ -138,6 +383,8 @@ impl Default for TestState<'_> {
 
             text: vec![],
             begining: Instant::now(),
+            paused_at: None,
+            paused_for: Duration::ZERO,
 
             // characters done on current line
             // this variable is reset after each line
@@ -155,18 +402,38 @@ impl Default for TestState<'_> {
             // used to calculate accuracy
             pmiss: 0,
 
-            cursor_x: 0,
-
             source: "storage/words/english".to_string(),
             length: 0,
+            total_words: 0,
             current_char: ' ',
             hoarder: WpmHoarder::new(400),
+            time_limit: None,
+            endless: false,
+            key_times: vec![],
+            word_ends: vec![],
+            word_cache: WordCache::default(),
+            quote_author: None,
+            replay_lines: vec![],
+            previous_wpm: None,
+            line_width: None,
+            max_extra_mistakes: MAX_EXTRA_MISTAKES,
+            extra_letters: ExtraLetters::default(),
+            skip_on_space: false,
+            separator: ' ',
+            strict: false,
+            max_mistakes: None,
+            min_acc: None,
+            missed_keys: HashMap::new(),
+            missed_words: HashMap::new(),
+            undo_stack: vec![],
+            line_buffer: String::new(),
+            last_line: vec![],
             colors: TestColors::default(),
         }
     }
 }
 
-impl TestState<'_> {
+impl<'a> TestState<'a> {
     pub fn with_colors(colors: TestColors) -> Self {
         Self {
             colors,
@@ -174,38 +441,242 @@ impl TestState<'_> {
         }
     }
 
+    /// words left to type, the one under the cursor included
+    pub fn words_left(&self) -> usize {
+        let mut ahead = self.active[self.done.min(self.active.len())..]
+            .iter()
+            .chain(self.down.iter())
+            .chain(self.backburner.iter().flatten())
+            .peekable();
+        // every word but the last is followed by a space,
+        // with nothing ahead the last one is typed as well
+        match ahead.peek() {
+            Some(_) => ahead.filter(|s| s.content == " ").count() + 1,
+            None => 0,
+        }
+    }
+
+    /// column of the cursor counting from 1, right after whatever
+    /// is typed on the active line, extra mistakes included
+    pub fn cursor_x(&self) -> u16 {
+        1 + self
+            .active
+            .iter()
+            .take(self.done)
+            .map(|s| columns(&s.content))
+            .sum::<u16>()
+    }
+
+    /// chars currently typed, uncorrected mistakes and extras included
+    /// blanks are skipped by the cursor so they don't count
+    pub fn typed_chars(&self) -> usize {
+        self.pdone + self.done - self.blanks + self.extra_mistakes
+    }
+
+    /// chars currently typed correctly
+    pub fn correct_chars(&self) -> usize {
+        self.pdone + self.done - self.blanks - self.mistakes
+    }
+
+    /// time the test has been running for, pauses don't count
+    pub fn elapsed(&self) -> Duration {
+        let now = self.paused_at.unwrap_or_else(Instant::now);
+        now.duration_since(self.begining)
+            .saturating_sub(self.paused_for)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    /// Pauses or resumes the test, the test can't be paused before it starts
+    pub fn toggle_pause(&mut self) {
+        match self.paused_at.take() {
+            Some(paused_at) => self.paused_for += paused_at.elapsed(),
+            None if !self.first => self.paused_at = Some(Instant::now()),
+            None => (),
+        }
+    }
+
+    /// the clock starts with the first key, not when the test shows up
+    fn chars_to_wpm(&self, chars: usize) -> f64 {
+        words_per_minute(chars, self.elapsed())
+    }
+
+    /// net wpm, correct chars only so uncorrected mistakes don't count
     pub fn calculate_wpm(&self) -> f64 {
-        let numerator: f64 = 12. * (self.pdone + self.done - self.blanks - self.mistakes) as f64;
-        let elapsed = Instant::now().duration_since(self.begining).as_secs_f64();
-        numerator / elapsed
+        self.chars_to_wpm(self.correct_chars())
     }
 
-    fn calculate_acc(&self) -> f64 {
-        let correct = (self.pdone + self.done - self.blanks - self.mistakes) as f64;
+    /// wpm of the last ROLLING_SECONDS, the overall wpm of
+    /// a long endless session barely moves anymore
+    pub fn rolling_wpm(&self) -> f64 {
+        let elapsed = self.elapsed();
+        let window = Duration::from_secs(ROLLING_SECONDS).min(elapsed);
+        // the timings are no longer recorded
+        if window.is_zero() || self.key_times.len() >= MAX_KEY_TIMES {
+            return self.calculate_wpm();
+        }
+
+        let since = (elapsed - window).as_millis() as u32;
+        let recent = self
+            .key_times
+            .iter()
+            .rev()
+            .take_while(|&&ms| ms >= since)
+            .count();
+        words_per_minute(recent, window)
+    }
+
+    /// raw wpm, every typed char counts mistakes included
+    pub fn calculate_raw_wpm(&self) -> f64 {
+        self.chars_to_wpm(self.typed_chars())
+    }
+
+    fn record_key_time(&mut self) {
+        if self.key_times.len() < MAX_KEY_TIMES {
+            let elapsed = self.elapsed().as_millis();
+            self.key_times.push(elapsed as u32);
+        }
+    }
+
+    fn record_word_end(&mut self) {
+        if self.word_ends.len() < MAX_KEY_TIMES {
+            let ms = self.elapsed().as_millis() as u32;
+            self.word_ends.push(WordEnd { ms, skipped: false });
+        }
+    }
+
+    /// Consistency in percent based on the coefficient of variation
+    /// of wpm in each second of the test, scaled the same way monkeytype does it
+    /// 100 means every second was typed at the same speed
+    pub fn consistency(&self) -> f64 {
+        let wpms = wpm_per_second(&self.key_times);
+        if wpms.is_empty() {
+            return 0.;
+        }
+
+        let mean = wpms.iter().sum::<f64>() / wpms.len() as f64;
+        let variance = wpms.iter().map(|w| (w - mean).powi(2)).sum::<f64>() / wpms.len() as f64;
+        let cv = variance.sqrt() / mean;
+
+        100. * (1. - (cv + cv.powi(3) / 3. + cv.powi(5) / 5.).tanh())
+    }
+
+    /// accuracy in percent, nothing typed yet is 100
+    pub fn calculate_acc(&self) -> f64 {
+        let correct = self.correct_chars() as f64;
         let key_presses = correct + self.pmiss as f64;
+        if key_presses == 0. {
+            return 100.;
+        }
         correct / key_presses * 100.
     }
 
+    /// net wpm and accuracy shown while typing,
+    /// wpm stays at 0 until the first key starts the clock
+    pub fn live_stats(&self) -> (f64, f64) {
+        match self.first {
+            true => (0., 100.),
+            false => (self.calculate_wpm(), self.calculate_acc()),
+        }
+    }
+
     pub fn summarize(&self) -> TestSummary {
         TestSummary {
-            correct_chars: self.pdone + self.done - self.blanks - self.mistakes,
+            correct_chars: self.correct_chars(),
             mistakes: self.mistakes + self.extra_mistakes,
-            wpm: self.calculate_wpm(),
+            raw_wpm: self.calculate_raw_wpm(),
+            net_wpm: self.calculate_wpm(),
+            consistency: self.consistency(),
             acc: self.calculate_acc(),
+            wpm_per_word: wpm_per_word(&self.key_times, &self.word_ends),
         }
     }
 
     pub fn reset(&mut self, config: &TypingTestConfig) {
+        let wordy = match config.variant {
+            TestVariant::Quote => match Quote::random_from(config.get_quotes_file_path()) {
+                Ok(quote) => {
+                    self.quote_author = quote.author.clone();
+                    langs::prepare_quote_test(&quote, &self.colors)
+                }
+                // same as a script that fails
+                Err(e) => {
+                    warn!("{}, falling back to english", e);
+                    self.quote_author = None;
+                    let english = TypingTestConfig::default();
+                    langs::prepare_test(&english, &self.colors, &mut self.word_cache)
+                }
+            },
+            _ => {
+                self.quote_author = None;
+                langs::prepare_test(config, &self.colors, &mut self.word_cache)
+            }
+        };
+        self.previous_wpm = None;
+        self.start(wordy, config);
+    }
+
+    /// starts over with the same text after failing hardcore limits,
+    /// nothing typed so far counts
+    pub fn restart(&mut self, config: &TypingTestConfig) {
+        let wordy = self.replay_lines.clone();
+        self.start(wordy, config);
+    }
+
+    /// checks whether the test broke the hardcore limits,
+    /// every wrong key counts even if it was backspaced
+    pub fn failed(&self) -> bool {
+        if let Some(max) = self.max_mistakes {
+            if self.pmiss > max {
+                return true;
+            }
+        }
+
+        match self.min_acc {
+            Some(min_acc) => {
+                let keystrokes = self.correct_chars() + self.pmiss;
+                keystrokes >= MIN_ACC_KEYSTROKES && self.calculate_acc() < min_acc
+            }
+            None => false,
+        }
+    }
+
+    /// restarts the test with the exact same text as the last one,
+    /// its result is kept around to compare against
+    pub fn replay(&mut self, config: &TypingTestConfig) {
+        let wordy = self.replay_lines.clone();
+        self.previous_wpm = Some(config.test_summary.net_wpm);
+        self.start(wordy, config);
+    }
+
+    fn start(&mut self, mut wordy: Vec<Vec<Span<'a>>>, config: &TypingTestConfig) {
+        self.replay_lines = wordy.clone();
+        self.undo_stack.clear();
+        self.total_words = count_words(&wordy);
         self.blanks = 0;
         self.done = 0;
         self.pdone = 0;
         self.up = vec![];
         self.pmiss = 0;
+        self.missed_keys.clear();
+        self.missed_words.clear();
         self.mistakes = 0;
         self.extra_mistakes = 0;
+        self.max_extra_mistakes = config.max_extra_mistakes;
+        self.extra_letters = config.extra_letters;
+        self.skip_on_space = config.skip_on_space;
+        self.separator = config.separator;
+        self.strict = config.strict;
+        self.max_mistakes = config.max_mistakes;
+        self.min_acc = config.min_acc;
         self.hoarder.reset();
+        self.key_times.clear();
+        self.word_ends.clear();
+        self.line_buffer.clear();
+        self.last_line.clear();
 
-        let mut wordy = langs::prepare_test(config, &self.colors);
         self.active = wordy.pop().expect("prep_test output shouldn't be empty");
         self.length = self.active.len();
         self.down = wordy.pop().unwrap_or_default();
@@ -214,10 +685,122 @@ impl TestState<'_> {
         self.length = self.active.len();
         self.first = true;
         self.begining = Instant::now();
+        self.paused_at = None;
+        self.paused_for = Duration::ZERO;
+        if let Some(width) = self.line_width {
+            self.rewrap(width);
+        }
+        // only word tests can be timed
+        self.time_limit = match config.variant {
+            TestVariant::Standard => config.time_limit.map(Duration::from_secs),
+            _ => None,
+        };
+        self.endless = matches!(config.variant, TestVariant::Standard) && config.endless;
+    }
+
+    /// lines wrap to the new width or go back to the generated
+    /// width with None, typing progress is kept
+    pub fn set_line_width(&mut self, line_width: Option<usize>) {
+        if self.line_width == line_width {
+            return;
+        }
+        self.line_width = line_width;
+        self.rewrap(line_width.unwrap_or(langs::LIMIT));
+    }
+
+    /// wraps everything from the word under the cursor onward anew,
+    /// the already typed words of the active line move up
+    /// as if the line was finished there
+    fn rewrap(&mut self, width: usize) {
+        let mut word_start = self.done;
+        while word_start > 0 && self.fetch(word_start - 1) != " " {
+            word_start -= 1;
+        }
+
+        let mut rest = self.active.split_off(word_start);
+        if word_start > 0 {
+            self.pdone += word_start;
+            self.done -= word_start;
+            self.up = std::mem::take(&mut self.active);
+        }
+
+        rest.append(&mut self.down);
+        for line in self.backburner.drain(..).rev() {
+            rest.extend(line);
+        }
+
+        let mut lines = wrap_spans(rest, width);
+        lines.reverse();
+        self.active = lines.pop().unwrap_or_default();
+        self.length = self.active.len();
+        self.down = lines.pop().unwrap_or_default();
+        self.backburner = lines;
+    }
+
+    /// checks if timed test ran out of time,
+    /// the clock starts ticking with the first key
+    pub fn time_is_up(&self) -> bool {
+        match self.time_limit {
+            Some(limit) => !self.first && self.elapsed() >= limit,
+            None => false,
+        }
+    }
+
+    /// seconds left in a timed test
+    pub fn time_left(&self) -> Option<u64> {
+        let limit = self.time_limit?;
+        if self.first {
+            return Some(limit.as_secs());
+        }
+        Some(limit.saturating_sub(self.elapsed()).as_secs())
+    }
+
+    /// appends freshly generated lines once a timed or endless test
+    /// runs low on text so it can go on until the time is up
+    pub fn top_up(&mut self, config: &TypingTestConfig) {
+        let streamed = self.time_limit.is_some() || self.endless;
+        if !streamed || !self.backburner.is_empty() {
+            return;
+        }
+
+        // the last line of the previous batch lacks the trailing space
+        if !self.down.is_empty() {
+            langs::add_space_with_blank(&mut self.down, &self.colors);
+            if let Some(last) = self.replay_lines.first_mut() {
+                langs::add_space_with_blank(last, &self.colors);
+            }
+        }
+
+        let mut more = langs::prepare_test(config, &self.colors, &mut self.word_cache);
+        self.total_words += count_words(&more);
+
+        // replays get the whole text typed so far
+        let mut replay_lines = more.clone();
+        replay_lines.append(&mut self.replay_lines);
+        // the oldest lines are at the back
+        replay_lines.truncate(MAX_REPLAY_LINES);
+        self.replay_lines = replay_lines;
+
+        more.append(&mut self.backburner);
+        self.backburner = more;
+
+        if let Some(width) = self.line_width {
+            let mut ahead = std::mem::take(&mut self.down);
+            for line in self.backburner.drain(..).rev() {
+                ahead.extend(line);
+            }
+            self.backburner = wrap_spans(ahead, width);
+            self.backburner.reverse();
+            self.down = vec![];
+        }
+
+        if self.down.is_empty() {
+            self.down = self.backburner.pop().unwrap_or_default();
+        }
     }
 
     pub fn update_wpm_history(&mut self) {
-        if self.hoarder.is_due(self.begining) {
+        if !self.is_paused() && self.hoarder.is_due(self.elapsed()) {
             self.hoarder.push(self.calculate_wpm());
         }
     }
@@ -277,7 +860,6 @@ impl TestState<'_> {
 
         self.done = 0;
 
-        self.cursor_x = 1;
         self.length = self.active.len();
         self.set_next_char();
         false
@@ -295,13 +877,6 @@ impl TestState<'_> {
 
         self.pdone -= self.length;
         self.done = self.length;
-
-        let mut crs = 0;
-        for sp in &self.active {
-            crs += sp.content.len();
-        }
-
-        self.cursor_x = 1 + crs as u16;
     }
 
     fn set_next_char_or_end(&mut self) -> bool {
@@ -313,54 +888,138 @@ impl TestState<'_> {
         self.progress_line()
     }
 
+    /// the word before the space already has as many extras as it can
+    fn extras_capped(&self) -> bool {
+        self.extra_letters == ExtraLetters::Ignore
+            || self.fetch(self.done - 1).chars().count() >= self.max_extra_mistakes
+    }
+
+    /// counts a wrong key toward the accuracy and the char that was expected
+    fn miss(&mut self, expected: char) {
+        self.pmiss += 1;
+        *self.missed_keys.entry(expected).or_insert(0) += 1;
+        if let Some(word) = self.current_word() {
+            *self.missed_words.entry(word).or_insert(0) += 1;
+        }
+    }
+
+    /// the word the cursor is in or right after, None for numbers and symbols
+    fn current_word(&self) -> Option<String> {
+        let at = self.done.min(self.active.len());
+        let start = self.active[..at]
+            .iter()
+            .rposition(|s| s.content == " ")
+            .map_or(0, |i| i + 1);
+        // the blank before the space holds extras, not the word
+        let end = self.active[start..]
+            .iter()
+            .position(|s| s.content == " ")
+            .map_or(self.active.len(), |i| start + i - 1);
+
+        let word: String = self
+            .active
+            .get(start..end)?
+            .iter()
+            .map(|s| s.content.as_ref())
+            .collect();
+        let word = word
+            .trim_matches(|c: char| !c.is_alphabetic())
+            .to_lowercase();
+        match !word.is_empty() && word.chars().all(char::is_alphabetic) {
+            true => Some(word),
+            false => None,
+        }
+    }
+
+    /// chars missed the most, ties go in alphabetical order
+    pub fn problem_keys(&self, amount: usize) -> Vec<(char, usize)> {
+        let mut keys: Vec<(char, usize)> = self.missed_keys.iter().map(|(&c, &n)| (c, n)).collect();
+        keys.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        keys.truncate(amount);
+        keys
+    }
+
+    /// marks the rest of the word wrong and types the space after it,
+    /// returns true if that was the last word of the test
+    fn skip_word(&mut self) -> bool {
+        while self.done < self.length && !self.fetch(self.done).is_empty() {
+            let expected = self.fetch(self.done).chars().next().expect("checked above");
+            self.mistakes += 1;
+            self.miss(expected);
+            self.active[self.done].style = self.colors.wrong.fg();
+            self.done += 1;
+        }
+
+        if self.done == self.length {
+            return self.set_next_char_or_end();
+        }
+        // steps over the blank onto the space
+        self.set_next_char_beware_blanks();
+        let skipped = self.word_ends.len();
+        let end = self.on_char(' ');
+        if let Some(word_end) = self.word_ends.get_mut(skipped) {
+            word_end.skipped = true;
+        }
+        end
+    }
+
+    /// The char a typed key stands for in the test, the separator is
+    /// the space between words and a space is just a wrong key then
+    pub fn key_char(&self, key: char) -> char {
+        match key {
+            key if key == self.separator => ' ',
+            ' ' => self.separator,
+            key => key,
+        }
+    }
+
     /// handles char event and returns
     /// returns a boolean signaling status of the test
     /// returns false when the test continues
     /// returns true when the test is done
     pub fn on_char(&mut self, c: char) -> bool {
-        self.cursor_x += 1;
-
-        // TODO this implemenation is quick and dirty
-        // and is just slapped onto existing infrastracture
-        // I don't really care for now
-        // as it works
-        if self.first {
-            self.hoarder.reset();
-            self.first = false;
-            // ofseting begining prevents very ugly graph start though not entirely.
-            // It's also only fair as the player would get the first char for free
-            // instead he gets it on the above world record pace of 240wpm
-            // which isn't at all significant for the player
-            // but helps the software
-            self.begining = Instant::now().checked_sub(INITAL_OFFSET).unwrap();
+        // undoing a deletion after typing on would throw the typed keys away
+        self.undo_stack.clear();
+
+        // extras past the cap aren't shown so the key is ignored
+        // the miss still contributes to the accuracy though
+        // TODO extremely low priority "controversial" decision to think through
+        if self.current_char == ' ' && c != ' ' && self.extras_capped() {
+            self.miss(self.current_char);
+            return false;
         }
 
+        self.start_clock();
+
         if c == self.current_char {
+            self.record_key_time();
+            if c == ' ' {
+                self.record_word_end();
+            }
             self.active[self.done].style = self.colors.done.fg();
             self.done += 1;
             return self.set_next_char_or_end();
         }
 
         // wrong key
-        // adds the mistake and the end of the word
-        if self.current_char == ' ' {
-            self.pmiss += 1;
-            if self.fetch(self.done - 1).len() < MAX_EXTRA_MISTAKES {
-                self.extra_mistakes += 1;
-                self.active[self.done - 1].content.to_mut().push(c);
-            } else {
-                // cursor is pushed +1 when KeyCode::Char is matched
-                // well in this rare case nothing happens so it needs to revert;
-                // $extra_mistakes is not incremented because the mistake itself
-                // isn't shown on the screen after exceeding the limit.
-                // the pmiss is bumped so it still contributes to the accuracy though
-                // TODO extremely low priority "controversial" decision to think through
-                self.cursor_x -= 1;
+        // stays on the letter until it's typed right, nothing piles up after the word
+        if self.strict {
+            self.miss(self.current_char);
+            if self.current_char != ' ' {
+                self.active[self.done].style = self.colors.wrong.fg();
             }
+        // adds the mistake and the end of the word
+        } else if self.current_char == ' ' {
+            self.miss(self.current_char);
+            self.extra_mistakes += 1;
+            self.active[self.done - 1].content.to_mut().push(c);
+        // jumps over what's left of the word
+        } else if c == ' ' && self.skip_on_space {
+            return self.skip_word();
         // just changes to wrong and moves on
         } else {
             self.mistakes += 1;
-            self.pmiss += 1;
+            self.miss(self.current_char);
             self.active[self.done].style = self.colors.wrong.fg();
             self.done += 1;
             return self.set_next_char_or_end();
@@ -368,56 +1027,175 @@ impl TestState<'_> {
         false
     }
 
+    // TODO this implemenation is quick and dirty
+    // and is just slapped onto existing infrastracture
+    // I don't really care for now
+    // as it works
+    fn start_clock(&mut self) {
+        if self.first {
+            self.hoarder.reset();
+            self.first = false;
+            // ofseting begining prevents very ugly graph start though not entirely.
+            // It's also only fair as the player would get the first char for free
+            // instead he gets it on the above world record pace of 240wpm
+            // which isn't at all significant for the player
+            // but helps the software
+            self.begining = Instant::now().checked_sub(INITAL_OFFSET).unwrap();
+        }
+    }
+
+    // SUBMIT LAYOUT push_line_char, submit_line
+
+    /// what's left to type of the active line
+    pub fn active_line(&self) -> String {
+        self.active[self.done.min(self.active.len())..]
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    /// types the char into the line buffer, the test starts with the first one
+    pub fn push_line_char(&mut self, c: char) {
+        self.start_clock();
+        self.line_buffer.push(c);
+    }
+
+    /// Checks the line buffer against the rest of the active line
+    /// marking every letter and moving on to the next line
+    /// returns true when the test is done
+    pub fn submit_line(&mut self) -> bool {
+        self.undo_stack.clear();
+        let target = self.active_line();
+        let mut typed = std::mem::take(&mut self.line_buffer);
+        // Enter stands in for the space the line ends with
+        if target.ends_with(' ') && !typed.ends_with(' ') {
+            typed.push(' ');
+        }
+
+        let diff = diff_line(&target, &typed);
+        let mut ended = false;
+        for d in diff.iter() {
+            match *d {
+                CharDiff::Correct(c) => {
+                    self.record_key_time();
+                    if c == ' ' {
+                        self.record_word_end();
+                    }
+                    self.active[self.done].style = self.colors.done.fg();
+                }
+                CharDiff::Wrong(expected, _) | CharDiff::Missing(expected) => {
+                    self.mistakes += 1;
+                    self.miss(expected);
+                    self.active[self.done].style = self.colors.wrong.fg();
+                }
+                // counted like extras of the last word would be
+                CharDiff::Extra(_) => {
+                    self.pmiss += 1;
+                    *self.missed_keys.entry(' ').or_insert(0) += 1;
+                    continue;
+                }
+            }
+            self.done += 1;
+            ended = self.set_next_char_or_end();
+        }
+        self.last_line = diff;
+        ended
+    }
+
     // BACKSPACE undo_char, undo_word
 
     // undo word
 
-    fn undo_space_char_and_extras(&mut self) {
-        let x = self.fetch(self.done - 1).len();
-        self.extra_mistakes -= x;
-        debug!("{}", x);
-        self.cursor_x -= x as u16 + 1;
+    /// clears extra mistakes from the blank before the space at self.done
+    /// and leaves self.done on the blank
+    fn undo_blank_and_extras(&mut self) {
+        self.extra_mistakes -= self.fetch(self.done - 1).chars().count();
         self.change(self.done - 1, String::new());
-        self.done -= 2;
-
-        self.if_mistake_deduct(self.done);
-        self.active[self.done].style = self.colors.todo.fg();
+        self.done -= 1;
         self.blanks -= 1;
     }
 
+    /// Deletes the previous word the way Ctrl + Backspace does
+    /// the space after it, extra mistakes and all of its letters,
+    /// leaving the cursor at the beginning of the word
     pub fn undo_word(&mut self) {
+        if self.done == 0 && self.up.is_empty() {
+            return;
+        }
+        self.remember_deletion();
+
         if self.done == 0 {
-            if !self.up.is_empty() {
-                self.regress_line();
-            } else {
-                return;
-            }
+            self.regress_line();
         }
 
-        if self.current_char == ' ' {
-            self.undo_space_char_and_extras();
-        } else if self.fetch(self.done - 1) == " " {
+        // the word was finished with a space
+        if self.fetch(self.done - 1) == " " {
+            self.word_ends.pop();
             self.done -= 1;
-            self.cursor_x -= 1;
             self.active[self.done].style = self.colors.todo.fg();
+        }
 
-            self.undo_space_char_and_extras();
+        // standing on the space means the blank is right behind
+        if self.fetch(self.done) == " " {
+            self.undo_blank_and_extras();
         }
 
         while self.done != 0 && self.fetch(self.done - 1) != " " {
-            self.cursor_x -= 1;
             self.done -= 1;
             self.if_mistake_deduct(self.done);
             self.active[self.done].style = self.colors.todo.fg();
         }
+
+        self.set_next_char();
+    }
+
+    fn remember_deletion(&mut self) {
+        if self.undo_stack.len() == UNDO_DEPTH {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(WordDeletion {
+            up: self.up.clone(),
+            active: self.active.clone(),
+            down: self.down.clone(),
+            backburner_len: self.backburner.len(),
+            done: self.done,
+            pdone: self.pdone,
+            length: self.length,
+            blanks: self.blanks,
+            mistakes: self.mistakes,
+            extra_mistakes: self.extra_mistakes,
+            current_char: self.current_char,
+            word_ends: self.word_ends.clone(),
+        });
+    }
+
+    /// Brings back the word deleted last, only deletions
+    /// since the last typed key can be undone
+    pub fn undo_deletion(&mut self) {
+        let deletion = match self.undo_stack.pop() {
+            Some(deletion) => deletion,
+            None => return,
+        };
+
+        self.backburner.truncate(deletion.backburner_len);
+        self.up = deletion.up;
+        self.active = deletion.active;
+        self.down = deletion.down;
+        self.done = deletion.done;
+        self.pdone = deletion.pdone;
+        self.length = deletion.length;
+        self.blanks = deletion.blanks;
+        self.mistakes = deletion.mistakes;
+        self.extra_mistakes = deletion.extra_mistakes;
+        self.current_char = deletion.current_char;
+        self.word_ends = deletion.word_ends;
     }
 
     // undo char
     //
     pub fn undo_char(&mut self) {
+        self.undo_stack.clear();
         if self.done > 0 {
-            self.cursor_x -= 1;
-
             if self.current_char == ' ' {
                 if self.active[self.done - 1].content.is_empty() {
                     self.if_mistake_deduct(self.done - 2);
@@ -435,7 +1213,14 @@ impl TestState<'_> {
                     self.extra_mistakes -= 1;
                 }
             } else {
+                // strict mode leaves the letter it's stuck on marked wrong
+                if self.strict {
+                    self.active[self.done].style = self.colors.todo.fg();
+                }
                 self.done -= 1;
+                if self.fetch(self.done) == " " {
+                    self.word_ends.pop();
+                }
                 self.if_mistake_deduct(self.done);
                 self.set_next_char();
                 self.active[self.done].style = self.colors.todo.fg();
@@ -451,6 +1236,19 @@ impl TestState<'_> {
     }
 }
 
+#[cfg(test)]
+impl<'a> TestState<'a> {
+    /// starts on the text the way quotes do, for tests that need to know every letter
+    pub(crate) fn start_on_text(&mut self, text: &str) {
+        let quote = Quote {
+            text: text.to_string(),
+            author: None,
+        };
+        let wordy = langs::prepare_quote_test(&quote, &self.colors);
+        self.start(wordy, &TypingTestConfig::default());
+    }
+}
+
 // TODO these tests save to real database XEDDD
 // plx fix
 #[cfg(test)]
@@ -466,6 +1264,23 @@ mod tests {
         'ź'
     }
 
+    #[test]
+    fn test_broken_quotes_fall_back() {
+        let config = TypingTestConfig {
+            name: "surely_no_such_quotes".to_string(),
+            variant: TestVariant::Quote,
+            ..Default::default()
+        };
+        let mut test = TestState {
+            quote_author: Some("someone".to_string()),
+            ..TestState::default()
+        };
+        test.reset(&config);
+
+        assert_eq!(test.quote_author, None);
+        assert!(test.length > 0);
+    }
+
     fn setup_new_test() -> TestState<'static> {
         let config = TypingTestConfig {
             length: 100,
@@ -478,6 +1293,56 @@ mod tests {
         test
     }
 
+    #[test]
+    fn test_calculate_wpm() {
+        let mut test = setup_new_test();
+        // frozen at exactly a minute in
+        let now = Instant::now();
+        test.begining = now - Duration::from_secs(60);
+        test.paused_at = Some(now);
+        test.first = false;
+
+        test.pdone = 310;
+        test.done = 0;
+        test.mistakes = 60;
+        test.extra_mistakes = 10;
+        assert_eq!(test.correct_chars(), 250);
+        assert_eq!(test.calculate_wpm(), 50.);
+        assert_eq!(test.calculate_raw_wpm(), 64.);
+
+        test.paused_for = Duration::from_secs(30);
+        assert_eq!(test.calculate_wpm(), 100.);
+    }
+
+    #[test]
+    fn test_rolling_wpm() {
+        let mut test = setup_new_test();
+        test.begining = Instant::now() - Duration::from_secs(20);
+        // slow first 10 seconds and 100 keys in the last 10
+        test.key_times = (0..10).map(|s| s * 1000).collect();
+        test.key_times.extend((0..100).map(|k| 10_050 + k * 99));
+
+        let rolling = test.rolling_wpm();
+        assert!((rolling - 120.).abs() < 1., "rolling wpm {}", rolling);
+    }
+
+    #[test]
+    fn test_endless_replay_is_bounded() {
+        let config = TypingTestConfig {
+            endless: true,
+            ..Default::default()
+        };
+        let mut test = TestState::default();
+        test.reset(&config);
+
+        for _ in 0..3 * MAX_REPLAY_LINES {
+            test.backburner.clear();
+            test.top_up(&config);
+        }
+        assert!(test.total_words > 3 * MAX_REPLAY_LINES);
+        assert_eq!(test.replay_lines.len(), MAX_REPLAY_LINES);
+    }
+
     #[test]
     fn test_undo_char() {
         let mut test = setup_new_test();
@@ -506,6 +1371,711 @@ mod tests {
         assert!(test.fetch(done - 1).is_empty());
     }
 
+    /// types the rest of the current word and then the given extras
+    fn finish_word_with_extras(test: &mut TestState, extras: usize) {
+        while test.current_char != ' ' {
+            test.on_char(test.current_char);
+        }
+        for _ in 0..extras {
+            test.on_char(get_wrong_char(test.current_char));
+        }
+    }
+
+    #[test]
+    fn test_undo_word() {
+        let mut test = setup_new_test();
+        let first_char = test.current_char;
+        let start_x = test.cursor_x();
+
+        // word with extra mistakes and a space
+        finish_word_with_extras(&mut test, 2);
+        assert_eq!(test.extra_mistakes, 2);
+        test.on_char(' ');
+
+        let (done, cursor_x) = (test.done, test.cursor_x());
+        let second_char = test.current_char;
+
+        // mid word with a mistake
+        test.on_char(test.current_char);
+        test.on_char(get_wrong_char(test.current_char));
+        assert_eq!(test.mistakes, 1);
+
+        test.undo_word();
+        assert_eq!(test.done, done);
+        assert_eq!(test.cursor_x(), cursor_x);
+        assert_eq!(test.mistakes, 0);
+        assert_eq!(test.current_char, second_char);
+
+        test.undo_word();
+        assert_eq!(test.done, 0);
+        assert_eq!(test.cursor_x(), start_x);
+        assert_eq!(test.blanks, 0);
+        assert_eq!(test.extra_mistakes, 0);
+        assert_eq!(test.current_char, first_char);
+        // blanks keep their mistake color
+        assert!(test.active[..done]
+            .iter()
+            .filter(|span| !span.content.is_empty())
+            .all(|span| span.style == test.colors.todo.fg()));
+        assert!(test.fetch(done - 2).is_empty());
+
+        // nothing left to delete
+        test.undo_word();
+        assert_eq!(test.done, 0);
+        assert_eq!(test.cursor_x(), start_x);
+    }
+
+    #[test]
+    fn test_undo_word_at_the_space() {
+        let mut test = setup_new_test();
+        let start_x = test.cursor_x();
+
+        finish_word_with_extras(&mut test, 3);
+        let blank = test.done - 1;
+        assert_eq!(test.fetch(blank).chars().count(), 3);
+
+        test.undo_word();
+        assert_eq!(test.done, 0);
+        assert_eq!(test.cursor_x(), start_x);
+        assert_eq!(test.blanks, 0);
+        assert_eq!(test.extra_mistakes, 0);
+        assert!(test.fetch(blank).is_empty());
+
+        // retyping ends up in the same place
+        finish_word_with_extras(&mut test, 0);
+        assert_eq!(test.done, blank + 1);
+        assert_eq!(test.blanks, 1);
+    }
+
+    #[test]
+    fn test_raw_and_net_wpm() {
+        let mut test = setup_new_test();
+
+        // first word with one uncorrected mistake and two extras
+        test.on_char(get_wrong_char(test.current_char));
+        finish_word_with_extras(&mut test, 2);
+        let word_len = test.done - 1;
+        test.on_char(' ');
+
+        // corrected mistake in the second word doesn't count
+        test.on_char(get_wrong_char(test.current_char));
+        test.undo_char();
+        test.on_char(test.current_char);
+
+        // the word, two extras, space and one char
+        assert_eq!(test.typed_chars(), word_len + 2 + 1 + 1);
+        // the word without the mistake, space and one char
+        assert_eq!(test.correct_chars(), word_len - 1 + 1 + 1);
+
+        // a minute in wpm equals chars / 5
+        test.begining = Instant::now() - Duration::from_secs(60);
+        let summary = test.summarize();
+        let raw = test.typed_chars() as f64 / 5.;
+        let net = test.correct_chars() as f64 / 5.;
+        assert!((summary.raw_wpm - raw).abs() < 0.01);
+        assert!((summary.net_wpm - net).abs() < 0.01);
+        assert!(summary.raw_wpm > summary.net_wpm);
+    }
+
+    #[test]
+    fn test_consistency() {
+        let mut test = TestState::default();
+        assert_eq!(test.consistency(), 0.);
+
+        // 5 keys every second
+        test.key_times = (0..20).map(|i| i * 200).collect();
+        assert!((test.consistency() - 100.).abs() < 1e-9);
+
+        // 6 and 2 keys: wpm of 72 and 24, mean 48, deviation 24, cv 0.5
+        test.key_times = vec![0, 100, 200, 300, 400, 500, 1000, 1500];
+        let cv: f64 = 0.5;
+        let expected = 100. * (1. - (cv + cv.powi(3) / 3. + cv.powi(5) / 5.).tanh());
+        assert!((test.consistency() - expected).abs() < 1e-9);
+
+        // an idle second ruins it
+        test.key_times = vec![0, 100, 2000, 2100];
+        assert!(test.consistency() < expected);
+    }
+
+    #[test]
+    fn test_key_times_reset() {
+        let mut test = setup_new_test();
+        test.on_char(test.current_char);
+        test.on_char(get_wrong_char(test.current_char));
+        // only correct keystrokes are timed
+        assert_eq!(test.key_times.len(), 1);
+
+        test.reset(&TypingTestConfig::default());
+        assert!(test.key_times.is_empty());
+    }
+
+    #[test]
+    fn test_pause() {
+        let mut test = setup_new_test();
+
+        // nothing to pause before the first key
+        test.toggle_pause();
+        assert!(!test.is_paused());
+
+        test.on_char(test.current_char);
+        test.toggle_pause();
+        assert!(test.is_paused());
+
+        let frozen = test.elapsed();
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(test.elapsed(), frozen);
+
+        test.toggle_pause();
+        assert!(!test.is_paused());
+        assert!(test.paused_for >= Duration::from_millis(200));
+        // the pause didn't inflate the elapsed time
+        assert!(test.elapsed() < frozen + Duration::from_millis(100));
+
+        test.reset(&TypingTestConfig::default());
+        assert_eq!(test.paused_for, Duration::ZERO);
+    }
+
+    fn remaining_text(test: &TestState) -> String {
+        let mut text: String = test.active[test.done..]
+            .iter()
+            .map(|s| s.content.as_ref())
+            .collect();
+        for line in [&test.down].into_iter().chain(test.backburner.iter().rev()) {
+            text.extend(line.iter().map(|s| s.content.as_ref()));
+        }
+        text
+    }
+
+    fn line_len(line: &[Span]) -> usize {
+        line.iter().map(|s| s.content.chars().count()).sum()
+    }
+
+    #[test]
+    fn test_rewrap() {
+        let mut test = setup_new_test();
+        for _ in 0..12 {
+            test.on_char(test.current_char);
+        }
+        // an extra mistake on the word being typed
+        while test.current_char != ' ' {
+            test.on_char(test.current_char);
+        }
+        test.on_char(get_wrong_char(' '));
+
+        let text = remaining_text(&test);
+        let typed = test.typed_chars();
+        let summary = test.summarize();
+        let current_char = test.current_char;
+
+        test.set_line_width(Some(20));
+        assert_eq!(remaining_text(&test), text);
+        assert_eq!(test.typed_chars(), typed);
+        assert_eq!(test.summarize().correct_chars, summary.correct_chars);
+        assert_eq!(test.current_char, current_char);
+        // the cursor is right behind the typed part of the word
+        let typed_on_line = line_len(&test.active[..test.done]);
+        assert_eq!(test.cursor_x() as usize, 1 + typed_on_line);
+
+        for line in [&test.active, &test.down]
+            .into_iter()
+            .chain(test.backburner.iter())
+        {
+            // with the trailing space
+            assert!(
+                line_len(line) <= 21 || !line[..line.len() - 2].iter().any(|s| s.content == " ")
+            );
+            assert_ne!(line.len(), 0);
+        }
+
+        // wider terminal brings the generated width back
+        test.set_line_width(None);
+        assert_eq!(remaining_text(&test), text);
+        assert!(line_len(&test.down) > 21);
+
+        // the test can still be finished without new mistakes
+        test.undo_char();
+        let mut bail = 0;
+        while !test.on_char(test.current_char) {
+            bail += 1;
+            assert!(bail < 10_000, "the test never ended");
+        }
+        assert_eq!(test.summarize().mistakes, 0);
+    }
+
+    fn type_until_space(test: &mut TestState) {
+        while test.current_char != ' ' {
+            test.on_char(test.current_char);
+        }
+    }
+
+    #[test]
+    fn test_extras_cap() {
+        for cap in [MAX_EXTRA_MISTAKES, 0, 5] {
+            let config = TypingTestConfig {
+                max_extra_mistakes: cap,
+                ..Default::default()
+            };
+            let mut test = TestState::default();
+            test.reset(&config);
+            type_until_space(&mut test);
+
+            let cursor_x = test.cursor_x();
+            let pmiss = test.pmiss;
+            for _ in 0..10 {
+                test.on_char('x');
+            }
+
+            assert_eq!(test.fetch(test.done - 1), "x".repeat(cap));
+            assert_eq!(test.extra_mistakes, cap);
+            assert_eq!(test.cursor_x(), cursor_x + cap as u16);
+            // ignored keys still hit the accuracy
+            assert_eq!(test.pmiss, pmiss + 10);
+            assert_eq!(test.current_char, ' ');
+        }
+    }
+
+    #[test]
+    fn test_line_width_of_one() {
+        let mut test = setup_new_test();
+        test.set_line_width(Some(1));
+        // every word ends up on its own line
+        for line in [&test.active, &test.down] {
+            assert_eq!(line.iter().filter(|s| s.content == " ").count(), 1);
+        }
+
+        let mut bail = 0;
+        while !test.on_char(test.current_char) {
+            bail += 1;
+            assert!(bail < 10_000, "the test never ended");
+        }
+        assert_eq!(test.summarize().mistakes, 0);
+    }
+
+    #[test]
+    fn test_extra_letters() {
+        let mut test = TestState::default();
+        test.start_on_text("ab cd");
+        for c in "abxy".chars() {
+            test.on_char(c);
+        }
+        // overflow letters sit in the blank after the word in their own color
+        assert_eq!(test.fetch(test.done - 1), "xy");
+        assert_eq!(
+            test.active[test.done - 1].style,
+            TestColors::default().extra.fg()
+        );
+        let summary = test.summarize();
+        assert_eq!(summary.mistakes, 2);
+        assert_eq!(summary.correct_chars, 2);
+
+        let config = TypingTestConfig {
+            extra_letters: ExtraLetters::Ignore,
+            ..Default::default()
+        };
+        test.reset(&config);
+        type_until_space(&mut test);
+        let pmiss = test.pmiss;
+        test.on_char('x');
+        assert_eq!(test.fetch(test.done - 1), "");
+        assert_eq!(test.extra_mistakes, 0);
+        assert_eq!(test.pmiss, pmiss + 1);
+    }
+
+    #[test]
+    fn test_diff_line() {
+        let diff = diff_line("the cat", "the cot");
+        let wrong: Vec<&CharDiff> = diff
+            .iter()
+            .filter(|d| !matches!(d, CharDiff::Correct(_)))
+            .collect();
+        assert_eq!(wrong, vec![&CharDiff::Wrong('a', 'o')]);
+        assert_eq!(diff.len(), 7);
+
+        assert_eq!(
+            diff_line("ab", "a"),
+            vec![CharDiff::Correct('a'), CharDiff::Missing('b')]
+        );
+        assert_eq!(
+            diff_line("a", "ab"),
+            vec![CharDiff::Correct('a'), CharDiff::Extra('b')]
+        );
+    }
+
+    #[test]
+    fn test_submit_line() {
+        let mut test = TestState::default();
+        test.start_on_text("the cat sat");
+        for c in "the cot sat".chars() {
+            test.push_line_char(c);
+        }
+        assert!(!test.first);
+        assert!(test.submit_line());
+        assert!(test.line_buffer.is_empty());
+        assert_eq!(test.summarize().mistakes, 1);
+        assert_eq!(test.missed_keys.get(&'a'), Some(&1));
+        // the letters are marked as they would be typed one by one
+        let wrong = TestColors::default().wrong.fg();
+        let marked: String = test
+            .up
+            .iter()
+            .filter(|s| s.style == wrong)
+            .map(|s| s.content.as_ref())
+            .collect();
+        assert_eq!(marked, "a");
+    }
+
+    /// columns the cursor should be at going by what's on the active line
+    fn rendered_cursor(test: &TestState) -> u16 {
+        let typed: String = test.active[..test.done]
+            .iter()
+            .map(|s| s.content.as_ref())
+            .collect();
+        1 + typed.width() as u16
+    }
+
+    #[test]
+    fn test_wpm_per_word() {
+        // a quick 4 letter word with its space, then a slow 2 letter one ending the test
+        let key_times = [100, 200, 300, 400, 500, 2500, 3500];
+        let ends = [WordEnd {
+            ms: 500,
+            skipped: false,
+        }];
+        let wpms = wpm_per_word(&key_times, &ends);
+        assert_eq!(wpms.len(), 2);
+        assert_eq!(wpms[0].round(), 120.);
+        assert_eq!(wpms[1].round(), 8.);
+
+        assert!(wpm_per_word(&[], &[]).is_empty());
+        let skipped = [WordEnd {
+            ms: 300,
+            skipped: true,
+        }];
+        let wpms = wpm_per_word(&[300], &skipped);
+        assert_eq!(wpms.len(), 1);
+        assert!(wpms[0].is_nan());
+    }
+
+    #[test]
+    fn test_word_ends() {
+        let mut test = setup_new_test();
+        finish_word_with_extras(&mut test, 0);
+        test.on_char(' ');
+        assert_eq!(test.word_ends.len(), 1);
+
+        // backspacing over the space takes the word end back
+        test.undo_char();
+        assert!(test.word_ends.is_empty());
+        test.on_char(' ');
+
+        test.skip_on_space = true;
+        test.on_char(test.current_char);
+        test.on_char(' ');
+        assert_eq!(test.word_ends.len(), 2);
+        assert!(!test.word_ends[0].skipped);
+        assert!(test.word_ends[1].skipped);
+        assert!(test.summarize().wpm_per_word[1].is_nan());
+
+        test.undo_word();
+        assert_eq!(test.word_ends.len(), 1);
+    }
+
+    #[test]
+    fn test_cursor_with_extras_and_backspaces() {
+        let mut test = setup_new_test();
+        let start = (test.done, test.cursor_x());
+
+        for round in 0..4 {
+            finish_word_with_extras(&mut test, 1 + round % 3);
+            assert_eq!(test.cursor_x(), rendered_cursor(&test));
+            for _ in 0..round {
+                test.undo_char();
+                assert_eq!(test.cursor_x(), rendered_cursor(&test));
+            }
+            test.on_char(get_wrong_char(test.current_char));
+            test.on_char(' ');
+            test.undo_char();
+            test.undo_char();
+            assert_eq!(test.cursor_x(), rendered_cursor(&test));
+            test.on_char(' ');
+        }
+
+        // backing all the way out lands right where the test started
+        while test.done > 0 {
+            test.undo_char();
+            assert_eq!(test.cursor_x(), rendered_cursor(&test));
+        }
+        assert_eq!((test.done, test.cursor_x()), start);
+        assert_eq!(test.extra_mistakes, 0);
+        assert_eq!(test.mistakes, 0);
+    }
+
+    #[test]
+    fn test_cursor_over_wide_chars() {
+        let quote = Quote {
+            text: "漢字 かな 漢字".to_string(),
+            author: None,
+        };
+        let mut test = TestState::default();
+        test.start(
+            langs::prepare_quote_test(&quote, &test.colors),
+            &TypingTestConfig::default(),
+        );
+
+        // each wide char moves the cursor by two columns
+        test.on_char('漢');
+        assert_eq!(test.cursor_x(), 3);
+        test.on_char(get_wrong_char(test.current_char));
+        assert_eq!(test.cursor_x(), 5);
+        test.on_char('語');
+        assert_eq!(test.cursor_x(), 7);
+        test.on_char('x');
+        assert_eq!(test.cursor_x(), 8);
+
+        test.undo_char();
+        assert_eq!(test.cursor_x(), 7);
+        test.undo_char();
+        assert_eq!(test.cursor_x(), 5);
+        test.undo_word();
+        assert_eq!(test.cursor_x(), 1);
+
+        for c in "漢字 か".chars() {
+            test.on_char(c);
+        }
+        assert_eq!(test.cursor_x() as usize, 1 + "漢字 か".width());
+    }
+
+    fn start_on_words(test: &mut TestState, text: &str, skip_on_space: bool) {
+        let quote = Quote {
+            text: text.to_string(),
+            author: None,
+        };
+        let cfg = TypingTestConfig {
+            skip_on_space,
+            ..Default::default()
+        };
+        test.start(langs::prepare_quote_test(&quote, &test.colors), &cfg);
+    }
+
+    #[test]
+    fn test_must_finish_word() {
+        let mut test = TestState::default();
+        start_on_words(&mut test, "abc de", false);
+
+        test.on_char('a');
+        test.on_char(' ');
+        // the space is just a wrong letter
+        assert_eq!(test.current_char, 'c');
+        assert_eq!(test.mistakes, 1);
+        for c in "c de".chars() {
+            test.on_char(c);
+        }
+        assert_eq!(test.summarize().correct_chars, 5);
+    }
+
+    #[test]
+    fn test_stop_on_space() {
+        let mut test = TestState::default();
+        start_on_words(&mut test, "abc de fgh", true);
+
+        test.on_char('a');
+        test.on_char(' ');
+        assert_eq!(test.current_char, 'd');
+        assert_eq!(test.mistakes, 2);
+        assert_eq!(test.pmiss, 2);
+
+        // backspacing into the skipped word works as usual
+        test.undo_char();
+        assert_eq!(test.current_char, ' ');
+        test.undo_char();
+        assert_eq!(test.current_char, 'c');
+        assert_eq!(test.mistakes, 1);
+        test.on_char('c');
+        test.on_char(' ');
+        assert_eq!(test.current_char, 'd');
+        assert_eq!(test.mistakes, 1);
+
+        // skipping the last word ends the test
+        for c in "de f".chars() {
+            assert!(!test.on_char(c));
+        }
+        assert!(test.on_char(' '));
+        assert_eq!(test.summarize().correct_chars, 7);
+    }
+
+    #[test]
+    fn test_strict_waits_for_the_right_letter() {
+        let mut test = TestState::default();
+        let quote = Quote {
+            text: String::from("abc de"),
+            author: None,
+        };
+        let cfg = TypingTestConfig {
+            strict: true,
+            ..Default::default()
+        };
+        test.start(langs::prepare_quote_test(&quote, &test.colors), &cfg);
+
+        test.on_char('a');
+        test.on_char('x');
+        assert_eq!(test.done, 1);
+        assert_eq!(test.current_char, 'b');
+        assert_eq!(test.active[1].style, test.colors.wrong.fg());
+
+        // neither space nor another wrong key gets past it
+        test.on_char(' ');
+        test.on_char('c');
+        assert_eq!(test.done, 1);
+        assert_eq!(test.pmiss, 3);
+
+        // backspace takes the mark off on the way back
+        test.undo_char();
+        assert_eq!(test.active[1].style, test.colors.todo.fg());
+        assert_eq!(test.current_char, 'a');
+
+        for c in "abc".chars() {
+            test.on_char(c);
+        }
+        // nothing piles up after the word either
+        test.on_char('x');
+        assert_eq!(test.extra_mistakes, 0);
+        assert_eq!(test.current_char, ' ');
+
+        for c in " d".chars() {
+            assert!(!test.on_char(c));
+        }
+        assert!(test.on_char('e'));
+        let summary = test.summarize();
+        assert_eq!(summary.mistakes, 0);
+        assert_eq!(summary.correct_chars, 6);
+        assert!(summary.acc < 100.);
+    }
+
+    #[test]
+    fn test_missed_keys() {
+        let mut test = TestState::default();
+        start_on_words(&mut test, "abc de fgh", true);
+
+        // an extra after the word goes to the space
+        for c in "axx@".chars() {
+            test.on_char(c);
+        }
+        assert_eq!(test.problem_keys(5), vec![(' ', 1), ('b', 1), ('c', 1)]);
+        test.undo_char();
+        test.undo_char();
+        test.on_char('c');
+        for c in "xx d ".chars() {
+            test.on_char(c);
+        }
+        // backspacing doesn't take misses back, skipping the word misses the rest of it
+        assert_eq!(
+            test.problem_keys(5),
+            vec![(' ', 3), ('b', 1), ('c', 1), ('e', 1)]
+        );
+        assert_eq!(test.problem_keys(1), vec![(' ', 3)]);
+        assert_eq!(test.pmiss, 6);
+
+        test.restart(&TypingTestConfig::default());
+        assert!(test.problem_keys(5).is_empty());
+    }
+
+    #[test]
+    fn test_missed_words() {
+        let mut test = TestState::default();
+        start_on_words(&mut test, "Abc, de 42 fgh", true);
+
+        // punctuation and capitals are left out, extras count toward the word
+        for c in "xbc,x d".chars() {
+            test.on_char(c);
+        }
+        assert_eq!(test.missed_words.get("abc"), Some(&2));
+        assert_eq!(test.missed_words.get("de"), None);
+
+        // numbers aren't words to practice
+        for c in "e 4x fxh".chars() {
+            test.on_char(c);
+        }
+        assert_eq!(test.missed_words.len(), 2);
+        assert_eq!(test.missed_words.get("fgh"), Some(&1));
+
+        test.restart(&TypingTestConfig::default());
+        assert!(test.missed_words.is_empty());
+    }
+
+    #[test]
+    fn test_live_stats() {
+        let mut test = setup_new_test();
+        assert_eq!(test.live_stats(), (0., 100.));
+
+        test.on_char(test.current_char);
+        // no time passed at all yet
+        test.paused_at = Some(test.begining);
+        assert_eq!(test.calculate_wpm(), 0.);
+        assert_eq!(test.calculate_raw_wpm(), 0.);
+        test.paused_at = None;
+
+        test.on_char(get_wrong_char(test.current_char));
+        let (wpm, acc) = test.live_stats();
+        assert!(wpm.is_finite() && wpm > 0.);
+        assert_eq!(acc, 50.);
+    }
+
+    /// everything a word deletion and its undo touch
+    fn typing_state<'a>(test: &TestState<'a>) -> impl PartialEq + std::fmt::Debug + 'a {
+        (
+            (test.up.clone(), test.active.clone(), test.down.clone()),
+            test.backburner.clone(),
+            (test.done, test.pdone, test.length, test.blanks),
+            (test.mistakes, test.extra_mistakes, test.pmiss),
+            (test.current_char, test.cursor_x()),
+            test.word_ends.clone(),
+        )
+    }
+
+    #[test]
+    fn test_undo_deletion() {
+        let mut test = setup_new_test();
+        finish_word_with_extras(&mut test, 2);
+        test.on_char(' ');
+        test.on_char(get_wrong_char(test.current_char));
+        test.on_char(test.current_char);
+
+        let before = typing_state(&test);
+        assert_eq!(test.word_ends.len(), 1);
+        test.undo_word();
+        test.undo_word();
+        assert_eq!(test.done, 0);
+        assert!(test.word_ends.is_empty());
+
+        test.undo_deletion();
+        test.undo_deletion();
+        assert_eq!(typing_state(&test), before);
+        // nothing more to undo
+        test.undo_deletion();
+        assert_eq!(typing_state(&test), before);
+
+        // typing on forgets the deletions
+        test.undo_word();
+        test.on_char(test.current_char);
+        let typed = typing_state(&test);
+        test.undo_deletion();
+        assert_eq!(typing_state(&test), typed);
+    }
+
+    #[test]
+    fn test_undo_deletion_on_previous_line() {
+        let mut test = setup_new_test();
+        while test.up.is_empty() {
+            test.on_char(test.current_char);
+        }
+
+        let before = typing_state(&test);
+        test.undo_word();
+        assert!(test.up.is_empty());
+        test.undo_deletion();
+        assert_eq!(typing_state(&test), before);
+    }
+
     #[test]
     fn test_undo_previous_line() {
         let mut test = setup_new_test();
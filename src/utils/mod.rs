@@ -1,5 +1,6 @@
 pub mod randorst;
 pub mod termprep;
+use crate::storage::StorageError;
 use bytecount;
 use std::fs::File;
 use std::io::{self, BufRead};
@@ -35,6 +36,9 @@ impl<T> StatefulList<T> {
     }
 
     pub fn next(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i >= self.items.len() - 1 {
@@ -49,6 +53,9 @@ impl<T> StatefulList<T> {
     }
 
     pub fn previous(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i == 0 {
@@ -72,12 +79,40 @@ macro_rules! vec_of_strings {
     ($($x:expr),*) => (vec![$($x.to_string()),*]);
 }
 
-pub fn count_lines_from_path(filepath: impl AsRef<Path>) -> anyhow::Result<usize> {
-    let file = File::open(filepath)?;
-    count_lines(file)
+pub fn count_lines_from_path(filepath: impl AsRef<Path>) -> Result<usize, StorageError> {
+    let path = filepath.as_ref();
+    File::open(path)
+        .and_then(count_lines)
+        .map_err(|e| StorageError::from_io(path, e))
+}
+
+/// Number of words in a word list, blank lines don't count
+/// so they can't push word_pool out of bounds
+///
+/// ```
+/// use smokey::utils::count_words;
+/// let file: &[u8] = b"one\ntwo\n\nthree\n\n  \n";
+/// assert_eq!(count_words(file).unwrap(), 3);
+/// ```
+pub fn count_words<R: io::Read>(file: R) -> io::Result<usize> {
+    let mut count: usize = 0;
+    for line in io::BufReader::new(file).split(b'\n') {
+        if !line?.iter().all(u8::is_ascii_whitespace) {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+pub fn count_words_from_path(filepath: impl AsRef<Path>) -> Result<usize, StorageError> {
+    let path = filepath.as_ref();
+    File::open(path)
+        .and_then(count_words)
+        .map_err(|e| StorageError::from_io(path, e))
 }
 
 /// Expects a file and returns number of lines
+/// a last line without a newline counts, blank lines at the end don't
 ///
 /// ```
 /// use smokey::utils::count_lines;
@@ -90,18 +125,60 @@ pub fn count_lines_from_path(filepath: impl AsRef<Path>) -> anyhow::Result<usize
 /// https://github.com/Freaky/cw
 /// a fast wc clone in Rust
 /// great stuff I use it as well
-pub fn count_lines<R: io::Read>(file: R) -> anyhow::Result<usize> {
+pub fn count_lines<R: io::Read>(file: R) -> io::Result<usize> {
     let mut reader = io::BufReader::new(file);
     let mut count: usize = 0;
+    // lines up to the last one with anything on it
+    let mut filled: usize = 0;
 
     loop {
         let buffer = reader.fill_buf()?;
         if buffer.is_empty() {
             break;
         }
+        if let Some(last) = buffer.iter().rposition(|b| !b.is_ascii_whitespace()) {
+            filled = count + bytecount::count(&buffer[..last], b'\n') + 1;
+        }
         count += bytecount::count(buffer, b'\n');
         let buflen = buffer.len();
         reader.consume(buflen);
     }
-    Ok(count)
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_lines() {
+        assert_eq!(count_lines(&b"one\ntwo\n"[..]).unwrap(), 2);
+        // the last word has no newline after it
+        assert_eq!(count_lines(&b"one\ntwo"[..]).unwrap(), 2);
+        assert_eq!(count_lines(&b"one\ntwo\n\n \n\n"[..]).unwrap(), 2);
+        // blank lines in between still count
+        assert_eq!(count_lines(&b"one\n\ntwo"[..]).unwrap(), 3);
+        assert_eq!(count_lines(&b""[..]).unwrap(), 0);
+        assert_eq!(count_lines(&b"\n\n"[..]).unwrap(), 0);
+
+        // lines spread over more than one read of the buffer
+        let long = "word\n".repeat(5_000) + "last";
+        assert_eq!(count_lines(long.as_bytes()).unwrap(), 5_001);
+        assert_eq!(count_lines((long + "\n\n").as_bytes()).unwrap(), 5_001);
+    }
+
+    #[test]
+    fn test_count_lines_from_path() {
+        let dir = std::env::temp_dir().join("smokey_test_count_lines_from_path");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for (text, lines) in [("a\nb\n", 2), ("a\nb", 2), ("a\nb\n\n\n", 2)] {
+            let path = dir.join("words");
+            std::fs::write(&path, text).unwrap();
+            assert_eq!(count_lines_from_path(&path).unwrap(), lines, "{:?}", text);
+        }
+        assert!(count_lines_from_path(dir.join("missing")).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
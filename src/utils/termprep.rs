@@ -17,7 +17,8 @@ use std::panic;
 use std::process;
 
 use crossterm::{
-    cursor, execute,
+    cursor::{self, CursorShape},
+    execute,
     style::Print,
     terminal::{
         disable_raw_mode, enable_raw_mode, is_raw_mode_enabled, Clear, ClearType,
@@ -59,10 +60,17 @@ fn init_terminal() {
     enable_raw_mode().expect("Unable to enter raw mode.");
 }
 
+/// shape of the terminal cursor until shutdown
+pub fn set_cursor_shape(shape: CursorShape) {
+    execute!(stdout(), cursor::SetCursorShape(shape)).expect("setting cursor shape");
+}
+
 /// leaves the alt screen and leaves terminal as it was before
 /// launching the program
 fn cleanup_terminal() {
     let mut sout = stdout();
+    // back to the shape the terminal uses by default
+    execute!(sout, Print("\x1b[0 q")).expect("resetting cursor shape");
     execute!(sout, Clear(ClearType::All)).expect("Unable to clear screen.");
     execute!(sout, LeaveAlternateScreen).expect("Unable to leave alternate screen.");
     disable_raw_mode().expect("Unable to disable raw mode");